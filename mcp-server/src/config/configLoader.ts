@@ -5,6 +5,7 @@
 
 import { readFileSync, existsSync } from 'fs';
 import { join, dirname } from 'path';
+import { tmpdir } from 'os';
 import { fileURLToPath } from 'url';
 
 const __filename = fileURLToPath(import.meta.url);
@@ -163,6 +164,21 @@ export class ConfigLoader {
     return this.getCurrentEnvironment().juanNotePort;
   }
 
+  // Token for the Juan Note REST API. Falls back to the token the app writes
+  // next to its database for MCP clients.
+  getJuanNoteApiToken(): string | undefined {
+    const fromEnv = this.getEnvOverride('JUAN_NOTE_API_TOKEN', '');
+    if (fromEnv) {
+      return fromEnv;
+    }
+
+    try {
+      return readFileSync(join(tmpdir(), 'juan-note-mcp-token'), 'utf-8').trim() || undefined;
+    } catch {
+      return undefined;
+    }
+  }
+
   getMaxBulkOperationSize(): number {
     return this.config.security.maxBulkOperationSize;
   }
@@ -62,6 +62,11 @@ export class NoteManager {
       }
     };
 
+    const token = configLoader.getJuanNoteApiToken();
+    if (token) {
+      options.headers['Authorization'] = `Bearer ${token}`;
+    }
+
     if (data) {
       options.body = JSON.stringify(data);
     }
@@ -0,0 +1,123 @@
+//! Concurrent note reads, as the REST handlers make them, while a writer keeps
+//! a write transaction open. Compares the reader pool with the single shared
+//! connection every request used to lock. Run with
+//! `cargo bench --bench concurrent_reads`.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use juan_note_lib::database::{run_migrations, Database};
+use juan_note_lib::repository::{NoteRepository, SqliteRepository};
+use rusqlite::Connection;
+
+const NOTES: usize = 500;
+const CONCURRENT_READS: usize = 8;
+
+// How long each write transaction stays open, like a bulk update would
+const WRITE_DURATION: Duration = Duration::from_millis(20);
+
+fn database_path() -> PathBuf {
+    let path = std::env::temp_dir().join("juan-note-bench.db");
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    path
+}
+
+fn seed(conn: &Connection) {
+    for i in 0..NOTES {
+        conn.execute(
+            "INSERT INTO notes (title, content, labels) VALUES (?, ?, '[\"bench\"]')",
+            [format!("Note {}", i), format!("Content of note {}", i)],
+        )
+        .unwrap();
+    }
+}
+
+// Keep a write transaction open most of the time until `stop` is set
+fn spawn_writer(
+    write: impl Fn(&dyn Fn(&Connection)) + Send + 'static,
+    stop: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            write(&|conn| {
+                conn.execute_batch("BEGIN IMMEDIATE; UPDATE notes SET priority = (priority + 1) % 6;")
+                    .unwrap();
+                thread::sleep(WRITE_DURATION);
+                conn.execute_batch("COMMIT").unwrap();
+            });
+            thread::sleep(Duration::from_millis(1));
+        }
+    })
+}
+
+// Run CONCURRENT_READS note listings at once and wait for all of them
+fn concurrent_reads(read: &(dyn Fn() + Sync)) {
+    thread::scope(|scope| {
+        for _ in 0..CONCURRENT_READS {
+            scope.spawn(read);
+        }
+    });
+}
+
+fn pooled(c: &mut Criterion, path: &Path) {
+    let database = Arc::new(Database::open(path).unwrap());
+    run_migrations(&mut database.writer().unwrap()).unwrap();
+    seed(&database.writer().unwrap());
+
+    let read = || {
+        let conn = database.reader().unwrap();
+        assert_eq!(SqliteRepository::new(&conn).list_notes().unwrap().len(), NOTES);
+    };
+    c.bench_function("pool/idle", |b| b.iter(|| concurrent_reads(&read)));
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let database = database.clone();
+        spawn_writer(move |write| write(&database.writer().unwrap()), stop.clone())
+    };
+    c.bench_function("pool/during_writes", |b| b.iter(|| concurrent_reads(&read)));
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+}
+
+fn shared_connection(c: &mut Criterion, path: &Path) {
+    let mut conn = Connection::open(path).unwrap();
+    conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+    run_migrations(&mut conn).unwrap();
+    seed(&conn);
+    let conn = Arc::new(Mutex::new(conn));
+
+    let read = || {
+        let conn = conn.lock().unwrap();
+        assert_eq!(SqliteRepository::new(&conn).list_notes().unwrap().len(), NOTES);
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let conn = conn.clone();
+        spawn_writer(move |write| write(&conn.lock().unwrap()), stop.clone())
+    };
+    c.bench_function("shared_connection/during_writes", |b| {
+        b.iter(|| concurrent_reads(&read))
+    });
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+}
+
+fn benches(c: &mut Criterion) {
+    pooled(c, &database_path());
+    shared_connection(c, &database_path());
+}
+
+criterion_group! {
+    name = concurrent;
+    config = Criterion::default().sample_size(20);
+    targets = benches
+}
+criterion_main!(concurrent);
@@ -0,0 +1,224 @@
+use axum::{
+    extract::Request,
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::commands::token_commands::authenticate_api_token;
+use crate::models::TokenScope;
+
+// Endpoints that answer without a token: API documentation, health check and OpenAPI document
+pub(crate) const PUBLIC_PATHS: &[&str] = &["/", "/health", "/openapi.json"];
+
+// Query parameter accepted instead of the Authorization header, for clients that
+// can't set headers (EventSource, WebSocket in browsers, calendar subscriptions)
+const ACCESS_TOKEN_PARAM: &str = "access_token";
+
+/// Require a valid API token with a scope that covers the request.
+/// The authenticated `ApiToken` is stored in the request extensions.
+pub async fn require_api_token(mut request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+
+    // CORS preflight requests never carry credentials
+    if request.method() == Method::OPTIONS || PUBLIC_PATHS.contains(&path) {
+        return next.run(request).await;
+    }
+
+    let required = required_scope(request.method(), path);
+
+    let Some(presented) = extract_token(&request) else {
+        return unauthorized("Missing API token");
+    };
+
+    let authenticated =
+        tokio::task::spawn_blocking(move || authenticate_api_token(&presented)).await;
+    let token = match authenticated {
+        Ok(Ok(Some(token))) => token,
+        Ok(Ok(None)) => return unauthorized("Invalid or revoked API token"),
+        Ok(Err(e)) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+        Err(e) => {
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to check API token: {}", e),
+            )
+        }
+    };
+
+    if !token.has_scope(required) {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            &format!(
+                "API token '{}' lacks the '{}' scope",
+                token.name,
+                scope_name(required)
+            ),
+        );
+    }
+
+    request.extensions_mut().insert(token);
+    next.run(request).await
+}
+
+pub fn required_scope(method: &Method, path: &str) -> TokenScope {
+    if path == "/tokens" || path.starts_with("/tokens/") {
+        return TokenScope::Admin;
+    }
+
+    // Search is a POST but doesn't change anything, and MCP checks the
+    // write scope itself for the tools that change data
+    if path == "/notes/search" || path == "/mcp" {
+        return TokenScope::Read;
+    }
+
+    match *method {
+        Method::GET | Method::HEAD => TokenScope::Read,
+        _ => TokenScope::Write,
+    }
+}
+
+pub(crate) fn extract_token(request: &Request) -> Option<String> {
+    let from_header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            let (scheme, token) = value.split_once(' ')?;
+            scheme
+                .eq_ignore_ascii_case("bearer")
+                .then(|| token.trim().to_string())
+        });
+
+    if from_header.is_some() {
+        return from_header;
+    }
+
+    if request.method() != Method::GET {
+        return None;
+    }
+
+    request.uri().query().and_then(|query| {
+        query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key == ACCESS_TOKEN_PARAM && !value.is_empty()).then(|| value.to_string())
+        })
+    })
+}
+
+fn scope_name(scope: TokenScope) -> &'static str {
+    match scope {
+        TokenScope::Read => "read",
+        TokenScope::Write => "write",
+        TokenScope::Admin => "admin",
+    }
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = error_response(StatusCode::UNAUTHORIZED, message);
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        header::HeaderValue::from_static("Bearer"),
+    );
+    response
+}
+
+pub(crate) fn error_response(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "success": false,
+            "data": null,
+            "error": message
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(method: Method, uri: &str, authorization: Option<&str>) -> Request {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn token_management_needs_the_admin_scope() {
+        assert_eq!(required_scope(&Method::GET, "/tokens"), TokenScope::Admin);
+        assert_eq!(
+            required_scope(&Method::DELETE, "/tokens/3"),
+            TokenScope::Admin
+        );
+        assert_eq!(required_scope(&Method::GET, "/tokensets"), TokenScope::Read);
+    }
+
+    #[test]
+    fn search_and_mcp_need_only_the_read_scope() {
+        assert_eq!(
+            required_scope(&Method::POST, "/notes/search"),
+            TokenScope::Read
+        );
+        assert_eq!(required_scope(&Method::POST, "/mcp"), TokenScope::Read);
+    }
+
+    #[test]
+    fn other_requests_need_read_or_write_by_method() {
+        assert_eq!(required_scope(&Method::GET, "/notes"), TokenScope::Read);
+        assert_eq!(required_scope(&Method::HEAD, "/notes/1"), TokenScope::Read);
+        assert_eq!(required_scope(&Method::POST, "/notes"), TokenScope::Write);
+        assert_eq!(required_scope(&Method::PUT, "/notes/1"), TokenScope::Write);
+        assert_eq!(
+            required_scope(&Method::DELETE, "/notes/1"),
+            TokenScope::Write
+        );
+    }
+
+    #[test]
+    fn extracts_a_bearer_token_from_the_authorization_header() {
+        let token = extract_token(&request(Method::POST, "/notes", Some("Bearer  abc ")));
+        assert_eq!(token.as_deref(), Some("abc"));
+
+        let token = extract_token(&request(Method::GET, "/notes", Some("bearer abc")));
+        assert_eq!(token.as_deref(), Some("abc"));
+
+        assert_eq!(
+            extract_token(&request(Method::GET, "/notes", Some("Basic abc"))),
+            None
+        );
+        assert_eq!(
+            extract_token(&request(Method::GET, "/notes", Some("Bearer"))),
+            None
+        );
+    }
+
+    #[test]
+    fn accepts_the_access_token_parameter_only_on_get() {
+        let uri = "/events?since=4&access_token=abc";
+        assert_eq!(
+            extract_token(&request(Method::GET, uri, None)).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(extract_token(&request(Method::POST, uri, None)), None);
+        assert_eq!(
+            extract_token(&request(Method::GET, "/events?access_token=", None)),
+            None
+        );
+    }
+
+    #[test]
+    fn prefers_the_header_over_the_query_parameter() {
+        let request = request(
+            Method::GET,
+            "/events?access_token=query",
+            Some("Bearer header"),
+        );
+        assert_eq!(extract_token(&request).as_deref(), Some("header"));
+    }
+}
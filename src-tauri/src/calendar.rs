@@ -0,0 +1,272 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::{CalendarQuery, Note, State};
+
+const PRODUCT_ID: &str = "-//Juan Note//Deadlines//EN";
+
+// RFC 5545 recommends folding content lines longer than 75 octets
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Keep only the notes that have a deadline and match the feed filters.
+/// Fails when the state filter doesn't name an existing state.
+pub fn filter_notes<'a>(
+    notes: &'a [Note],
+    states: &[State],
+    query: &CalendarQuery,
+) -> Result<Vec<&'a Note>, String> {
+    // Resolve the state filter, which may be given either as an id or as a name
+    let state_id = match &query.state {
+        Some(state) => {
+            let id = state.parse::<i64>().ok();
+            let found = states
+                .iter()
+                .find(|s| s.id.is_some() && (s.id == id || s.name.eq_ignore_ascii_case(state)))
+                .ok_or_else(|| format!("Unknown state '{}'", state))?;
+            Some(found.id)
+        }
+        None => None,
+    };
+
+    Ok(notes
+        .iter()
+        .filter(|note| note.deadline.is_some())
+        .filter(|note| match &query.section {
+            Some(section) => note.section == *section,
+            None => true,
+        })
+        .filter(|note| match &query.label {
+            Some(label) => note.labels.iter().any(|l| l.eq_ignore_ascii_case(label)),
+            None => true,
+        })
+        .filter(|note| match state_id {
+            Some(state_id) => note.state_id == state_id,
+            None => true,
+        })
+        .collect())
+}
+
+/// Render notes as an iCalendar document with one VTODO (or VEVENT) per note.
+pub fn build_calendar(notes: &[&Note], query: &CalendarQuery) -> String {
+    let as_events = matches!(query.component.as_deref(), Some("event") | Some("vevent"));
+    let now = Utc::now();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:Juan Note".to_string(),
+    ];
+
+    for note in notes {
+        let (Some(id), Some(deadline)) = (note.id, note.deadline) else {
+            continue;
+        };
+
+        let component = if as_events { "VEVENT" } else { "VTODO" };
+        lines.push(format!("BEGIN:{}", component));
+        lines.push(format!("UID:note-{}@juan-note", id));
+        lines.push(format!(
+            "DTSTAMP:{}",
+            format_timestamp(note.updated_at.unwrap_or(now))
+        ));
+        if let Some(created_at) = note.created_at {
+            lines.push(format!("CREATED:{}", format_timestamp(created_at)));
+        }
+        if let Some(updated_at) = note.updated_at {
+            lines.push(format!("LAST-MODIFIED:{}", format_timestamp(updated_at)));
+        }
+        // VEVENT has no completed status, so done events say it in their summary
+        if as_events && note.done {
+            lines.push(format!("SUMMARY:[done] {}", escape_text(&note.title)));
+        } else {
+            lines.push(format!("SUMMARY:{}", escape_text(&note.title)));
+        }
+        if !note.content.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&note.content)));
+        }
+        if !note.labels.is_empty() {
+            let categories: Vec<String> = note.labels.iter().map(|l| escape_text(l)).collect();
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        if let Some(priority) = map_priority(note.priority) {
+            lines.push(format!("PRIORITY:{}", priority));
+        }
+
+        if as_events {
+            // Deadlines are points in time, so events start and end at the deadline
+            lines.push(format!("DTSTART:{}", format_timestamp(deadline)));
+            lines.push(format!("DTEND:{}", format_timestamp(deadline)));
+            lines.push("TRANSP:TRANSPARENT".to_string());
+            lines.push("STATUS:CONFIRMED".to_string());
+        } else {
+            lines.push(format!("DUE:{}", format_timestamp(deadline)));
+            if note.done {
+                lines.push("STATUS:COMPLETED".to_string());
+                lines.push("PERCENT-COMPLETE:100".to_string());
+                lines.push(format!(
+                    "COMPLETED:{}",
+                    format_timestamp(note.updated_at.unwrap_or(now))
+                ));
+            } else {
+                lines.push("STATUS:NEEDS-ACTION".to_string());
+            }
+        }
+
+        // Completed notes don't need to nag anyone
+        if note.reminder_minutes > 0 && !note.done {
+            // Alarms on a VTODO are relative to DUE, on a VEVENT to DTSTART
+            let related = if as_events { "START" } else { "END" };
+            lines.push("BEGIN:VALARM".to_string());
+            lines.push("ACTION:DISPLAY".to_string());
+            lines.push(format!("DESCRIPTION:{}", escape_text(&note.title)));
+            lines.push(format!(
+                "TRIGGER;RELATED={}:-PT{}M",
+                related, note.reminder_minutes
+            ));
+            lines.push("END:VALARM".to_string());
+        }
+
+        lines.push(format!("END:{}", component));
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut output = String::new();
+    for line in lines {
+        output.push_str(&fold_line(&line));
+        output.push_str("\r\n");
+    }
+    output
+}
+
+fn format_timestamp(dt: DateTime<Utc>) -> String {
+    dt.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Note priorities go from 0 (none) to 5 (highest), iCalendar uses 1 (highest) to 9 (lowest)
+fn map_priority(priority: i32) -> Option<i32> {
+    match priority {
+        p if p <= 0 => None,
+        p => Some((11 - 2 * p.min(5)).clamp(1, 9)),
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+fn fold_line(line: &str) -> String {
+    if line.len() <= MAX_LINE_OCTETS {
+        return line.to_string();
+    }
+
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut current_len = 0;
+    for ch in line.chars() {
+        // Continuation lines start with a space, which counts towards their length
+        if current_len + ch.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            current_len = 1;
+        }
+        folded.push(ch);
+        current_len += ch.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn note_due(id: i64, title: &str) -> Note {
+        let mut note = Note::new(title.to_string(), String::new());
+        note.id = Some(id);
+        note.deadline = Some(Utc.with_ymd_and_hms(2026, 3, 14, 9, 5, 0).unwrap());
+        note
+    }
+
+    fn render(notes: &[Note], component: Option<&str>) -> String {
+        let query = CalendarQuery {
+            component: component.map(str::to_string),
+            ..CalendarQuery::default()
+        };
+        build_calendar(&notes.iter().collect::<Vec<_>>(), &query)
+    }
+
+    #[test]
+    fn escapes_text_values() {
+        assert_eq!(
+            escape_text("a\\b; c, d\r\ne\nf"),
+            "a\\\\b\\; c\\, d\\ne\\nf"
+        );
+    }
+
+    #[test]
+    fn folds_long_lines_at_75_octets() {
+        assert_eq!(fold_line("SUMMARY:short"), "SUMMARY:short");
+
+        let folded = fold_line(&format!("SUMMARY:{}", "é".repeat(80)));
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        // Multi-byte characters are never split
+        let unfolded: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| if i == 0 { *line } else { &line[1..] })
+            .collect();
+        assert_eq!(unfolded, format!("SUMMARY:{}", "é".repeat(80)));
+    }
+
+    #[test]
+    fn events_start_and_end_at_the_deadline_in_utc() {
+        let calendar = render(&[note_due(1, "Dentist")], Some("event"));
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.contains("BEGIN:VEVENT\r\n"));
+        assert!(calendar.contains("DTSTART:20260314T090500Z\r\n"));
+        assert!(calendar.contains("DTEND:20260314T090500Z\r\n"));
+        assert!(calendar.contains("STATUS:CONFIRMED\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn done_notes_are_completed_without_alarms() {
+        let mut done = note_due(1, "Taxes");
+        done.done = true;
+        done.reminder_minutes = 30;
+
+        let todo = render(std::slice::from_ref(&done), None);
+        assert!(todo.contains("DUE:20260314T090500Z\r\n"));
+        assert!(todo.contains("STATUS:COMPLETED\r\n"));
+        assert!(todo.contains("PERCENT-COMPLETE:100\r\n"));
+        assert!(!todo.contains("BEGIN:VALARM"));
+
+        let event = render(&[done], Some("event"));
+        assert!(event.contains("SUMMARY:[done] Taxes\r\n"));
+        assert!(event.contains("STATUS:CONFIRMED\r\n"));
+        assert!(!event.contains("BEGIN:VALARM"));
+    }
+
+    #[test]
+    fn reminders_become_alarms_relative_to_the_deadline() {
+        let mut open = note_due(1, "Taxes");
+        open.reminder_minutes = 30;
+
+        let todo = render(std::slice::from_ref(&open), None);
+        assert!(todo.contains("STATUS:NEEDS-ACTION\r\n"));
+        assert!(todo.contains("TRIGGER;RELATED=END:-PT30M\r\n"));
+
+        let event = render(&[open], Some("event"));
+        assert!(event.contains("SUMMARY:Taxes\r\n"));
+        assert!(event.contains("TRIGGER;RELATED=START:-PT30M\r\n"));
+    }
+}
@@ -1,20 +1,13 @@
-use crate::database::{establish_connection, DbConnection};
+use crate::database;
+use crate::events;
 use crate::models::*;
+use crate::repository::{NoteRepository, SqliteRepository};
 use chrono::Utc;
 use rusqlite::Result;
-use std::sync::OnceLock;
-
-static DB_CONNECTION: OnceLock<DbConnection> = OnceLock::new();
-
-fn get_db_connection() -> &'static DbConnection {
-    DB_CONNECTION
-        .get_or_init(|| establish_connection().expect("Failed to establish database connection"))
-}
 
 #[tauri::command]
 pub fn bulk_delete_notes(request: BulkDeleteRequest) -> Result<BulkOperationResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
     let mut successful_count = 0;
     let mut failed_count = 0;
@@ -25,6 +18,7 @@ pub fn bulk_delete_notes(request: BulkDeleteRequest) -> Result<BulkOperationResp
             Ok(rows_affected) => {
                 if rows_affected > 0 {
                     successful_count += 1;
+                    events::publish(ChangeEntity::Note, note_id, ChangeOperation::Deleted, None);
                 } else {
                     failed_count += 1;
                     errors.push(format!("Note {} not found", note_id));
@@ -54,8 +48,7 @@ pub fn bulk_delete_notes(request: BulkDeleteRequest) -> Result<BulkOperationResp
 pub fn bulk_update_notes_priority(
     request: BulkUpdatePriorityRequest,
 ) -> Result<BulkOperationResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
     let mut successful_count = 0;
     let mut failed_count = 0;
@@ -70,6 +63,7 @@ pub fn bulk_update_notes_priority(
             Ok(rows_affected) => {
                 if rows_affected > 0 {
                     successful_count += 1;
+                    publish_note_updated(note_id, &conn);
                 } else {
                     failed_count += 1;
                     errors.push(format!("Note {} not found", note_id));
@@ -99,8 +93,7 @@ pub fn bulk_update_notes_priority(
 pub fn bulk_update_notes_done(
     request: BulkUpdateDoneRequest,
 ) -> Result<BulkOperationResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
     let mut successful_count = 0;
     let mut failed_count = 0;
@@ -115,6 +108,7 @@ pub fn bulk_update_notes_done(
             Ok(rows_affected) => {
                 if rows_affected > 0 {
                     successful_count += 1;
+                    publish_note_updated(note_id, &conn);
                 } else {
                     failed_count += 1;
                     errors.push(format!("Note {} not found", note_id));
@@ -144,8 +138,7 @@ pub fn bulk_update_notes_done(
 pub fn bulk_update_notes_state(
     request: BulkUpdateStateRequest,
 ) -> Result<BulkOperationResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
     let mut successful_count = 0;
     let mut failed_count = 0;
@@ -153,6 +146,12 @@ pub fn bulk_update_notes_state(
     let now = Utc::now().timestamp();
 
     for &note_id in &request.note_ids {
+        let previous_state_id = SqliteRepository::new(&conn)
+            .get_note(note_id)
+            .ok()
+            .flatten()
+            .and_then(|note| note.state_id);
+
         match conn.execute(
             "UPDATE notes SET state_id = ?, updated_at = ? WHERE id = ?",
             rusqlite::params![request.state_id, now, note_id],
@@ -160,6 +159,10 @@ pub fn bulk_update_notes_state(
             Ok(rows_affected) => {
                 if rows_affected > 0 {
                     successful_count += 1;
+                    publish_note_updated(note_id, &conn);
+                    if previous_state_id != Some(request.state_id) {
+                        publish_note_state_changed(note_id, &conn);
+                    }
                 } else {
                     failed_count += 1;
                     errors.push(format!("Note {} not found", note_id));
@@ -189,8 +192,7 @@ pub fn bulk_update_notes_state(
 pub fn bulk_update_notes_order(
     request: BulkUpdateOrderRequest,
 ) -> Result<BulkOperationResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
     let mut successful_count = 0;
     let mut failed_count = 0;
@@ -208,6 +210,7 @@ pub fn bulk_update_notes_order(
             Ok(rows_affected) => {
                 if rows_affected > 0 {
                     successful_count += 1;
+                    publish_note_updated(note_id, &conn);
                 } else {
                     failed_count += 1;
                     errors.push(format!("Note {} not found", note_id));
@@ -235,8 +238,7 @@ pub fn bulk_update_notes_order(
 
 #[tauri::command]
 pub fn migrate_notes_to_states() -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
     // Get all notes that don't have a state_id assigned
     let mut stmt = conn.prepare(
@@ -285,9 +287,28 @@ pub fn migrate_notes_to_states() -> Result<NoteResponse, String> {
         rusqlite::params![state_id, now],
     ).map_err(|e| format!("Failed to migrate notes: {}", e))?;
 
+    for note_id in note_ids {
+        publish_note_updated(note_id, &conn);
+        publish_note_state_changed(note_id, &conn);
+    }
+
     Ok(NoteResponse {
         success: true,
         data: None,
         error: Some(format!("Migrated {} notes to default state", rows_affected)),
     })
-}
\ No newline at end of file
+}
+
+fn publish_note_updated(note_id: i64, conn: &rusqlite::Connection) {
+    publish_note_change(ChangeOperation::Updated, note_id, conn);
+}
+
+fn publish_note_state_changed(note_id: i64, conn: &rusqlite::Connection) {
+    publish_note_change(ChangeOperation::StateChanged, note_id, conn);
+}
+
+fn publish_note_change(operation: ChangeOperation, note_id: i64, conn: &rusqlite::Connection) {
+    if let Ok(Some(note)) = SqliteRepository::new(conn).get_note(note_id) {
+        events::publish_note(operation, &note);
+    }
+}
@@ -1,548 +1,977 @@
+use super::server_commands::get_server_port;
+use super::token_commands::{authenticate_api_token, get_mcp_client_token};
+use crate::http_server::{blocking, http_server_status};
+use crate::mcp::client::{self, McpClient};
+use crate::mcp::providers::{self, ServerTarget};
 use crate::models::*;
+use crate::settings;
 use serde_json;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tauri::Manager;
+use tokio::task::JoinSet;
 
-#[tauri::command]
-pub fn scan_mcp_configs() -> Result<McpScanResponse, String> {
-    use std::fs;
+// Name of Juan Note's entry in client configs
+const JUAN_NOTE_SERVER_NAME: &str = "juan-note-api";
+// Handshake timeout per client when verifying integrations; most answer in well under a second
+const INTEGRATION_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+// Argument that starts the app as a headless stdio MCP server
+const MCP_STDIO_FLAG: &str = "--mcp-stdio";
 
-    let mut results = Vec::new();
+// Discovered tools per server, so reopening the MCP panel doesn't relaunch every server
+const TOOLS_CACHE_TTL: Duration = Duration::from_secs(300);
+
+// Server cache key to when its tools were listed and the tools
+type ToolsCache = HashMap<String, (Instant, Vec<McpFunction>)>;
 
-    // Get home directory
+static TOOLS_CACHE: OnceLock<Mutex<ToolsCache>> = OnceLock::new();
+
+/// Read the MCP configs of the known clients and the custom paths from settings.
+/// With `discover` (default: the `mcp_scan.discover` setting) the usual config
+/// directories are also searched for other files that look like MCP configs.
+#[tauri::command]
+pub fn scan_mcp_configs(discover: Option<bool>) -> Result<McpScanResponse, String> {
     let home_dir = dirs::home_dir().ok_or("Could not determine home directory")?;
+    let scan_settings = settings::current().mcp_scan;
+    let mut scanner = ConfigScanner::new(&scan_settings);
+
+    // Known clients first, so their config files are attributed to them
+    for provider in providers::PROVIDERS {
+        let config_path = provider.config_path(&home_dir);
+        if config_path.exists() {
+            scanner.record(
+                &config_path,
+                provider.id,
+                format!("{} config", provider.name),
+                true,
+            );
+        }
+    }
 
-    // Config directories to scan (expanded list)
-    let mut config_dirs = vec![home_dir.join(".config"), home_dir.clone()];
+    for custom_path in &scan_settings.custom_config_paths {
+        let config_path = expand_home(custom_path, &home_dir);
+        scanner.record(
+            &config_path,
+            "custom",
+            "Custom config path".to_string(),
+            true,
+        );
+    }
 
-    // Add platform-specific directories
-    #[cfg(target_os = "macos")]
-    {
-        config_dirs.push(home_dir.join("Library/Application Support"));
-        config_dirs.push(home_dir.join("Library/Preferences"));
-        // Add specific mac paths mentioned by user
-        config_dirs.push(home_dir.join(".config/amp"));
-        config_dirs.push(home_dir.join(".config/.claude"));
-        config_dirs.push(home_dir.join(".config/opencode"));
-        config_dirs.push(home_dir.join(".claude"));
-        config_dirs.push(home_dir.join(".config/gemini"));
+    if discover.unwrap_or(scan_settings.discover) {
+        scanner.discover(&discovery_dirs(&home_dir));
     }
-    #[cfg(target_os = "windows")]
-    {
-        if let Some(appdata) = std::env::var_os("APPDATA") {
-            config_dirs.push(Path::new(&appdata).to_path_buf());
+
+    Ok(McpScanResponse {
+        success: true,
+        data: Some(scanner.results),
+        errors: (!scanner.errors.is_empty()).then_some(scanner.errors),
+        error: None,
+    })
+}
+
+/// Connect to every server found by `scan_mcp_configs` and list its tools.
+/// Results are cached per server; pass `refresh` to query the servers again.
+#[tauri::command]
+pub async fn query_mcp_functions(refresh: Option<bool>) -> Result<McpFunctionQueryResponse, String> {
+    let scan_result = blocking(|| scan_mcp_configs(None)).await?;
+
+    // The same server is often configured in several clients; only query it once
+    let mut seen = HashSet::new();
+    let mut servers = Vec::new();
+    for config_result in scan_result.data.unwrap_or_default() {
+        for server in config_result.mcp_servers {
+            if seen.insert(server_cache_key(&server)) {
+                servers.push((config_result.provider.clone(), server));
+            }
         }
-        if let Some(local_appdata) = std::env::var_os("LOCALAPPDATA") {
-            config_dirs.push(Path::new(&local_appdata).to_path_buf());
+    }
+
+    let mut functions = Vec::new();
+    let mut errors = Vec::new();
+    let mut queries = JoinSet::new();
+
+    for (provider, server) in servers {
+        if !refresh.unwrap_or(false) {
+            if let Some(cached) = get_cached_tools(&server) {
+                functions.extend(cached);
+                continue;
+            }
         }
+
+        queries.spawn(async move {
+            let result = query_server_tools(&provider, &server).await;
+            (server, result)
+        });
     }
-    #[cfg(target_os = "linux")]
-    {
-        config_dirs.push(home_dir.join(".local/share"));
+
+    while let Some(joined) = queries.join_next().await {
+        match joined {
+            Ok((server, Ok(tools))) => {
+                cache_tools(&server, tools.clone());
+                functions.extend(tools);
+            }
+            Ok((server, Err(e))) => errors.push(format!("{}: {}", server.name, e)),
+            Err(e) => errors.push(format!("Server query failed: {}", e)),
+        }
     }
 
-    // Known MCP client config files and patterns
-    let known_configs = vec![
-        // Claude Desktop
-        ("claude", ".claude.json"),
-        ("claude", "claude_desktop_config.json"),
-        ("claude", "claude.json"),
-        ("claude", "settings.json"),
-        // OpenCode
-        ("opencode", ".opencode.json"),
-        ("opencode", "opencode.json"),
-        ("opencode", "settings.json"),
-        // Gemini
-        ("gemini", "gemini.json"),
-        ("gemini", ".gemini.json"),
-        ("gemini", "settings.json"),
-        // AMP
-        ("amp", "amp.json"),
-        ("amp", ".amp.json"),
-        ("amp", "settings.json"),
-        // Continue
-        ("continue", "continue.json"),
-        ("continue", ".continue.json"),
-        ("continue", "config.json"),
-        ("continue", "settings.json"),
-        // Cline
-        ("cline", "cline.json"),
-        ("cline", ".cline.json"),
-        ("cline", "settings.json"),
-        // Specific mac paths mentioned by user
-        ("amp", ".config/amp/settings.json"),
-        ("claude", ".config/.claude/settings.local.json"),
-        ("opencode", ".config/opencode/opencode.json"),
-        ("claude", ".claude.json"),
-        ("gemini", ".config/gemini/settings.json"),
-        // Other MCP clients
-        ("mcp", "mcp.json"),
-        ("mcp", ".mcp.json"),
-        ("mcp-client", "mcp-client.json"),
-    ];
+    functions.sort_by(|a, b| (&a.server_name, &a.name).cmp(&(&b.server_name, &b.name)));
+    errors.sort();
 
-    // Scan known config files
-    for config_dir in &config_dirs {
-        if !config_dir.exists() {
-            continue;
-        }
+    Ok(McpFunctionQueryResponse {
+        success: true,
+        data: Some(functions),
+        errors: (!errors.is_empty()).then_some(errors),
+        error: None,
+    })
+}
 
-        for (provider, filename) in &known_configs {
-            let config_path = config_dir.join(filename);
+/// Look up a configured server by name, and by provider when the same name is
+/// configured in several clients.
+pub(crate) async fn find_configured_server(
+    server_name: &str,
+    provider: Option<&str>,
+) -> Result<(String, McpServerConfig), String> {
+    let scan_result = blocking(|| scan_mcp_configs(None)).await?;
+
+    scan_result
+        .data
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|config_result| provider.is_none_or(|p| config_result.provider == p))
+        .find_map(|config_result| {
+            let provider = config_result.provider;
+            config_result
+                .mcp_servers
+                .into_iter()
+                .find(|server| server.name == server_name)
+                .map(|server| (provider, server))
+        })
+        .ok_or_else(|| format!("MCP server '{}' not found", server_name))
+}
 
-            if config_path.exists() {
-                process_config_file(&config_path, provider, &mut results);
+/// Run the initialize/tools/list handshake against one server.
+pub(crate) async fn query_server_tools(
+    provider: &str,
+    server: &McpServerConfig,
+) -> Result<Vec<McpFunction>, String> {
+    let mut client = McpClient::connect(server, client::DEFAULT_TIMEOUT).await?;
+    let tools = client.list_tools().await;
+    client.close().await;
+
+    Ok(tools?
+        .iter()
+        .filter_map(|tool| mcp_function_from_tool(tool, &server.name, provider))
+        .collect())
+}
+
+fn mcp_function_from_tool(
+    tool: &serde_json::Value,
+    server_name: &str,
+    provider: &str,
+) -> Option<McpFunction> {
+    let input_schema = tool.get("inputSchema").cloned();
+
+    let required: Vec<&str> = input_schema
+        .as_ref()
+        .and_then(|schema| schema.get("required"))
+        .and_then(|required| required.as_array())
+        .map(|required| required.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default();
+
+    let parameters = input_schema
+        .as_ref()
+        .and_then(|schema| schema.get("properties"))
+        .and_then(|properties| properties.as_object())
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, property)| McpFunctionParameter {
+                    name: name.clone(),
+                    r#type: schema_type_name(property),
+                    description: property
+                        .get("description")
+                        .and_then(|d| d.as_str())
+                        .map(str::to_string),
+                    required: required.contains(&name.as_str()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(McpFunction {
+        name: tool.get("name")?.as_str()?.to_string(),
+        description: tool
+            .get("description")
+            .and_then(|d| d.as_str())
+            .map(str::to_string),
+        parameters,
+        server_name: server_name.to_string(),
+        server_provider: provider.to_string(),
+        input_schema,
+    })
+}
+
+// `"type"` may be missing or a list of types in JSON Schema
+fn schema_type_name(property: &serde_json::Value) -> String {
+    match property.get("type") {
+        Some(serde_json::Value::String(name)) => name.clone(),
+        Some(serde_json::Value::Array(names)) => names
+            .iter()
+            .filter_map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join("|"),
+        _ => "any".to_string(),
+    }
+}
+
+fn server_cache_key(server: &McpServerConfig) -> String {
+    match &server.url {
+        Some(url) => format!("{}|{}", server.name, url),
+        None => format!(
+            "{}|{}|{}",
+            server.name,
+            server.command,
+            server.args.clone().unwrap_or_default().join(" ")
+        ),
+    }
+}
+
+fn tools_cache() -> &'static Mutex<ToolsCache> {
+    TOOLS_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_cached_tools(server: &McpServerConfig) -> Option<Vec<McpFunction>> {
+    let cache = tools_cache().lock().unwrap();
+    let (cached_at, tools) = cache.get(&server_cache_key(server))?;
+    (cached_at.elapsed() < TOOLS_CACHE_TTL).then(|| tools.clone())
+}
+
+fn cache_tools(server: &McpServerConfig, tools: Vec<McpFunction>) {
+    tools_cache()
+        .lock()
+        .unwrap()
+        .insert(server_cache_key(server), (Instant::now(), tools));
+}
+
+/// The MCP clients Juan Note knows how to configure, and whether each one is
+/// installed and already has a Juan Note entry.
+#[tauri::command]
+pub fn get_mcp_providers() -> Result<McpProvidersResponse, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not determine home directory")?;
+
+    let data = providers::PROVIDERS
+        .iter()
+        .map(|provider| {
+            let config_path = provider.config_path(&home_dir);
+            let configured = std::fs::read_to_string(&config_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .is_some_and(|config| {
+                    provider
+                        .servers(&config)
+                        .is_some_and(|servers| servers.contains_key(JUAN_NOTE_SERVER_NAME))
+                });
+
+            McpProviderInfo {
+                id: provider.id.to_string(),
+                name: provider.name.to_string(),
+                config_path: config_path.to_string_lossy().to_string(),
+                installed: provider.is_installed(&home_dir),
+                configured,
             }
+        })
+        .collect();
 
-            // Also check subdirectories for some clients
-            if provider == &"claude" {
-                let claude_dir = config_dir.join("Claude");
-                if claude_dir.exists() {
-                    if let Ok(entries) = fs::read_dir(&claude_dir) {
-                        for entry in entries.flatten() {
-                            let path = entry.path();
-                            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                                process_config_file(&path, provider, &mut results);
-                            }
-                        }
-                    }
+    Ok(McpProvidersResponse {
+        success: true,
+        data,
+        error: None,
+    })
+}
+
+/// Show what `add_juan_note_mcp_server` (`action` "add") or
+/// `remove_juan_note_mcp_server` ("remove") would change, as a diff per config
+/// file. Nothing is written.
+#[tauri::command]
+pub fn preview_mcp_config_changes(
+    action: String,
+    providers: Option<Vec<String>>,
+    transport: Option<String>,
+) -> Result<McpConfigChangesResponse, String> {
+    let action = ConfigAction::parse(&action)?;
+    let data = plan_config_changes(action, providers, transport.as_deref())?
+        .into_iter()
+        .map(|planned| {
+            let mut change = McpConfigChange {
+                provider: planned.provider.id.to_string(),
+                config_path: planned.config_path.to_string_lossy().to_string(),
+                changed: false,
+                diff: String::new(),
+                error: None,
+            };
+            match planned.edit {
+                Ok(edit) => {
+                    change.changed = edit.changed();
+                    change.diff = edit.diff();
                 }
+                Err(e) => change.error = Some(e),
             }
-        }
-    }
+            change
+        })
+        .collect();
+
+    Ok(McpConfigChangesResponse {
+        success: true,
+        data,
+        error: None,
+    })
+}
 
-    // Also scan for any JSON files that might contain MCP configurations
-    scan_for_mcp_json_files(&config_dirs, &mut results);
+/// Add Juan Note to the config of every installed MCP client, or only the
+/// clients in `providers`. Each config file is backed up before it's changed.
+#[tauri::command]
+pub fn add_juan_note_mcp_server(
+    _app: tauri::AppHandle,
+    providers: Option<Vec<String>>,
+    transport: Option<String>,
+) -> Result<McpScanResponse, String> {
+    let results = plan_config_changes(ConfigAction::Add, providers, transport.as_deref())?
+        .into_iter()
+        .map(|planned| apply_config_change(ConfigAction::Add, planned))
+        .collect();
 
     Ok(McpScanResponse {
         success: true,
         data: Some(results),
+        errors: None,
         error: None,
     })
 }
 
+/// Remove Juan Note from every MCP client config that has it, or only the
+/// clients in `providers`. Each config file is backed up before it's changed.
 #[tauri::command]
-pub fn query_mcp_functions() -> Result<McpFunctionQueryResponse, String> {
-    // Return functions from the Juan Note MCP server
-    // These are API validation and monitoring functions
+pub fn remove_juan_note_mcp_server(
+    providers: Option<Vec<String>>,
+) -> Result<McpScanResponse, String> {
+    let results = plan_config_changes(ConfigAction::Remove, providers, None)?
+        .into_iter()
+        .map(|planned| apply_config_change(ConfigAction::Remove, planned))
+        .collect();
 
-    let juan_note_functions = vec![
-        McpFunction {
-            name: "validate_api_endpoints".to_string(),
-            description: Some("Validate that all frontend service methods have corresponding backend Tauri commands".to_string()),
-            parameters: vec![
-                McpFunctionParameter {
-                    name: "project_root".to_string(),
-                    r#type: "string".to_string(),
-                    description: Some("Path to the project root directory".to_string()),
-                    required: false,
-                },
-            ],
-            server_name: "juan-note-mcp-server".to_string(),
-            server_provider: "juan-note".to_string(),
-        },
-        McpFunction {
-            name: "generate_api_report".to_string(),
-            description: Some("Generate a comprehensive API validation report".to_string()),
-            parameters: vec![
-                McpFunctionParameter {
-                    name: "output_path".to_string(),
-                    r#type: "string".to_string(),
-                    description: Some("Path where to save the report (optional)".to_string()),
-                    required: false,
-                },
-                McpFunctionParameter {
-                    name: "include_details".to_string(),
-                    r#type: "boolean".to_string(),
-                    description: Some("Include detailed type mismatch information".to_string()),
-                    required: false,
-                },
-            ],
-            server_name: "juan-note-mcp-server".to_string(),
-            server_provider: "juan-note".to_string(),
-        },
-        McpFunction {
-            name: "check_file_changes".to_string(),
-            description: Some("Check for recent changes in API-related files".to_string()),
-            parameters: vec![
-                McpFunctionParameter {
-                    name: "since_minutes".to_string(),
-                    r#type: "number".to_string(),
-                    description: Some("Check changes within the last N minutes".to_string()),
-                    required: false,
-                },
-            ],
-            server_name: "juan-note-mcp-server".to_string(),
-            server_provider: "juan-note".to_string(),
-        },
-        McpFunction {
-            name: "update_api_documentation".to_string(),
-            description: Some("Update the AGENTS.md file with current API information".to_string()),
-            parameters: vec![
-                McpFunctionParameter {
-                    name: "force_update".to_string(),
-                    r#type: "boolean".to_string(),
-                    description: Some("Force update even if no changes detected".to_string()),
-                    required: false,
-                },
-            ],
-            server_name: "juan-note-mcp-server".to_string(),
-            server_provider: "juan-note".to_string(),
-        },
-        McpFunction {
-            name: "analyze_code_patterns".to_string(),
-            description: Some("Analyze code patterns and suggest improvements".to_string()),
-            parameters: vec![
-                McpFunctionParameter {
-                    name: "file_pattern".to_string(),
-                    r#type: "string".to_string(),
-                    description: Some("File pattern to analyze (e.g., *.ts, *.rs)".to_string()),
-                    required: false,
-                },
-                McpFunctionParameter {
-                    name: "analysis_type".to_string(),
-                    r#type: "string".to_string(),
-                    description: Some("Type of analysis (consistency, performance, security)".to_string()),
-                    required: false,
-                },
-            ],
-            server_name: "juan-note-mcp-server".to_string(),
-            server_provider: "juan-note".to_string(),
-        },
-        McpFunction {
-            name: "validate_type_safety".to_string(),
-            description: Some("Validate TypeScript and Rust type consistency".to_string()),
-            parameters: vec![
-                McpFunctionParameter {
-                    name: "strict_mode".to_string(),
-                    r#type: "boolean".to_string(),
-                    description: Some("Enable strict type checking mode".to_string()),
-                    required: false,
-                },
-            ],
-            server_name: "juan-note-mcp-server".to_string(),
-            server_provider: "juan-note".to_string(),
-        },
-    ];
-
-    Ok(McpFunctionQueryResponse {
+    Ok(McpScanResponse {
         success: true,
-        data: Some(juan_note_functions),
+        data: Some(results),
+        errors: None,
         error: None,
     })
 }
 
+/// Check every client config with a Juan Note entry: that it points at this
+/// executable or the running server's port, that its API token is still valid,
+/// and that the server answers the MCP handshake.
 #[tauri::command]
-pub fn add_juan_note_mcp_server(app: tauri::AppHandle) -> Result<McpScanResponse, String> {
-    use std::fs;
+pub async fn verify_mcp_integrations() -> Result<McpIntegrationsResponse, String> {
+    Ok(McpIntegrationsResponse {
+        success: true,
+        data: check_integrations().await?,
+        error: None,
+    })
+}
 
-    // First scan for all MCP config files
-    let scan_result = scan_mcp_configs()?;
-    let config_results = scan_result.data.unwrap_or_default();
+/// Rewrite the stale Juan Note entries found by `verify_mcp_integrations` with
+/// the current executable, port and token, then check them again.
+#[tauri::command]
+pub async fn repair_mcp_integrations() -> Result<McpIntegrationsResponse, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not determine home directory")?;
+    let checks = check_integrations().await?;
 
-    // Use the MCP server port (27182) - this is a weird port to avoid conflicts
-    let server_port = 27182;
+    let mut repairs = HashMap::new();
+    for check in checks
+        .iter()
+        .filter(|check| check.status == McpIntegrationStatus::Stale)
+    {
+        let Some(provider) = providers::find(&check.provider) else {
+            continue;
+        };
+        let outcome = juan_note_server_target(Some(&check.transport))
+            .and_then(|target| provider.entry(&target))
+            .and_then(|entry| provider.plan_edit(&home_dir, JUAN_NOTE_SERVER_NAME, Some(entry)))
+            .and_then(|edit| edit.apply());
+        repairs.insert(check.provider.clone(), outcome);
+    }
 
-    // Juan Note API configuration for remote MCP server
-    let juan_note_config = serde_json::json!({
-        "type": "remote",
-        "url": format!("http://localhost:{}", server_port),
-        "enabled": true,
-        "headers": {
-            // TODO: Add authentication headers when auth is implemented
-            // "Authorization": "Bearer <token>"
+    // Check again so the response shows whether the repair worked
+    let mut data = check_integrations().await?;
+    for check in &mut data {
+        match repairs.remove(&check.provider) {
+            Some(Ok(backup_path)) => {
+                check.repaired = true;
+                check.backup_path = backup_path.map(|path| path.to_string_lossy().to_string());
+            }
+            Some(Err(e)) => check.problems.push(format!("Repair failed: {}", e)),
+            None => {}
         }
+    }
+
+    Ok(McpIntegrationsResponse {
+        success: true,
+        data,
+        error: None,
+    })
+}
+
+async fn check_integrations() -> Result<Vec<McpIntegrationCheck>, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not determine home directory")?;
+
+    let mut checks = JoinSet::new();
+    for provider in providers::PROVIDERS {
+        let config_path = provider.config_path(&home_dir);
+        let Some(server) = std::fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|config| {
+                let entry = provider.servers(&config)?.get(JUAN_NOTE_SERVER_NAME)?;
+                parse_server_config(JUAN_NOTE_SERVER_NAME, entry)
+            })
+        else {
+            continue;
+        };
+
+        let config_path = config_path.to_string_lossy().to_string();
+        checks.spawn(check_integration(provider.id, config_path, server));
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = checks.join_next().await {
+        results.push(joined.map_err(|e| format!("Integration check failed: {}", e))?);
+    }
+    // Keep the registry's order regardless of which check finished first
+    results.sort_by_key(|check| {
+        providers::PROVIDERS
+            .iter()
+            .position(|provider| provider.id == check.provider)
     });
 
-    let mut updated_results = Vec::new();
-
-    // Try to add Juan Note MCP server to each config file found
-    for config_result in config_results {
-        let mut updated_config = config_result.clone();
-
-        match fs::read_to_string(&config_result.config_path) {
-            Ok(content) => {
-                match serde_json::from_str::<serde_json::Value>(&content) {
-                    Ok(mut json) => {
-                        // Try to add the server to this config
-                        let added = add_server_to_config(
-                            &mut json,
-                            "juan-note-api",
-                            juan_note_config.clone(),
-                        );
-
-                        if added {
-                            // Write back to file
-                            match serde_json::to_string_pretty(&json) {
-                                Ok(updated_content) => {
-                                    match fs::write(&config_result.config_path, updated_content) {
-                                        Ok(_) => {
-                                            updated_config.error = Some(
-                                                "Successfully added Juan Note MCP server"
-                                                    .to_string(),
-                                            );
-                                        }
-                                        Err(e) => {
-                                            updated_config.error =
-                                                Some(format!("Failed to write config file: {}", e));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    updated_config.error =
-                                        Some(format!("Failed to serialize config: {}", e));
-                                }
-                            }
-                        } else {
-                            updated_config.error = Some(
-                                "Could not add Juan Note MCP server to this config format"
-                                    .to_string(),
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        updated_config.error = Some(format!("Failed to parse config file: {}", e));
-                    }
-                }
-            }
-            Err(e) => {
-                updated_config.error = Some(format!("Failed to read config file: {}", e));
+    Ok(results)
+}
+
+async fn check_integration(
+    provider: &'static str,
+    config_path: String,
+    server: McpServerConfig,
+) -> McpIntegrationCheck {
+    let problems = match &server.url {
+        Some(url) => http_entry_problems(url, server.headers.as_ref()),
+        None => stdio_entry_problems(&server),
+    };
+
+    // Even a stale entry might still connect, e.g. to an older install
+    let handshake = match McpClient::connect(&server, INTEGRATION_CHECK_TIMEOUT).await {
+        Ok(mut client) => {
+            let tools = client.list_tools().await;
+            client.close().await;
+            tools.map(|_| ())
+        }
+        Err(e) => Err(e),
+    };
+
+    let (status, mut problems) = match (problems.is_empty(), handshake) {
+        (true, Ok(())) => (McpIntegrationStatus::Healthy, problems),
+        (true, Err(e)) => (
+            McpIntegrationStatus::Unreachable,
+            vec![format!("MCP handshake failed: {}", e)],
+        ),
+        (false, handshake) => {
+            let mut problems = problems;
+            if let Err(e) = handshake {
+                problems.push(format!("MCP handshake failed: {}", e));
             }
+            (McpIntegrationStatus::Stale, problems)
+        }
+    };
+    problems.dedup();
+
+    McpIntegrationCheck {
+        provider: provider.to_string(),
+        config_path,
+        transport: if server.url.is_some() {
+            "http"
+        } else {
+            "stdio"
+        }
+        .to_string(),
+        status,
+        problems,
+        repaired: false,
+        backup_path: None,
+    }
+}
+
+fn stdio_entry_problems(server: &McpServerConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if !Path::new(&server.command).exists() {
+        problems.push(format!("Command '{}' doesn't exist", server.command));
+    } else if let Ok(current) = juan_note_executable() {
+        if !same_file(Path::new(&server.command), Path::new(&current)) {
+            problems.push(format!(
+                "Command '{}' is not this Juan Note executable ({})",
+                server.command, current
+            ));
         }
+    }
 
-        updated_results.push(updated_config);
+    let args = server.args.as_deref().unwrap_or_default();
+    if !args.iter().any(|arg| arg == MCP_STDIO_FLAG) {
+        problems.push(format!("Arguments are missing {}", MCP_STDIO_FLAG));
     }
 
-    Ok(McpScanResponse {
-        success: true,
-        data: Some(updated_results),
-        error: None,
-    })
+    problems
 }
 
-#[tauri::command]
-pub fn remove_juan_note_mcp_server() -> Result<McpScanResponse, String> {
-    use std::fs;
-
-    // First scan for all MCP config files
-    let scan_result = scan_mcp_configs()?;
-    let config_results = scan_result.data.unwrap_or_default();
-
-    let mut updated_results = Vec::new();
-
-    // Try to remove Juan Note MCP server from each config file found
-    for config_result in config_results {
-        let mut updated_config = config_result.clone();
-
-        match fs::read_to_string(&config_result.config_path) {
-            Ok(content) => {
-                match serde_json::from_str::<serde_json::Value>(&content) {
-                    Ok(mut json) => {
-                        // Try to remove the server from this config
-                        let removed = remove_server_from_config(&mut json, "juan-note-api");
-
-                        if removed {
-                            // Write back to file
-                            match serde_json::to_string_pretty(&json) {
-                                Ok(updated_content) => {
-                                    match fs::write(&config_result.config_path, updated_content) {
-                                        Ok(_) => {
-                                            updated_config.error = Some(
-                                                "Successfully removed Juan Note MCP server"
-                                                    .to_string(),
-                                            );
-                                        }
-                                        Err(e) => {
-                                            updated_config.error =
-                                                Some(format!("Failed to write config file: {}", e));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    updated_config.error =
-                                        Some(format!("Failed to serialize config: {}", e));
-                                }
-                            }
-                        } else {
-                            updated_config.error =
-                                Some("Juan Note MCP server not found in this config".to_string());
-                        }
-                    }
-                    Err(e) => {
-                        updated_config.error = Some(format!("Failed to parse config file: {}", e));
-                    }
-                }
+fn http_entry_problems(url: &str, headers: Option<&serde_json::Value>) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let status = http_server_status();
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => {
+            let expected_port = status.port.unwrap_or(status.configured_port);
+            if parsed.port_or_known_default() != Some(expected_port) {
+                problems.push(format!(
+                    "URL {} doesn't use the server's port {}",
+                    url, expected_port
+                ));
             }
-            Err(e) => {
-                updated_config.error = Some(format!("Failed to read config file: {}", e));
+            if parsed.path() != "/mcp" {
+                problems.push(format!("URL {} doesn't point at /mcp", url));
             }
         }
+        Err(e) => problems.push(format!("Invalid URL '{}': {}", url, e)),
+    }
 
-        updated_results.push(updated_config);
+    let token = headers
+        .and_then(|headers| headers.as_object())
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        })
+        .and_then(|(_, value)| value.as_str())
+        .and_then(|value| value.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim().to_string());
+    match token {
+        None => problems.push("No API token in the Authorization header".to_string()),
+        Some(token) => match authenticate_api_token(&token) {
+            Err(e) => problems.push(e),
+            Ok(None) => problems.push("API token is invalid or revoked".to_string()),
+            Ok(Some(api_token)) if !api_token.has_scope(TokenScope::Read) => problems.push(format!(
+                "API token '{}' lacks the read scope",
+                api_token.name
+            )),
+            Ok(Some(_)) => {}
+        },
     }
 
-    Ok(McpScanResponse {
-        success: true,
-        data: Some(updated_results),
+    problems
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ConfigAction {
+    Add,
+    Remove,
+}
+
+impl ConfigAction {
+    fn parse(action: &str) -> Result<Self, String> {
+        match action {
+            "add" => Ok(ConfigAction::Add),
+            "remove" => Ok(ConfigAction::Remove),
+            other => Err(format!("Unknown config action: {}", other)),
+        }
+    }
+}
+
+struct PlannedChange {
+    provider: &'static providers::Provider,
+    config_path: PathBuf,
+    edit: Result<providers::PlannedEdit, String>,
+}
+
+// Without an explicit list, adding targets the installed clients and removing
+// targets the clients whose config file exists
+fn plan_config_changes(
+    action: ConfigAction,
+    provider_ids: Option<Vec<String>>,
+    transport: Option<&str>,
+) -> Result<Vec<PlannedChange>, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not determine home directory")?;
+
+    let explicit = provider_ids.is_some();
+    let selected: Vec<&'static providers::Provider> = match provider_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| providers::find(id).ok_or_else(|| format!("Unknown MCP client: {}", id)))
+            .collect::<Result<_, _>>()?,
+        None => providers::PROVIDERS
+            .iter()
+            .filter(|provider| match action {
+                ConfigAction::Add => provider.is_installed(&home_dir),
+                ConfigAction::Remove => provider.config_path(&home_dir).is_file(),
+            })
+            .collect(),
+    };
+
+    let target = match action {
+        ConfigAction::Add => Some(juan_note_server_target(transport)?),
+        ConfigAction::Remove => None,
+    };
+
+    Ok(selected
+        .into_iter()
+        .map(|provider| {
+            let edit = match &target {
+                Some(_) if explicit && !provider.is_installed(&home_dir) => {
+                    Err(format!("{} doesn't appear to be installed", provider.name))
+                }
+                Some(target) => provider.entry(target).and_then(|entry| {
+                    provider.plan_edit(&home_dir, JUAN_NOTE_SERVER_NAME, Some(entry))
+                }),
+                None => provider.plan_edit(&home_dir, JUAN_NOTE_SERVER_NAME, None),
+            };
+
+            PlannedChange {
+                provider,
+                config_path: provider.config_path(&home_dir),
+                edit,
+            }
+        })
+        .collect())
+}
+
+fn apply_config_change(action: ConfigAction, planned: PlannedChange) -> McpConfigResult {
+    let mut result = McpConfigResult {
+        provider: planned.provider.id.to_string(),
+        config_path: planned.config_path.to_string_lossy().to_string(),
+        mcp_servers: Vec::new(),
         error: None,
-    })
+        reason: None,
+        duration_ms: None,
+    };
+
+    let edit = match planned.edit {
+        Ok(edit) => edit,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+
+    let verb = match action {
+        ConfigAction::Add => "added",
+        ConfigAction::Remove => "removed",
+    };
+    let applied = edit.apply();
+    let contents = if applied.is_ok() { &edit.after } else { &edit.before };
+    if let Ok(config) = serde_json::from_str::<serde_json::Value>(contents) {
+        result.mcp_servers = extract_mcp_servers(&config);
+    }
+
+    result.error = Some(match applied {
+        Ok(_) if !edit.changed() => match action {
+            ConfigAction::Add => "Juan Note MCP server is already configured".to_string(),
+            ConfigAction::Remove => "Juan Note MCP server not found in this config".to_string(),
+        },
+        Ok(Some(backup_path)) => format!(
+            "Successfully {} Juan Note MCP server (backup: {})",
+            verb,
+            backup_path.display()
+        ),
+        Ok(None) => format!("Successfully {} Juan Note MCP server", verb),
+        Err(e) => e,
+    });
+
+    result
 }
 
+/// MCP client configuration for Juan Note. `transport` is `stdio` (default),
+/// which launches the app headless, or `http` for the running app's /mcp endpoint.
 #[tauri::command]
-pub fn get_mcp_server_config(_app: tauri::AppHandle) -> Result<String, String> {
-    // Use the MCP server port (27182) - this is a weird port to avoid conflicts
-    let server_port = 27182;
+pub fn get_mcp_server_config(
+    _app: tauri::AppHandle,
+    transport: Option<String>,
+) -> Result<String, String> {
+    let server_config = match transport.as_deref() {
+        None | Some("stdio") => juan_note_stdio_config()?,
+        Some("http") => juan_note_http_config()?,
+        Some(other) => return Err(format!("Unknown MCP transport: {}", other)),
+    };
 
-    // Create the MCP server configuration for remote MCP server
     let config = serde_json::json!({
         "mcp": {
-            "juan-note-api": {
-                "type": "remote",
-                "url": format!("http://localhost:{}", server_port),
-                "enabled": true,
-                "headers": {
-                    // TODO: Add authentication headers when auth is implemented
-                    // "Authorization": "Bearer <token>"
-                }
-            }
+            JUAN_NOTE_SERVER_NAME: server_config
         }
     });
 
     Ok(serde_json::to_string_pretty(&config).unwrap())
 }
 
-#[tauri::command]
-pub fn check_http_server_status() -> Result<serde_json::Value, String> {
-    // TODO: Implement actual HTTP server status check
-    // - Check if the HTTP server is running on the expected port
-    // - Return server status, port, and health information
-    // - This can be used by MCP clients to determine if app needs to be launched
+fn juan_note_stdio_config() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "type": "stdio",
+        "command": juan_note_executable()?,
+        "args": [MCP_STDIO_FLAG],
+        "enabled": true
+    }))
+}
+
+fn juan_note_http_config() -> Result<serde_json::Value, String> {
+    // The MCP endpoint is served by the app's own HTTP server
+    let server_port = get_server_port()?;
+    let token = get_mcp_client_token()?;
 
     Ok(serde_json::json!({
-        "server_running": true, // TODO: Actually check if server is running
-        "port": 3001,
-        "health_endpoint": "/health",
-        "api_endpoints": [
-            "/notes",
-            "/states",
-            "/bulk/notes/*"
-        ]
+        "type": "remote",
+        "url": format!("http://localhost:{}/mcp", server_port),
+        "enabled": true,
+        "headers": {
+            "Authorization": format!("Bearer {}", token)
+        }
     }))
 }
 
-#[tauri::command]
-pub fn get_server_port() -> Result<i32, String> {
-    // TODO: Make this configurable and persistent
-    // - Store port in app config
-    // - Handle port conflicts
-    // - Allow user to configure custom port
-    Ok(3001)
+// What a client needs to reach Juan Note over the given transport
+fn juan_note_server_target(transport: Option<&str>) -> Result<ServerTarget, String> {
+    match transport {
+        None | Some("stdio") => Ok(ServerTarget::Stdio {
+            command: juan_note_executable()?,
+            args: vec![MCP_STDIO_FLAG.to_string()],
+        }),
+        Some("http") => {
+            let mut headers = serde_json::Map::new();
+            headers.insert(
+                "Authorization".to_string(),
+                format!("Bearer {}", get_mcp_client_token()?).into(),
+            );
+            Ok(ServerTarget::Http {
+                url: format!("http://localhost:{}/mcp", get_server_port()?),
+                headers,
+            })
+        }
+        Some(other) => Err(format!("Unknown MCP transport: {}", other)),
+    }
 }
 
-#[tauri::command]
-pub fn get_launch_info(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
-    // TODO: Provide information for MCP clients to launch the app
-    // - Get the app executable path
-    // - Provide command line arguments if needed
-    // - Include working directory
-    // - Add platform-specific launch information
-
-    let exe_path = std::env::current_exe()
+fn juan_note_executable() -> Result<String, String> {
+    Ok(env::current_exe()
         .map_err(|e| format!("Failed to get executable path: {}", e))?
         .to_string_lossy()
-        .to_string();
+        .to_string())
+}
 
-    Ok(serde_json::json!({
-        "executable_path": exe_path,
-        "arguments": [],
-        "working_directory": std::env::current_dir()
-            .map_err(|e| format!("Failed to get working directory: {}", e))?
-            .to_string_lossy(),
-        "server_port": 3001,
-        "health_check_url": "http://localhost:3001/health",
-        "startup_timeout_ms": 10000 // 10 seconds to start HTTP server
-    }))
+// Directories never worth descending into when looking for configs
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    ".git",
+    ".hg",
+    ".svn",
+    ".cache",
+    "Cache",
+    "Caches",
+    "CachedData",
+    "Code Cache",
+    "GPUCache",
+    "logs",
+    "Logs",
+    ".npm",
+    ".pnpm-store",
+    ".yarn",
+    ".cargo",
+    ".rustup",
+    ".gradle",
+    ".m2",
+    ".venv",
+    "venv",
+    "__pycache__",
+    "target",
+    ".Trash",
+    "Trash",
+    "Containers",
+    "Group Containers",
+    "IndexedDB",
+    "Local Storage",
+    "Session Storage",
+    "Service Worker",
+    "workspaceStorage",
+];
+
+// Stops discovery from crawling a huge home directory even within the depth limit
+const MAX_DISCOVERY_ENTRIES: usize = 20_000;
+
+struct ConfigScanner {
+    results: Vec<McpConfigResult>,
+    // Problems with the scan as a whole, such as hitting a limit
+    errors: Vec<String>,
+    max_depth: usize,
+    max_file_bytes: u64,
+    timeout: Duration,
+    started_at: Instant,
 }
 
-fn process_config_file(config_path: &Path, provider: &str, results: &mut Vec<McpConfigResult>) {
-    use std::fs;
-
-    let config_path_str = config_path.to_string_lossy().to_string();
-
-    // Check if we already have this config file
-    if results.iter().any(|r| r.config_path == config_path_str) {
-        return;
-    }
-
-    // Try to read and parse the file to see if it has MCP configuration
-    match fs::read_to_string(config_path) {
-        Ok(content) => {
-            match serde_json::from_str::<serde_json::Value>(&content) {
-                Ok(json) => {
-                    // Check if it has MCP servers section
-                    let has_mcp = json.get("mcp").is_some() ||
-                                 json.get("servers").is_some() ||
-                                 json.get("mcpServers").is_some();
-
-                    if has_mcp {
-                        results.push(McpConfigResult {
-                            config_path: config_path_str,
-                            provider: provider.to_string(),
-                            mcp_servers: extract_mcp_servers(&json),
-                            error: None,
-                        });
-                    } else {
-                        // Still add it as a potential config file
-                        results.push(McpConfigResult {
-                            config_path: config_path_str,
-                            provider: provider.to_string(),
-                            mcp_servers: Vec::new(),
-                            error: Some("No MCP configuration found".to_string()),
-                        });
-                    }
-                }
-                Err(e) => {
-                    results.push(McpConfigResult {
-                        config_path: config_path_str,
-                        provider: provider.to_string(),
-                        mcp_servers: Vec::new(),
-                        error: Some(format!("Failed to parse JSON: {}", e)),
-                    });
-                }
-            }
+impl ConfigScanner {
+    fn new(scan_settings: &McpScanSettings) -> Self {
+        ConfigScanner {
+            results: Vec::new(),
+            errors: Vec::new(),
+            max_depth: scan_settings.max_depth,
+            max_file_bytes: scan_settings.max_file_bytes,
+            timeout: Duration::from_millis(scan_settings.timeout_ms),
+            started_at: Instant::now(),
         }
-        Err(e) => {
-            results.push(McpConfigResult {
-                config_path: config_path_str,
-                provider: provider.to_string(),
-                mcp_servers: Vec::new(),
-                error: Some(format!("Failed to read file: {}", e)),
-            });
+    }
+
+    fn timed_out(&mut self) -> bool {
+        if self.started_at.elapsed() < self.timeout {
+            return false;
         }
+        let message = format!(
+            "Scan stopped after {} ms; results are incomplete",
+            self.timeout.as_millis()
+        );
+        if !self.errors.contains(&message) {
+            self.errors.push(message);
+        }
+        true
     }
-}
 
-fn scan_for_mcp_json_files(config_dirs: &[std::path::PathBuf], results: &mut Vec<McpConfigResult>) {
-    for dir in config_dirs {
-        if !dir.exists() {
-            continue;
+    /// Read one config file and record it, with an error explaining why when it
+    /// couldn't be used. Files the user asked for (`explicit`: the known
+    /// clients' configs and the custom paths) are read whatever their size and
+    /// listed even without an MCP servers section; discovered ones are not.
+    fn record(&mut self, config_path: &Path, provider: &str, reason: String, explicit: bool) {
+        let config_path_str = config_path.to_string_lossy().to_string();
+        if self
+            .results
+            .iter()
+            .any(|r| r.config_path == config_path_str)
+        {
+            return;
+        }
+
+        let started_at = Instant::now();
+        let (mcp_servers, error) = match self.parse(config_path, !explicit) {
+            Ok(Some(json)) => (extract_mcp_servers(&json), None),
+            Ok(None) if !explicit => return,
+            Ok(None) => (Vec::new(), Some("No MCP configuration found".to_string())),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+
+        self.results.push(McpConfigResult {
+            config_path: config_path_str,
+            provider: provider.to_string(),
+            mcp_servers,
+            error,
+            reason: Some(reason),
+            duration_ms: Some(started_at.elapsed().as_millis() as u64),
+        });
+    }
+
+    // The parsed file, or `None` when it has no MCP servers section
+    fn parse(
+        &mut self,
+        config_path: &Path,
+        size_limited: bool,
+    ) -> Result<Option<serde_json::Value>, String> {
+        if self.timed_out() {
+            return Err("Skipped: the scan ran out of time".to_string());
         }
 
-        // Walk through directories to find JSON files that might contain MCP configs
-        if let Ok(entries) = walk_dir(dir, 3) {
-            for entry in entries {
-                if entry.extension().and_then(|s| s.to_str()) == Some("json") {
-                    // Check if filename suggests MCP config
-                    let filename = entry.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                    if filename.contains("mcp") ||
-                       filename.contains("claude") ||
-                       filename.contains("opencode") ||
-                       filename.contains("gemini") ||
-                       filename.contains("amp") ||
-                       filename.contains("cline") ||
-                       filename.contains("continue") {
-                        process_config_file(&entry, "auto-detected", results);
+        let metadata = std::fs::metadata(config_path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                "File not found".to_string()
+            } else {
+                format!("Failed to read file: {}", e)
+            }
+        })?;
+        if !metadata.is_file() {
+            return Err("Not a file".to_string());
+        }
+        if size_limited && metadata.len() > self.max_file_bytes {
+            return Err(format!(
+                "Skipped: {} bytes is over the {} byte limit",
+                metadata.len(),
+                self.max_file_bytes
+            ));
+        }
+
+        let content = std::fs::read_to_string(config_path)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let json = serde_json::from_str::<serde_json::Value>(&content)
+            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+        let has_mcp = MCP_SERVER_KEYS.iter().any(|key| json.get(*key).is_some());
+        Ok(has_mcp.then_some(json))
+    }
+
+    /// Walk `dirs` breadth-first for files that look like MCP configs, within
+    /// the depth, entry count and time limits. Symlinks aren't followed.
+    fn discover(&mut self, dirs: &[PathBuf]) {
+        let mut queue: VecDeque<(PathBuf, usize)> = dirs
+            .iter()
+            .filter(|dir| dir.is_dir())
+            .map(|dir| (dir.clone(), 0))
+            .collect();
+        // The roots overlap (`~` contains `~/.config`), so each directory is read once
+        let mut seen_dirs = HashSet::new();
+        let mut visited = 0;
+
+        while let Some((dir, depth)) = queue.pop_front() {
+            if self.timed_out() {
+                return;
+            }
+            if !seen_dirs.insert(dir.clone()) {
+                continue;
+            }
+
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                visited += 1;
+                if visited > MAX_DISCOVERY_ENTRIES {
+                    self.errors.push(format!(
+                        "Scan stopped after {} files and directories; results are incomplete",
+                        MAX_DISCOVERY_ENTRIES
+                    ));
+                    return;
+                }
+
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+
+                if file_type.is_dir() {
+                    if depth < self.max_depth && !SKIPPED_DIRS.contains(&name.as_str()) {
+                        queue.push_back((path, depth + 1));
+                    }
+                } else if file_type.is_file() {
+                    if let Some(reason) = discovery_reason(&path, &name) {
+                        self.record(&path, "auto-detected", reason, false);
                     }
                 }
             }
@@ -550,165 +979,389 @@ fn scan_for_mcp_json_files(config_dirs: &[std::path::PathBuf], results: &mut Vec
     }
 }
 
-fn walk_dir(dir: &Path, max_depth: usize) -> Result<Vec<std::path::PathBuf>, std::io::Error> {
-    let mut results = Vec::new();
-    walk_dir_recursive(dir, max_depth, 0, &mut results)?;
-    Ok(results)
+// Why a file looks like an MCP config, judging by its name and directory
+fn discovery_reason(path: &Path, name: &str) -> Option<String> {
+    if !name.ends_with(".json") {
+        return None;
+    }
+    if name.to_lowercase().contains("mcp") {
+        return Some("File name contains 'mcp'".to_string());
+    }
+
+    // Generic names only count inside a directory belonging to an MCP client
+    let dir_name = path
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .map(|dir| dir.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let client_dirs = [
+        "claude",
+        ".claude",
+        "amp",
+        "gemini",
+        ".gemini",
+        "opencode",
+        ".continue",
+    ];
+    if matches!(
+        name,
+        "settings.json" | "config.json" | "settings.local.json"
+    ) && client_dirs.contains(&dir_name.as_str())
+    {
+        return Some(format!("{} in a '{}' directory", name, dir_name));
+    }
+
+    None
 }
 
-fn walk_dir_recursive(
-    dir: &Path,
-    max_depth: usize,
-    current_depth: usize,
-    results: &mut Vec<std::path::PathBuf>,
-) -> Result<(), std::io::Error> {
-    if current_depth > max_depth {
-        return Ok(());
-    }
-
-    let entries = std::fs::read_dir(dir)?;
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file() {
-            results.push(path);
-        } else if path.is_dir() && current_depth < max_depth {
-            walk_dir_recursive(&path, max_depth, current_depth + 1, results)?;
+// Where MCP clients usually keep their configs, for discovery
+fn discovery_dirs(home_dir: &Path) -> Vec<PathBuf> {
+    #[allow(unused_mut)]
+    let mut config_dirs = vec![home_dir.join(".config"), home_dir.to_path_buf()];
+
+    #[cfg(target_os = "macos")]
+    {
+        config_dirs.push(home_dir.join("Library/Application Support"));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            config_dirs.push(PathBuf::from(appdata));
         }
     }
+    #[cfg(target_os = "linux")]
+    {
+        config_dirs.push(home_dir.join(".local/share"));
+    }
 
-    Ok(())
+    config_dirs
 }
 
+fn expand_home(path: &str, home_dir: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+// Keys that hold a name → server map in the configs of the clients we know about
+const MCP_SERVER_KEYS: &[&str] = &["mcpServers", "amp.mcpServers", "mcp", "servers"];
+
 fn extract_mcp_servers(json: &serde_json::Value) -> Vec<McpServerConfig> {
-    let mut servers = Vec::new();
+    // VS Code nests its map one level deeper, under `mcp.servers`
+    let nested = json.get("mcp").and_then(|mcp| mcp.get("servers"));
+
+    MCP_SERVER_KEYS
+        .iter()
+        .filter_map(|key| json.get(*key))
+        .chain(nested)
+        .filter_map(|servers| servers.as_object())
+        .flat_map(|servers| {
+            servers
+                .iter()
+                .filter_map(|(name, config)| parse_server_config(name, config))
+        })
+        .collect()
+}
 
-    // Try different possible MCP server locations in the JSON
-    if let Some(mcp) = json.get("mcp") {
-        if let Some(servers_obj) = mcp.get("servers") {
-            if let Some(obj) = servers_obj.as_object() {
-                for (name, config) in obj {
-                    if let Ok(server_config) = serde_json::from_value(config.clone()) {
-                        servers.push(server_config);
-                    }
-                }
-            }
+// Clients describe servers in slightly different shapes: `command` is either a
+// string with separate `args` or an array, and env may be `env` or `environment`
+fn parse_server_config(name: &str, config: &serde_json::Value) -> Option<McpServerConfig> {
+    let obj = config.as_object()?;
+    let strings = |value: &serde_json::Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|items| items.iter().filter_map(|item| item.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    let (command, mut args) = match obj.get("command") {
+        Some(serde_json::Value::String(command)) => (command.clone(), Vec::new()),
+        Some(parts @ serde_json::Value::Array(_)) => {
+            let parts = strings(parts);
+            let (command, args) = parts.split_first()?;
+            (command.clone(), args.to_vec())
         }
+        _ => (String::new(), Vec::new()),
+    };
+    if let Some(extra_args) = obj.get("args") {
+        args.extend(strings(extra_args));
     }
 
-    // Also check top-level servers
-    if let Some(servers_obj) = json.get("servers") {
-        if let Some(obj) = servers_obj.as_object() {
-            for (name, config) in obj {
-                if let Ok(server_config) = serde_json::from_value(config.clone()) {
-                    servers.push(server_config);
-                }
-            }
-        }
+    let url = ["url", "serverUrl", "httpUrl"]
+        .iter()
+        .find_map(|key| obj.get(*key).and_then(|url| url.as_str()))
+        .map(str::to_string);
+
+    if command.is_empty() && url.is_none() {
+        return None;
     }
 
-    servers
+    Some(McpServerConfig {
+        name: name.to_string(),
+        command,
+        args: (!args.is_empty()).then_some(args),
+        env: obj.get("env").or_else(|| obj.get("environment")).cloned(),
+        url,
+        headers: obj.get("headers").cloned(),
+    })
 }
 
-fn extract_server_config(
-    json: &serde_json::Value,
-    server_name: &str,
-) -> Option<McpServerConfig> {
-    // Try different possible locations
-    if let Some(mcp) = json.get("mcp") {
-        if let Some(servers) = mcp.get("servers") {
-            if let Some(server) = servers.get(server_name) {
-                return serde_json::from_value(server.clone()).ok();
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn server(name: &str) -> McpServerConfig {
+        McpServerConfig {
+            name: name.to_string(),
+            command: "node".to_string(),
+            args: Some(vec!["server.js".to_string()]),
+            env: None,
+            url: None,
+            headers: None,
         }
     }
 
-    if let Some(servers) = json.get("servers") {
-        if let Some(server) = servers.get(server_name) {
-            return serde_json::from_value(server.clone()).ok();
+    fn function(name: &str, server_name: &str) -> McpFunction {
+        McpFunction {
+            name: name.to_string(),
+            description: None,
+            parameters: Vec::new(),
+            server_name: server_name.to_string(),
+            server_provider: "custom".to_string(),
+            input_schema: None,
         }
     }
 
-    None
-}
+    #[test]
+    fn cached_tools_are_returned_until_they_expire() {
+        let fresh = server("cache-fresh");
+        assert!(get_cached_tools(&fresh).is_none());
+
+        cache_tools(&fresh, vec![function("search", "cache-fresh")]);
+        let cached = get_cached_tools(&fresh).unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].name, "search");
+
+        // Same server with other arguments is another cache entry
+        let mut other = server("cache-fresh");
+        other.args = Some(vec!["other.js".to_string()]);
+        assert!(get_cached_tools(&other).is_none());
+
+        let stale = server("cache-stale");
+        let listed_at = Instant::now() - TOOLS_CACHE_TTL - Duration::from_secs(1);
+        tools_cache().lock().unwrap().insert(
+            server_cache_key(&stale),
+            (listed_at, vec![function("search", "cache-stale")]),
+        );
+        assert!(get_cached_tools(&stale).is_none());
+    }
 
-fn remove_server_from_config(
-    json: &mut serde_json::Value,
-    server_name: &str,
-) -> bool {
-    // Try to remove from mcp object (OpenCode format)
-    if let Some(mcp) = json.get_mut("mcp") {
-        if let Some(obj) = mcp.as_object_mut() {
-            if obj.remove(server_name).is_some() {
-                return true;
-            }
+    #[test]
+    fn parses_every_client_config_shape() {
+        let cases = [
+            (
+                "command string with args and env",
+                json!({ "mcpServers": { "fs": {
+                    "command": "npx",
+                    "args": ["-y", "server-fs"],
+                    "env": { "ROOT": "/tmp" }
+                } } }),
+                json!({ "name": "fs", "command": "npx", "args": ["-y", "server-fs"],
+                        "env": { "ROOT": "/tmp" }, "url": null, "headers": null }),
+            ),
+            (
+                "command array with environment (opencode)",
+                json!({ "mcp": { "oc": {
+                    "type": "local",
+                    "command": ["bun", "run", "server.ts"],
+                    "environment": { "DEBUG": "1" }
+                } } }),
+                json!({ "name": "oc", "command": "bun", "args": ["run", "server.ts"],
+                        "env": { "DEBUG": "1" }, "url": null, "headers": null }),
+            ),
+            (
+                "command array with extra args",
+                json!({ "mcpServers": { "mixed": { "command": ["uvx", "srv"], "args": ["--verbose"] } } }),
+                json!({ "name": "mixed", "command": "uvx", "args": ["srv", "--verbose"],
+                        "env": null, "url": null, "headers": null }),
+            ),
+            (
+                "url with headers",
+                json!({ "mcpServers": { "remote": {
+                    "type": "http",
+                    "url": "http://127.0.0.1:3030/mcp",
+                    "headers": { "Authorization": "Bearer jn_x" }
+                } } }),
+                json!({ "name": "remote", "command": "", "args": null, "env": null,
+                        "url": "http://127.0.0.1:3030/mcp",
+                        "headers": { "Authorization": "Bearer jn_x" } }),
+            ),
+            (
+                "serverUrl (Windsurf)",
+                json!({ "mcpServers": { "ws": { "serverUrl": "https://example.com/mcp" } } }),
+                json!({ "name": "ws", "command": "", "args": null, "env": null,
+                        "url": "https://example.com/mcp", "headers": null }),
+            ),
+            (
+                "httpUrl (Gemini CLI)",
+                json!({ "mcpServers": { "gem": { "httpUrl": "https://example.com/mcp" } } }),
+                json!({ "name": "gem", "command": "", "args": null, "env": null,
+                        "url": "https://example.com/mcp", "headers": null }),
+            ),
+            (
+                "flat amp.mcpServers key",
+                json!({ "amp.mcpServers": { "amp": { "command": "amp-server" } } }),
+                json!({ "name": "amp", "command": "amp-server", "args": null,
+                        "env": null, "url": null, "headers": null }),
+            ),
+            (
+                "VS Code settings mcp.servers",
+                json!({ "mcp": { "servers": { "vs": { "command": "node", "args": ["a.js"] } } } }),
+                json!({ "name": "vs", "command": "node", "args": ["a.js"],
+                        "env": null, "url": null, "headers": null }),
+            ),
+            (
+                "VS Code workspace servers",
+                json!({ "servers": { "ws-file": { "type": "stdio", "command": "node" } } }),
+                json!({ "name": "ws-file", "command": "node", "args": null,
+                        "env": null, "url": null, "headers": null }),
+            ),
+        ];
+
+        for (shape, config, expected) in cases {
+            let servers = extract_mcp_servers(&config);
+            assert_eq!(servers.len(), 1, "{}", shape);
+            assert_eq!(
+                serde_json::to_value(&servers[0]).unwrap(),
+                expected,
+                "{}",
+                shape
+            );
         }
     }
 
-    // Try to remove from servers object
-    if let Some(servers) = json.get_mut("servers") {
-        if let Some(obj) = servers.as_object_mut() {
-            if obj.remove(server_name).is_some() {
-                return true;
-            }
-        }
+    #[test]
+    fn skips_entries_without_a_command_or_url() {
+        let config = json!({ "mcpServers": {
+            "empty": {},
+            "empty-array": { "command": [] },
+            "not-an-object": "npx server",
+            "ok": { "command": "npx" }
+        } });
+
+        let names: Vec<String> = extract_mcp_servers(&config)
+            .into_iter()
+            .map(|server| server.name)
+            .collect();
+        assert_eq!(names, ["ok"]);
     }
 
-    // Try to remove from top level
-    if let Some(obj) = json.as_object_mut() {
-        if obj.remove(server_name).is_some() {
-            return true;
-        }
+    #[test]
+    fn discovery_reason_needs_mcp_in_the_name_or_a_client_directory() {
+        let reason = |path: &str| {
+            let path = Path::new(path);
+            discovery_reason(path, &path.file_name().unwrap().to_string_lossy())
+        };
+
+        assert_eq!(
+            reason("/home/u/tools/My-MCP.json").as_deref(),
+            Some("File name contains 'mcp'")
+        );
+        assert_eq!(
+            reason("/home/u/.gemini/settings.json").as_deref(),
+            Some("settings.json in a '.gemini' directory")
+        );
+        assert_eq!(
+            reason("/home/u/Claude/config.json").as_deref(),
+            Some("config.json in a 'claude' directory")
+        );
+        assert_eq!(reason("/home/u/project/settings.json"), None);
+        assert_eq!(reason("/home/u/.claude/mcp.yaml"), None);
     }
 
-    false
-}
+    fn discovery_scanner(max_depth: usize, max_file_bytes: u64) -> ConfigScanner {
+        ConfigScanner::new(&McpScanSettings {
+            max_depth,
+            max_file_bytes,
+            timeout_ms: 60_000,
+            ..McpScanSettings::default()
+        })
+    }
 
-fn add_server_to_config(
-    json: &mut serde_json::Value,
-    server_name: &str,
-    server_config: serde_json::Value,
-) -> bool {
-    // Try to add directly to mcp object (OpenCode format)
-    if let Some(mcp) = json.get_mut("mcp") {
-        if let Some(obj) = mcp.as_object_mut() {
-            obj.insert(server_name.to_string(), server_config);
-            return true;
-        }
-    } else {
-        // Create mcp object if it doesn't exist
-        if let Some(obj) = json.as_object_mut() {
-            let mut mcp_obj = serde_json::Map::new();
-            mcp_obj.insert(server_name.to_string(), server_config);
-            obj.insert("mcp".to_string(), serde_json::Value::Object(mcp_obj));
-            return true;
-        }
+    fn write_config(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
     }
 
-    // Fallback: Try top-level servers (some MCP clients)
-    if let Some(servers) = json.get_mut("servers") {
-        if let Some(obj) = servers.as_object_mut() {
-            obj.insert(server_name.to_string(), server_config);
-            return true;
-        }
-    } else {
-        // Try to add directly at top level (alternative format)
-        if let Some(obj) = json.as_object_mut() {
-            // Check if this looks like a config that expects servers at top level
-            let has_existing_servers = obj.keys().any(|k| k == "mcp" || k == "servers" || k == "mcpServers");
-            if !has_existing_servers {
-                obj.insert(server_name.to_string(), server_config);
-                return true;
-            } else {
-                // Create top-level servers object
-                let mut servers = serde_json::Map::new();
-                servers.insert(server_name.to_string(), server_config);
-                obj.insert("servers".to_string(), serde_json::Value::Object(servers));
-                return true;
-            }
-        }
+    const CONFIG: &str = r#"{ "mcpServers": { "found": { "command": "npx" } } }"#;
+
+    fn found_paths(scanner: &ConfigScanner, root: &Path) -> Vec<String> {
+        let mut paths: Vec<String> = scanner
+            .results
+            .iter()
+            .map(|result| {
+                Path::new(&result.config_path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        paths.sort();
+        paths
     }
 
-    false
+    #[test]
+    fn discovery_stops_at_the_depth_limit() {
+        let root = tempfile::tempdir().unwrap();
+        write_config(&root.path().join("mcp.json"), CONFIG);
+        write_config(&root.path().join("a/mcp.json"), CONFIG);
+        write_config(&root.path().join("a/b/mcp.json"), CONFIG);
+
+        let mut scanner = discovery_scanner(1, 1024);
+        scanner.discover(&[root.path().to_path_buf()]);
+
+        assert_eq!(
+            found_paths(&scanner, root.path()),
+            ["a/mcp.json", "mcp.json"]
+        );
+        assert_eq!(scanner.results[0].mcp_servers[0].name, "found");
+    }
+
+    #[test]
+    fn discovery_skips_cache_and_dependency_directories() {
+        let root = tempfile::tempdir().unwrap();
+        write_config(&root.path().join("node_modules/pkg/mcp.json"), CONFIG);
+        write_config(&root.path().join(".git/mcp.json"), CONFIG);
+        write_config(&root.path().join("project/mcp.json"), CONFIG);
+
+        let mut scanner = discovery_scanner(3, 1024);
+        scanner.discover(&[root.path().to_path_buf()]);
+
+        assert_eq!(found_paths(&scanner, root.path()), ["project/mcp.json"]);
+    }
+
+    #[test]
+    fn discovery_reports_files_over_the_size_limit_and_ignores_unrelated_json() {
+        let root = tempfile::tempdir().unwrap();
+        let padding = " ".repeat(64);
+        write_config(
+            &root.path().join("big-mcp.json"),
+            &format!("{}{}", CONFIG, padding),
+        );
+        write_config(
+            &root.path().join("other-mcp.json"),
+            r#"{ "theme": "dark" }"#,
+        );
+
+        let mut scanner = discovery_scanner(3, CONFIG.len() as u64);
+        scanner.discover(&[root.path().to_path_buf()]);
+
+        assert_eq!(found_paths(&scanner, root.path()), ["big-mcp.json"]);
+        let error = scanner.results[0].error.as_deref().unwrap();
+        assert!(error.starts_with("Skipped: "), "{}", error);
+        assert!(scanner.results[0].mcp_servers.is_empty());
+    }
 }
@@ -1,10 +1,24 @@
 pub mod bulk_commands;
 pub mod mcp_commands;
 pub mod note_commands;
+pub mod semantic_commands;
+pub mod server_commands;
+pub mod settings_commands;
 pub mod state_commands;
+pub mod sync_commands;
+pub mod token_commands;
+pub mod tool_commands;
+pub mod webhook_commands;
 
 // Re-export all command functions for easy access
 pub use bulk_commands::*;
 pub use mcp_commands::*;
 pub use note_commands::*;
-pub use state_commands::*;
\ No newline at end of file
+pub use semantic_commands::*;
+pub use server_commands::*;
+pub use settings_commands::*;
+pub use state_commands::*;
+pub use sync_commands::*;
+pub use token_commands::*;
+pub use tool_commands::*;
+pub use webhook_commands::*;
\ No newline at end of file
@@ -1,21 +1,50 @@
-use crate::database::{establish_connection, run_migrations, DbConnection};
+use crate::database::{self, run_migrations, search_index};
+use crate::events;
 use crate::models::*;
-use chrono::{DateTime, Utc};
+use crate::repository::{NoteRepository, SqliteRepository, UpdateOutcome};
+use crate::settings;
 use rusqlite::Result;
-use serde_json;
-use std::sync::OnceLock;
-
-static DB_CONNECTION: OnceLock<DbConnection> = OnceLock::new();
-
-fn get_db_connection() -> &'static DbConnection {
-    DB_CONNECTION
-        .get_or_init(|| establish_connection().expect("Failed to establish database connection"))
-}
+use sha2::{Digest, Sha256};
+
+const DEFAULT_PAGE_SIZE: i32 = 100;
+const MAX_PAGE_SIZE: i32 = 500;
+
+// Fields of a serialized `Note` that `list_notes` can project to
+const NOTE_FIELDS: &[&str] = &[
+    "id",
+    "title",
+    "content",
+    "created_at",
+    "updated_at",
+    "priority",
+    "labels",
+    "deadline",
+    "reminder_minutes",
+    "done",
+    "state_id",
+    "order",
+    "section",
+    "change_seq",
+    "version",
+];
 
 #[tauri::command]
 pub fn initialize_db() -> Result<NotesListResponse, String> {
-    let conn = get_db_connection();
-    run_migrations(conn).map_err(|e| e.to_string())?;
+    let mut conn = database::writer()?;
+    run_migrations(&mut conn)?;
+
+    // A database restored from a backup or written by another tool can come
+    // with an index that doesn't match its notes
+    let problem =
+        search_index::check(&conn).map_err(|e| format!("Failed to check search index: {}", e))?;
+    if let Some(problem) = problem {
+        eprintln!(
+            "Search index doesn't match the notes ({}), rebuilding",
+            problem
+        );
+        search_index::rebuild(&conn)
+            .map_err(|e| format!("Failed to rebuild search index: {}", e))?;
+    }
 
     // Return empty list on successful initialization
     Ok(NotesListResponse {
@@ -27,388 +56,230 @@ pub fn initialize_db() -> Result<NotesListResponse, String> {
 
 #[tauri::command]
 pub fn create_note(request: CreateNoteRequest) -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let labels_json = serde_json::to_string(&request.labels.unwrap_or_default())
-        .map_err(|e| format!("Failed to serialize labels: {}", e))?;
-
-    let now = Utc::now().timestamp();
-
-    conn.execute(
-        "INSERT INTO notes (title, content, created_at, updated_at, priority, labels, deadline, reminder_minutes, done, state_id, \"order\", section)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-        rusqlite::params![
-            request.title,
-            request.content,
-            now,
-            now,
-            request.priority.unwrap_or(0),
-            labels_json,
-            request.deadline.map(|dt| dt.timestamp()),
-            request.reminder_minutes.unwrap_or(0),
-            request.done.unwrap_or(false) as i32,
-            request.state_id,
-            request.order.unwrap_or(0),
-            request.section.unwrap_or_else(|| "unset".to_string())
-        ],
-    ).map_err(|e| format!("Failed to create note: {}", e))?;
-
-    let id = conn.last_insert_rowid();
-
-    // Retrieve the created note
-    get_note_sync(id, &conn)
+    let conn = database::writer()?;
+
+    let note = SqliteRepository::new(&conn).create_note(&request)?;
+    events::publish_note(ChangeOperation::Created, &note);
+
+    Ok(note_response(Some(note)))
 }
 
 #[tauri::command]
 pub fn get_note(id: i64) -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-    get_note_sync(id, &conn)
+    let conn = database::reader()?;
+
+    Ok(note_response(SqliteRepository::new(&conn).get_note(id)?))
 }
 
 #[tauri::command]
 pub fn get_all_notes() -> Result<NotesListResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let mut stmt = conn.prepare(
-        "SELECT id, title, content, created_at, updated_at, priority, labels, deadline, reminder_minutes, done, state_id, \"order\", section
-         FROM notes ORDER BY \"order\" ASC, created_at DESC"
-    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
-
-    let note_iter = stmt.query_map([], |row| {
-        let labels_json: String = row.get(6)?;
-        let labels: Vec<String> = serde_json::from_str(&labels_json).unwrap_or_default();
-
-        Ok(Note {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            content: row.get(2)?,
-            created_at: Some(DateTime::from_timestamp(row.get(3)?, 0).unwrap_or_default()),
-            updated_at: Some(DateTime::from_timestamp(row.get(4)?, 0).unwrap_or_default()),
-            priority: row.get(5)?,
-            labels,
-            deadline: match row.get::<_, Option<i64>>(7)? {
-                Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-                None => None,
-            },
-            reminder_minutes: row.get(8)?,
-            done: row.get::<_, i32>(9)? != 0,
-            state_id: row.get(10)?,
-            order: row.get(11)?,
-            section: row.get(12)?,
-        })
-    }).map_err(|e| format!("Failed to query notes: {}", e))?;
-
-    let mut notes = Vec::new();
-    for note in note_iter {
-        notes.push(note.map_err(|e| format!("Failed to read note: {}", e))?);
-    }
+    let conn = database::reader()?;
 
     Ok(NotesListResponse {
         success: true,
-        data: notes,
+        data: SqliteRepository::new(&conn).list_notes()?,
         error: None,
     })
 }
 
+/// A page of the note list. List views can leave out `content` and fetch it
+/// with `get_note` when a note is opened.
 #[tauri::command]
-pub fn update_note(request: UpdateNoteRequest) -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let now = Utc::now().timestamp();
-
-    // Build dynamic update query
-    let mut set_parts = Vec::new();
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-    if let Some(title) = &request.title {
-        set_parts.push("title = ?".to_string());
-        params.push(Box::new(title.clone()));
-    }
-    if let Some(content) = &request.content {
-        set_parts.push("content = ?".to_string());
-        params.push(Box::new(content.clone()));
-    }
-    if let Some(priority) = request.priority {
-        set_parts.push("priority = ?".to_string());
-        params.push(Box::new(priority));
-    }
-    if let Some(labels) = &request.labels {
-        let labels_json = serde_json::to_string(labels)
-            .map_err(|e| format!("Failed to serialize labels: {}", e))?;
-        set_parts.push("labels = ?".to_string());
-        params.push(Box::new(labels_json));
+pub fn list_notes(request: ListNotesRequest) -> Result<NotesPageResponse, String> {
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE) as usize;
+    if let Some(unknown) = request
+        .fields
+        .iter()
+        .flatten()
+        .find(|field| !NOTE_FIELDS.contains(&field.as_str()))
+    {
+        return Err(format!(
+            "Unknown note field '{}', expected one of: {}",
+            unknown,
+            NOTE_FIELDS.join(", ")
+        ));
     }
-    if let Some(deadline) = &request.deadline {
-        set_parts.push("deadline = ?".to_string());
-        params.push(Box::new(deadline.timestamp()));
-    }
-    if let Some(reminder_minutes) = request.reminder_minutes {
-        set_parts.push("reminder_minutes = ?".to_string());
-        params.push(Box::new(reminder_minutes));
-    }
-    if let Some(done) = request.done {
-        set_parts.push("done = ?".to_string());
-        params.push(Box::new(done as i32));
-    }
-    if let Some(state_id) = request.state_id {
-        set_parts.push("state_id = ?".to_string());
-        params.push(Box::new(state_id));
-    }
-    if let Some(order) = request.order {
-        set_parts.push("\"order\" = ?".to_string());
-        params.push(Box::new(order));
-    }
-    if let Some(section) = &request.section {
-        set_parts.push("section = ?".to_string());
-        params.push(Box::new(section.clone()));
-    }
-
-    set_parts.push("updated_at = ?".to_string());
-    params.push(Box::new(now));
-
-    if set_parts.is_empty() {
-        return Err("No fields to update".to_string());
+    let with_content = request
+        .fields
+        .as_ref()
+        .is_none_or(|fields| fields.iter().any(|field| field == "content"));
+
+    let conn = database::reader()?;
+    let page = SqliteRepository::new(&conn).list_notes_page(
+        request.cursor.as_deref(),
+        limit,
+        with_content,
+    )?;
+
+    let mut data = Vec::new();
+    for note in page.notes {
+        let mut note =
+            serde_json::to_value(note).map_err(|e| format!("Failed to serialize note: {}", e))?;
+        if let (Some(fields), Some(note)) = (&request.fields, note.as_object_mut()) {
+            note.retain(|field, _| field == "id" || fields.contains(field));
+        }
+        data.push(note);
     }
 
-    let query = format!("UPDATE notes SET {} WHERE id = ?", set_parts.join(", "));
-    params.push(Box::new(request.id));
+    // Hash of everything a client would get, so a page reads as changed
+    // exactly when its response body would be different
+    let body = serde_json::to_vec(&(&data, page.total_count, &page.next_cursor))
+        .map_err(|e| format!("Failed to serialize page: {}", e))?;
+    let etag = hex::encode(&Sha256::digest(&body)[..16]);
 
-    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    Ok(NotesPageResponse {
+        success: true,
+        data,
+        total_count: page.total_count,
+        next_cursor: page.next_cursor,
+        etag: Some(etag),
+        error: None,
+    })
+}
 
-    conn.execute(&query, &param_refs[..])
-        .map_err(|e| format!("Failed to update note: {}", e))?;
+#[tauri::command]
+pub fn update_note(request: UpdateNoteRequest) -> Result<NoteResponse, String> {
+    let conn = database::writer()?;
+    let notes = SqliteRepository::new(&conn);
+
+    let previous_state_id = notes.get_note(request.id)?.and_then(|note| note.state_id);
+    let note = match notes.update_note(&request)? {
+        UpdateOutcome::Updated(note) => note,
+        UpdateOutcome::NotFound => return Ok(note_response(None)),
+        // The current note goes back so the caller can merge and retry
+        UpdateOutcome::Conflict(current) => {
+            return Ok(NoteResponse {
+                success: false,
+                error: Some(format!(
+                    "Conflict: note {} is at version {}, not {}",
+                    request.id,
+                    current.version,
+                    request.expected_version.unwrap_or_default()
+                )),
+                data: Some(current),
+            })
+        }
+    };
+    events::publish_note(ChangeOperation::Updated, &note);
+    if note.state_id != previous_state_id {
+        events::publish_note(ChangeOperation::StateChanged, &note);
+    }
 
-    // Retrieve the updated note
-    get_note_sync(request.id, &conn)
+    Ok(note_response(Some(note)))
 }
 
 #[tauri::command]
 pub fn delete_note(request: DeleteNoteRequest) -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
-    // First get the note to return it
-    let note = get_note_sync(request.id, &conn)?;
-
-    // Delete the note
-    conn.execute("DELETE FROM notes WHERE id = ?", [request.id])
-        .map_err(|e| format!("Failed to delete note: {}", e))?;
+    let note = SqliteRepository::new(&conn).delete_note(request.id)?;
+    if let Some(deleted) = &note {
+        events::publish_note(ChangeOperation::Deleted, deleted);
+    }
 
-    Ok(note)
+    Ok(note_response(note))
 }
 
 #[tauri::command]
-pub fn search_notes(request: SearchRequest) -> Result<NotesListResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let limit = request.limit.unwrap_or(50).min(1000); // Cap at 1000
-    let offset = request.offset.unwrap_or(0);
-
-    let (query, params) = if request.query.is_empty() {
-        // Simple query without search
-        let sql = format!(
-            "SELECT id, title, content, created_at, updated_at, priority, labels, deadline, reminder_minutes, done, state_id, \"order\", section
-             FROM notes ORDER BY \"order\" ASC, created_at DESC LIMIT {} OFFSET {}",
-            limit, offset
-        );
-        (sql, Vec::new())
-    } else {
-        // Use FTS search
-        perform_fts_search(&conn, &request.query, limit as i64, offset as i64)
-    };
+pub fn search_notes(request: SearchRequest) -> Result<SearchNotesResponse, String> {
+    let conn = database::reader()?;
 
-    let mut stmt = conn.prepare(&query)
-        .map_err(|e| format!("Failed to prepare search statement: {}", e))?;
-
-    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
-
-    let note_iter = stmt.query_map(&param_refs[..], |row| {
-        let labels_json: String = row.get(6)?;
-        let labels: Vec<String> = serde_json::from_str(&labels_json).unwrap_or_default();
-
-        Ok(Note {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            content: row.get(2)?,
-            created_at: Some(DateTime::from_timestamp(row.get(3)?, 0).unwrap_or_default()),
-            updated_at: Some(DateTime::from_timestamp(row.get(4)?, 0).unwrap_or_default()),
-            priority: row.get(5)?,
-            labels,
-            deadline: match row.get::<_, Option<i64>>(7)? {
-                Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-                None => None,
-            },
-            reminder_minutes: row.get(8)?,
-            done: row.get::<_, i32>(9)? != 0,
-            state_id: row.get(10)?,
-            order: row.get(11)?,
-            section: row.get(12)?,
-        })
-    }).map_err(|e| format!("Failed to execute search: {}", e))?;
-
-    let mut notes = Vec::new();
-    for note in note_iter {
-        notes.push(note.map_err(|e| format!("Failed to read search result: {}", e))?);
-    }
+    let results =
+        SqliteRepository::new(&conn).search_notes(&request, &settings::current().search_weights)?;
 
-    Ok(NotesListResponse {
+    Ok(SearchNotesResponse {
         success: true,
-        data: notes,
+        data: results.notes,
+        suggestion: results.suggestion,
         error: None,
     })
 }
 
 #[tauri::command]
 pub fn update_note_done(request: UpdateNoteDoneRequest) -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
-    let now = Utc::now().timestamp();
-
-    conn.execute(
-        "UPDATE notes SET done = ?, updated_at = ? WHERE id = ?",
-        rusqlite::params![request.done as i32, now, request.id],
-    ).map_err(|e| format!("Failed to update note done status: {}", e))?;
+    let note = SqliteRepository::new(&conn).set_note_done(request.id, request.done)?;
+    if let Some(note) = &note {
+        events::publish_note(ChangeOperation::Updated, note);
+    }
 
-    // Retrieve the updated note
-    get_note_sync(request.id, &conn)
+    Ok(note_response(note))
 }
 
 #[tauri::command]
 pub fn reorder_note(request: ReorderNoteRequest) -> Result<NoteResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let now = Utc::now().timestamp();
-
-    // Update the note's order and optionally its state
-    let query = if let Some(state_id) = request.state_id {
-        "UPDATE notes SET \"order\" = ?, state_id = ?, updated_at = ? WHERE id = ?"
-    } else {
-        "UPDATE notes SET \"order\" = ?, updated_at = ? WHERE id = ?"
-    };
-
-    let result = if let Some(state_id) = request.state_id {
-        conn.execute(
-            query,
-            rusqlite::params![request.new_order, state_id, now, request.note_id],
-        )
-    } else {
-        conn.execute(
-            query,
-            rusqlite::params![request.new_order, now, request.note_id],
-        )
-    };
-
-    result.map_err(|e| format!("Failed to reorder note: {}", e))?;
+    let conn = database::writer()?;
+    let notes = SqliteRepository::new(&conn);
+
+    let previous_state_id = notes
+        .get_note(request.note_id)?
+        .and_then(|note| note.state_id);
+    let note = notes.reorder_note(&request)?;
+    if let Some(note) = &note {
+        events::publish_note(ChangeOperation::Updated, note);
+        if note.state_id != previous_state_id {
+            events::publish_note(ChangeOperation::StateChanged, note);
+        }
+    }
 
-    // Retrieve the updated note
-    get_note_sync(request.note_id, &conn)
+    Ok(note_response(note))
 }
 
-fn get_note_sync(id: i64, conn: &rusqlite::Connection) -> Result<NoteResponse, String> {
-    let mut stmt = conn.prepare(
-        "SELECT id, title, content, created_at, updated_at, priority, labels, deadline, reminder_minutes, done, state_id, \"order\", section
-         FROM notes WHERE id = ?"
-    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
-
-    let mut rows = stmt.query_map([id], |row| {
-        let labels_json: String = row.get(6)?;
-        let labels: Vec<String> = serde_json::from_str(&labels_json).unwrap_or_default();
-
-        Ok(Note {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            content: row.get(2)?,
-            created_at: Some(DateTime::from_timestamp(row.get(3)?, 0).unwrap_or_default()),
-            updated_at: Some(DateTime::from_timestamp(row.get(4)?, 0).unwrap_or_default()),
-            priority: row.get(5)?,
-            labels,
-            deadline: match row.get::<_, Option<i64>>(7)? {
-                Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-                None => None,
-            },
-            reminder_minutes: row.get(8)?,
-            done: row.get::<_, i32>(9)? != 0,
-            state_id: row.get(10)?,
-            order: row.get(11)?,
-            section: row.get(12)?,
-        })
-    }).map_err(|e| format!("Failed to query note: {}", e))?;
-
-    match rows.next() {
-        Some(row) => {
-            let note = row.map_err(|e| format!("Failed to read note: {}", e))?;
-            Ok(NoteResponse {
-                success: true,
-                data: Some(note),
-                error: None,
-            })
-        }
-        None => Ok(NoteResponse {
+fn note_response(note: Option<Note>) -> NoteResponse {
+    match note {
+        Some(note) => NoteResponse {
+            success: true,
+            data: Some(note),
+            error: None,
+        },
+        None => NoteResponse {
             success: false,
             data: None,
             error: Some("Note not found".to_string()),
-        }),
+        },
     }
 }
 
-fn perform_fts_search(
-    conn: &rusqlite::Connection,
-    query: &str,
-    limit: i64,
-    offset: i64,
-) -> (String, Vec<String>) {
-    let enhanced_query = enhance_search_query(query);
-
-    // Use FTS5 virtual table for full-text search
-    let sql = format!(
-        "SELECT n.id, n.title, n.content, n.created_at, n.updated_at, n.priority, n.labels, n.deadline, n.reminder_minutes, n.done, n.state_id, n.\"order\", n.section
-         FROM notes n
-         JOIN notes_fts fts ON n.id = fts.rowid
-         WHERE fts.notes_fts MATCH ?
-         ORDER BY rank, n.\"order\" ASC, n.created_at DESC
-         LIMIT {} OFFSET {}",
-        limit, offset
-    );
-
-    (sql, vec![enhanced_query])
+/// Run FTS5's integrity check on the search index.
+#[tauri::command]
+pub fn check_search_index() -> Result<SearchIndexResponse, String> {
+    search_index_status(false)
 }
 
-fn perform_like_search(
-    conn: &rusqlite::Connection,
-    query: &str,
-    limit: i64,
-    offset: i64,
-) -> (String, Vec<String>) {
-    let like_pattern = format!("%{}%", query);
-
-    let sql = format!(
-        "SELECT id, title, content, created_at, updated_at, priority, labels, deadline, reminder_minutes, done, state_id, \"order\", section
-         FROM notes
-         WHERE title LIKE ? OR content LIKE ?
-         ORDER BY \"order\" ASC, created_at DESC
-         LIMIT {} OFFSET {}",
-        limit, offset
-    );
-
-    (sql, vec![like_pattern.clone(), like_pattern])
+/// Rebuild the search index from the notes, then check it.
+#[tauri::command]
+pub fn rebuild_search_index() -> Result<SearchIndexResponse, String> {
+    search_index_status(true)
 }
 
-fn enhance_search_query(query: &str) -> String {
-    // Enhance the search query for better FTS results
-    let words: Vec<&str> = query.split_whitespace().collect();
+fn search_index_status(rebuild: bool) -> Result<SearchIndexResponse, String> {
+    // FTS5's integrity check is run through an INSERT, so even checking needs the writer
+    let conn = database::writer()?;
+    let started_at = std::time::Instant::now();
 
-    if words.len() == 1 {
-        // Single word - use prefix search
-        format!("{}*", words[0])
-    } else {
-        // Multiple words - use AND operator
-        words.iter().map(|word| format!("{}*", word)).collect::<Vec<_>>().join(" AND ")
+    if rebuild {
+        search_index::rebuild(&conn)
+            .map_err(|e| format!("Failed to rebuild search index: {}", e))?;
     }
-}
\ No newline at end of file
+    let problem =
+        search_index::check(&conn).map_err(|e| format!("Failed to check search index: {}", e))?;
+    let note_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count notes: {}", e))?;
+
+    Ok(SearchIndexResponse {
+        success: true,
+        data: Some(SearchIndexStatus {
+            healthy: problem.is_none(),
+            problem,
+            rebuilt: rebuild,
+            note_count,
+            duration_ms: started_at.elapsed().as_millis() as u64,
+        }),
+        error: None,
+    })
+}
@@ -0,0 +1,283 @@
+use crate::database;
+use crate::embeddings;
+use crate::models::*;
+use crate::repository::{NoteRepository, SqliteRepository};
+use rusqlite::Result;
+
+// Notes embedded per model call
+const EMBEDDING_BATCH_SIZE: usize = 16;
+
+// Below this similarity a note is unlikely to have anything to do with the query
+const DEFAULT_MIN_SCORE: f32 = 0.25;
+
+/// Notes ranked by how close their meaning is to the query. The model runs
+/// on the blocking thread pool.
+#[tauri::command]
+pub async fn semantic_search(
+    request: SemanticSearchRequest,
+) -> Result<SemanticSearchResponse, String> {
+    tokio::task::spawn_blocking(move || search_by_meaning(request))
+        .await
+        .map_err(|e| format!("Semantic search failed: {}", e))?
+}
+
+/// Notes closest in meaning to the given note.
+#[tauri::command]
+pub async fn similar_notes(request: SimilarNotesRequest) -> Result<SemanticSearchResponse, String> {
+    tokio::task::spawn_blocking(move || find_similar_notes(request))
+        .await
+        .map_err(|e| format!("Similar notes search failed: {}", e))?
+}
+
+pub(crate) fn search_by_meaning(
+    request: SemanticSearchRequest,
+) -> Result<SemanticSearchResponse, String> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Err("Query must not be empty".to_string());
+    }
+
+    let vector = embeddings::embed(&[query.to_string()])?
+        .pop()
+        .ok_or("No embedding was computed for the query")?;
+
+    Ok(SemanticSearchResponse {
+        success: true,
+        data: rank_notes(&vector, None, request.limit, request.min_score)?,
+        error: None,
+    })
+}
+
+pub(crate) fn find_similar_notes(
+    request: SimilarNotesRequest,
+) -> Result<SemanticSearchResponse, String> {
+    let vector = note_vector(request.id)?;
+
+    Ok(SemanticSearchResponse {
+        success: true,
+        data: rank_notes(&vector, Some(request.id), request.limit, request.min_score)?,
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_embedding_status() -> Result<EmbeddingStatusResponse, String> {
+    let conn = database::reader()?;
+
+    let model = embeddings::model_name();
+    let model_dir = embeddings::model_dir();
+    let total_notes: i64 = conn
+        .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count notes: {}", e))?;
+    let embedded_notes: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM note_embeddings WHERE model = ?",
+            [&model],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count embeddings: {}", e))?;
+
+    Ok(EmbeddingStatusResponse {
+        success: true,
+        data: Some(EmbeddingStatus {
+            available: embeddings::available(),
+            model,
+            model_dir: model_dir.to_string_lossy().to_string(),
+            missing_files: embeddings::MODEL_FILES
+                .iter()
+                .filter(|file| !model_dir.join(file).is_file())
+                .map(|file| file.to_string())
+                .collect(),
+            embedded_notes,
+            total_notes,
+        }),
+        error: (!embeddings::available()).then(|| embeddings::NOT_AVAILABLE.to_string()),
+    })
+}
+
+/// Embed every note whose vector is missing or out of date, e.g. after the
+/// model files were put in place.
+#[tauri::command]
+pub async fn reindex_embeddings() -> Result<EmbeddingStatusResponse, String> {
+    tokio::task::spawn_blocking(|| index_notes(None))
+        .await
+        .map_err(|e| format!("Embedding task failed: {}", e))??;
+
+    get_embedding_status()
+}
+
+/// Embed the given notes, or all notes, whose text changed since they were
+/// embedded or that were embedded by another model. The database isn't locked
+/// while the model runs. Returns how many notes were embedded.
+pub(crate) fn index_notes(note_ids: Option<&[i64]>) -> Result<usize, String> {
+    let model = embeddings::model_name();
+    let stale = stale_notes(note_ids, &model)?;
+
+    for batch in stale.chunks(EMBEDDING_BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|(_, text, _)| text.clone()).collect();
+        let vectors = embeddings::embed(&texts)?;
+
+        let conn = database::writer()?;
+        for ((id, _, hash), vector) in batch.iter().zip(vectors) {
+            store_embedding(&conn, *id, &model, hash, &vector)?;
+        }
+    }
+
+    Ok(stale.len())
+}
+
+// (id, text, hash) of the notes that need embedding
+fn stale_notes(
+    note_ids: Option<&[i64]>,
+    model: &str,
+) -> Result<Vec<(i64, String, String)>, String> {
+    let conn = database::reader()?;
+
+    let mut sql = "SELECT n.id, n.title, n.content, e.content_hash, e.model
+         FROM notes n LEFT JOIN note_embeddings e ON e.note_id = n.id"
+        .to_string();
+    if let Some(note_ids) = note_ids {
+        let placeholders = vec!["?"; note_ids.len()].join(", ");
+        sql.push_str(&format!(" WHERE n.id IN ({})", placeholders));
+    }
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params_from_iter(note_ids.unwrap_or_default()),
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )
+        .map_err(|e| format!("Failed to query notes: {}", e))?;
+
+    let mut stale = Vec::new();
+    for row in rows {
+        let (id, title, content, stored_hash, stored_model) =
+            row.map_err(|e| format!("Failed to read note: {}", e))?;
+        let text = embeddings::note_text(&title, &content);
+        let hash = embeddings::content_hash(&text);
+        if stored_hash.as_deref() != Some(hash.as_str()) || stored_model.as_deref() != Some(model) {
+            stale.push((id, text, hash));
+        }
+    }
+
+    Ok(stale)
+}
+
+fn store_embedding(
+    conn: &rusqlite::Connection,
+    note_id: i64,
+    model: &str,
+    hash: &str,
+    vector: &[f32],
+) -> Result<(), String> {
+    // The note may have been deleted while it was being embedded
+    conn.execute(
+        "INSERT INTO note_embeddings (note_id, model, content_hash, dimensions, vector, updated_at)
+         SELECT ?1, ?2, ?3, ?4, ?5, strftime('%s', 'now') WHERE EXISTS (SELECT 1 FROM notes WHERE id = ?1)
+         ON CONFLICT(note_id) DO UPDATE SET
+             model = excluded.model,
+             content_hash = excluded.content_hash,
+             dimensions = excluded.dimensions,
+             vector = excluded.vector,
+             updated_at = excluded.updated_at",
+        rusqlite::params![
+            note_id,
+            model,
+            hash,
+            vector.len() as i64,
+            embeddings::to_blob(vector)
+        ],
+    )
+    .map_err(|e| format!("Failed to store embedding: {}", e))?;
+
+    Ok(())
+}
+
+// The note's stored vector, or a freshly computed one if it hasn't been embedded yet
+fn note_vector(id: i64) -> Result<Vec<f32>, String> {
+    let model = embeddings::model_name();
+    let stored: Option<Vec<u8>> = {
+        let conn = database::reader()?;
+
+        if SqliteRepository::new(&conn).get_note(id)?.is_none() {
+            return Err(format!("Note {} not found", id));
+        }
+        conn.query_row(
+            "SELECT vector FROM note_embeddings WHERE note_id = ? AND model = ?",
+            rusqlite::params![id, model],
+            |row| row.get(0),
+        )
+        .ok()
+    };
+
+    if let Some(blob) = stored {
+        return Ok(embeddings::from_blob(&blob));
+    }
+
+    index_notes(Some(&[id]))?;
+
+    let conn = database::reader()?;
+    conn.query_row(
+        "SELECT vector FROM note_embeddings WHERE note_id = ? AND model = ?",
+        rusqlite::params![id, model],
+        |row| row.get::<_, Vec<u8>>(0),
+    )
+    .map(|blob| embeddings::from_blob(&blob))
+    .map_err(|e| format!("Failed to load embedding of note {}: {}", id, e))
+}
+
+// Embedded notes by cosine similarity to `vector`, best first
+fn rank_notes(
+    vector: &[f32],
+    exclude_id: Option<i64>,
+    limit: Option<i32>,
+    min_score: Option<f32>,
+) -> Result<Vec<ScoredNote>, String> {
+    let limit = limit.unwrap_or(10).clamp(1, 100) as usize;
+    let min_score = min_score.unwrap_or(DEFAULT_MIN_SCORE);
+
+    let conn = database::reader()?;
+
+    let mut stmt = conn
+        .prepare("SELECT note_id, vector FROM note_embeddings WHERE model = ?")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map([embeddings::model_name()], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })
+        .map_err(|e| format!("Failed to query embeddings: {}", e))?;
+
+    let mut scored = Vec::new();
+    for row in rows {
+        let (id, blob) = row.map_err(|e| format!("Failed to read embedding: {}", e))?;
+        if Some(id) == exclude_id {
+            continue;
+        }
+        let score = embeddings::cosine_similarity(vector, &embeddings::from_blob(&blob));
+        if score >= min_score {
+            scored.push((score, id));
+        }
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(limit);
+
+    let repository = SqliteRepository::new(&conn);
+    let mut notes = Vec::new();
+    for (score, id) in scored {
+        if let Some(note) = repository.get_note(id)? {
+            notes.push(ScoredNote { note, score });
+        }
+    }
+
+    Ok(notes)
+}
@@ -0,0 +1,59 @@
+use crate::http_server;
+use crate::models::*;
+use crate::settings;
+
+#[tauri::command]
+pub fn check_http_server_status() -> Result<HttpServerStatus, String> {
+    Ok(http_server::http_server_status())
+}
+
+/// The port the HTTP server is listening on, or the one it will try first if it's stopped.
+#[tauri::command]
+pub fn get_server_port() -> Result<u16, String> {
+    let status = http_server::http_server_status();
+    Ok(status.port.unwrap_or(status.configured_port))
+}
+
+#[tauri::command]
+pub async fn start_http_server() -> Result<HttpServerStatus, String> {
+    http_server::start_http_server().await?;
+    Ok(http_server::http_server_status())
+}
+
+#[tauri::command]
+pub async fn stop_http_server() -> Result<HttpServerStatus, String> {
+    http_server::stop_http_server().await?;
+    Ok(http_server::http_server_status())
+}
+
+/// Stop and start the HTTP server, picking up port and body limit changes.
+#[tauri::command]
+pub async fn restart_http_server() -> Result<HttpServerStatus, String> {
+    http_server::stop_http_server().await?;
+    http_server::start_http_server().await?;
+    Ok(http_server::http_server_status())
+}
+
+/// Information MCP clients need to launch the app and wait for its HTTP server.
+#[tauri::command]
+pub fn get_launch_info() -> Result<serde_json::Value, String> {
+    let exe_path = std::env::current_exe()
+        .map_err(|e| format!("Failed to get executable path: {}", e))?
+        .to_string_lossy()
+        .to_string();
+
+    let status = http_server::http_server_status();
+    let port = status.port.unwrap_or(settings::current().server_port);
+
+    Ok(serde_json::json!({
+        "executable_path": exe_path,
+        "arguments": [],
+        "working_directory": std::env::current_dir()
+            .map_err(|e| format!("Failed to get working directory: {}", e))?
+            .to_string_lossy(),
+        "server_running": status.server_running,
+        "server_port": port,
+        "health_check_url": format!("http://localhost:{}/health", port),
+        "startup_timeout_ms": 10000 // 10 seconds to start HTTP server
+    }))
+}
@@ -0,0 +1,149 @@
+use crate::models::*;
+use crate::settings;
+
+#[tauri::command]
+pub fn get_settings() -> Result<SettingsResponse, String> {
+    Ok(SettingsResponse {
+        success: true,
+        data: Some(settings::current()),
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub fn update_settings(request: UpdateSettingsRequest) -> Result<SettingsResponse, String> {
+    if let Some(origins) = &request.allowed_origins {
+        for origin in origins {
+            validate_origin(origin)?;
+        }
+    }
+
+    if let Some(limits) = &request.limits {
+        validate_limits(limits)?;
+    }
+    if request.server_port == Some(0) {
+        return Err("Server port must be greater than zero".to_string());
+    }
+    if let Some(mcp_scan) = &request.mcp_scan {
+        validate_mcp_scan(mcp_scan)?;
+    }
+    if let Some(search_weights) = &request.search_weights {
+        validate_search_weights(search_weights)?;
+    }
+    if let Some(dir) = &request.embedding_model_dir {
+        if !dir.is_empty() && !std::path::Path::new(dir).is_absolute() {
+            return Err(format!(
+                "Embedding model directory must be an absolute path: {}",
+                dir
+            ));
+        }
+    }
+
+    let updated = settings::update(|settings| {
+        if let Some(origins) = request.allowed_origins {
+            settings.allowed_origins = origins
+                .into_iter()
+                .map(|origin| origin.trim_end_matches('/').to_string())
+                .collect();
+        }
+        if let Some(limits) = request.limits {
+            settings.limits = limits;
+        }
+        // Takes effect the next time the HTTP server is (re)started
+        if let Some(port) = request.server_port {
+            settings.server_port = port;
+        }
+        if let Some(mcp_scan) = request.mcp_scan {
+            settings.mcp_scan = mcp_scan;
+        }
+        if let Some(search_weights) = request.search_weights {
+            settings.search_weights = search_weights;
+        }
+        // Notes are re-embedded when the model's name changes
+        if let Some(dir) = request.embedding_model_dir {
+            settings.embedding_model_dir = (!dir.is_empty()).then_some(dir);
+        }
+    })?;
+
+    Ok(SettingsResponse {
+        success: true,
+        data: Some(updated),
+        error: None,
+    })
+}
+
+// Browsers send origins as `scheme://host[:port]`, without a path
+fn validate_origin(origin: &str) -> Result<(), String> {
+    let trimmed = origin.trim_end_matches('/');
+    match reqwest::Url::parse(trimmed) {
+        Ok(url)
+            if url.host_str().is_some()
+                && matches!(url.path(), "" | "/")
+                && url.query().is_none() =>
+        {
+            Ok(())
+        }
+        Ok(_) => Err(format!(
+            "Origin must not contain a path or query: {}",
+            origin
+        )),
+        Err(e) => Err(format!("Invalid origin '{}': {}", origin, e)),
+    }
+}
+
+fn validate_limits(limits: &ApiLimits) -> Result<(), String> {
+    if limits.requests_per_minute_per_token == 0
+        || limits.requests_per_minute_per_client == 0
+        || limits.burst == 0
+    {
+        return Err("Rate limits must be greater than zero".to_string());
+    }
+    if limits.max_body_bytes == 0 || limits.max_bulk_ids == 0 {
+        return Err("Size limits must be greater than zero".to_string());
+    }
+    Ok(())
+}
+
+// Keeps discovery bounded however the settings are edited
+const MAX_MCP_SCAN_DEPTH: usize = 6;
+
+fn validate_mcp_scan(mcp_scan: &McpScanSettings) -> Result<(), String> {
+    if mcp_scan.max_depth > MAX_MCP_SCAN_DEPTH {
+        return Err(format!(
+            "MCP scan depth can be at most {}",
+            MAX_MCP_SCAN_DEPTH
+        ));
+    }
+    if mcp_scan.max_file_bytes == 0 || mcp_scan.timeout_ms == 0 {
+        return Err("MCP scan limits must be greater than zero".to_string());
+    }
+    for path in &mcp_scan.custom_config_paths {
+        if !path.starts_with("~/") && !std::path::Path::new(path).is_absolute() {
+            return Err(format!(
+                "Custom config path must be absolute or start with ~/: {}",
+                path
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_search_weights(weights: &SearchWeights) -> Result<(), String> {
+    let all = [
+        weights.title,
+        weights.labels,
+        weights.content,
+        weights.section,
+        weights.state,
+    ];
+    if all
+        .iter()
+        .any(|weight| !weight.is_finite() || *weight < 0.0)
+    {
+        return Err("Search weights must be zero or positive numbers".to_string());
+    }
+    if all.iter().all(|weight| *weight == 0.0) {
+        return Err("At least one search weight must be greater than zero".to_string());
+    }
+    Ok(())
+}
@@ -1,252 +1,79 @@
-use crate::database::{establish_connection, DbConnection};
+use crate::database;
+use crate::events;
 use crate::models::*;
-use chrono::{DateTime, Utc};
+use crate::repository::{SqliteRepository, StateRepository, UpdateOutcome};
 use rusqlite::Result;
-use std::sync::OnceLock;
-
-static DB_CONNECTION: OnceLock<DbConnection> = OnceLock::new();
-
-fn get_db_connection() -> &'static DbConnection {
-    DB_CONNECTION
-        .get_or_init(|| establish_connection().expect("Failed to establish database connection"))
-}
 
 #[tauri::command]
 pub fn get_all_states() -> Result<StatesListResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, position, color, created_at, updated_at
-         FROM states ORDER BY position ASC",
-        )
-        .map_err(|e| format!("Failed to prepare query: {}", e))?;
-
-    let states_iter = stmt
-        .query_map([], |row| {
-            // Handle both INTEGER and TEXT timestamps
-            let created_at: Option<DateTime<Utc>> = match row.get::<_, Option<i64>>(4)? {
-                Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-                None => match row.get::<_, Option<String>>(4)? {
-                    Some(ts_str) => DateTime::parse_from_rfc3339(&ts_str)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .ok()
-                        .or_else(|| {
-                            // Try parsing as unix timestamp string
-                            ts_str.parse::<i64>().ok()
-                                .and_then(|ts| DateTime::from_timestamp(ts, 0))
-                        }),
-                    None => None,
-                },
-            };
-
-            let updated_at: Option<DateTime<Utc>> = match row.get::<_, Option<i64>>(5)? {
-                Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-                None => match row.get::<_, Option<String>>(5)? {
-                    Some(ts_str) => DateTime::parse_from_rfc3339(&ts_str)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .ok()
-                        .or_else(|| {
-                            // Try parsing as unix timestamp string
-                            ts_str.parse::<i64>().ok()
-                                .and_then(|ts| DateTime::from_timestamp(ts, 0))
-                        }),
-                    None => None,
-                },
-            };
-
-            Ok(State {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                position: row.get(2)?,
-                color: row.get(3)?,
-                created_at,
-                updated_at,
-            })
-        })
-        .map_err(|e| format!("Failed to query states: {}", e))?;
-
-    let mut states = Vec::new();
-    for state in states_iter {
-        match state {
-            Ok(state) => states.push(state),
-            Err(e) => return Err(format!("Failed to parse state: {}", e)),
-        }
-    }
+    let conn = database::reader()?;
 
     Ok(StatesListResponse {
         success: true,
-        data: states,
+        data: SqliteRepository::new(&conn).list_states()?,
         error: None,
     })
 }
 
 #[tauri::command]
 pub fn create_state(request: CreateStateRequest) -> Result<StateResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    let now = Utc::now().timestamp();
+    let conn = database::writer()?;
 
-    conn.execute(
-        "INSERT INTO states (name, position, color, created_at, updated_at)
-         VALUES (?, ?, ?, ?, ?)",
-        rusqlite::params![request.name, request.position, request.color, now, now],
-    )
-    .map_err(|e| format!("Failed to create state: {}", e))?;
+    let state = SqliteRepository::new(&conn).create_state(&request)?;
+    events::publish_state(ChangeOperation::Created, &state);
 
-    let id = conn.last_insert_rowid();
-
-    // Retrieve the created state
-    get_state_sync(id, &conn)
+    Ok(state_response(Some(state)))
 }
 
 #[tauri::command]
 pub fn update_state(request: UpdateStateRequest) -> Result<StateResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
-
-    // Build dynamic update query
-    let mut set_parts = Vec::new();
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-    if let Some(name) = &request.name {
-        set_parts.push("name = ?".to_string());
-        params.push(Box::new(name.clone()));
-    }
-
-    if let Some(position) = request.position {
-        set_parts.push("position = ?".to_string());
-        params.push(Box::new(position));
-    }
-
-    if let Some(color) = &request.color {
-        set_parts.push("color = ?".to_string());
-        params.push(Box::new(color.clone()));
-    }
-
-    if set_parts.is_empty() {
-        return Err("No fields to update".to_string());
-    }
-
-    set_parts.push("updated_at = ?".to_string());
-    let now = Utc::now().timestamp();
-    params.push(Box::new(now));
-
-    let query = format!("UPDATE states SET {} WHERE id = ?", set_parts.join(", "));
-    params.push(Box::new(request.id));
-
-    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-    let rows_affected = conn
-        .execute(&query, &param_refs[..])
-        .map_err(|e| format!("Failed to update state: {}", e))?;
-
-    if rows_affected == 0 {
-        return Ok(StateResponse {
-            success: false,
-            data: None,
-            error: Some("State not found".to_string()),
-        });
-    }
+    let conn = database::writer()?;
+
+    let state = match SqliteRepository::new(&conn).update_state(&request)? {
+        UpdateOutcome::Updated(state) => state,
+        UpdateOutcome::NotFound => return Ok(state_response(None)),
+        // The current state goes back so the caller can merge and retry
+        UpdateOutcome::Conflict(current) => {
+            return Ok(StateResponse {
+                success: false,
+                error: Some(format!(
+                    "Conflict: state {} is at version {}, not {}",
+                    request.id,
+                    current.version,
+                    request.expected_version.unwrap_or_default()
+                )),
+                data: Some(current),
+            })
+        }
+    };
+    events::publish_state(ChangeOperation::Updated, &state);
 
-    // Return the updated state
-    get_state_sync(request.id, &conn)
+    Ok(state_response(Some(state)))
 }
 
 #[tauri::command]
 pub fn delete_state(id: i64) -> Result<StateResponse, String> {
-    let conn = get_db_connection();
-    let conn = conn.lock().unwrap();
+    let conn = database::writer()?;
 
-    // First get the state for return
-    let state_result = get_state_sync(id, &conn)?;
-    if !state_result.success {
-        return Ok(state_result);
+    let state = SqliteRepository::new(&conn).delete_state(id)?;
+    if let Some(state) = &state {
+        events::publish_state(ChangeOperation::Deleted, state);
     }
 
-    // Delete the state
-    let rows_affected = conn
-        .execute("DELETE FROM states WHERE id = ?", [id])
-        .map_err(|e| format!("Failed to delete state: {}", e))?;
-
-    if rows_affected == 0 {
-        return Ok(StateResponse {
-            success: false,
-            data: None,
-            error: Some("State not found".to_string()),
-        });
-    }
-
-    Ok(StateResponse {
-        success: true,
-        data: state_result.data,
-        error: None,
-    })
+    Ok(state_response(state))
 }
 
-fn get_state_sync(id: i64, conn: &rusqlite::Connection) -> Result<StateResponse, String> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, position, color, created_at, updated_at
-         FROM states WHERE id = ?",
-        )
-        .map_err(|e| format!("Failed to prepare query: {}", e))?;
-
-    let state = stmt.query_row([id], |row| {
-        // Handle both INTEGER and TEXT timestamps
-        let created_at: Option<DateTime<Utc>> = match row.get::<_, Option<i64>>(4)? {
-            Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-            None => match row.get::<_, Option<String>>(4)? {
-                Some(ts_str) => DateTime::parse_from_rfc3339(&ts_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .ok()
-                    .or_else(|| {
-                        // Try parsing as unix timestamp string
-                        ts_str.parse::<i64>().ok()
-                            .and_then(|ts| DateTime::from_timestamp(ts, 0))
-                    }),
-                None => None,
-            },
-        };
-
-        let updated_at: Option<DateTime<Utc>> = match row.get::<_, Option<i64>>(5)? {
-            Some(ts) => Some(DateTime::from_timestamp(ts, 0).unwrap_or_default()),
-            None => match row.get::<_, Option<String>>(5)? {
-                Some(ts_str) => DateTime::parse_from_rfc3339(&ts_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .ok()
-                    .or_else(|| {
-                        // Try parsing as unix timestamp string
-                        ts_str.parse::<i64>().ok()
-                            .and_then(|ts| DateTime::from_timestamp(ts, 0))
-                    }),
-                None => None,
-            },
-        };
-
-        Ok(State {
-            id: Some(row.get(0)?),
-            name: row.get(1)?,
-            position: row.get(2)?,
-            color: row.get(3)?,
-            created_at,
-            updated_at,
-        })
-    });
-
+fn state_response(state: Option<State>) -> StateResponse {
     match state {
-        Ok(state) => Ok(StateResponse {
+        Some(state) => StateResponse {
             success: true,
             data: Some(state),
             error: None,
-        }),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(StateResponse {
+        },
+        None => StateResponse {
             success: false,
             data: None,
             error: Some("State not found".to_string()),
-        }),
-        Err(e) => Err(format!("Failed to get state: {}", e)),
+        },
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,20 @@
+use crate::database;
+use crate::models::*;
+use crate::repository::{ChangeRepository, SqliteRepository};
+use rusqlite::Result;
+
+// Changes returned per call; clients ask again while `has_more` is set
+const MAX_CHANGES: usize = 1000;
+
+/// Notes and states created, updated or deleted after `since_seq`, the
+/// `latest_seq` of the previous sync, or everything from 0.
+#[tauri::command]
+pub fn get_changes(since_seq: i64) -> Result<ChangesResponse, String> {
+    let conn = database::reader()?;
+
+    Ok(ChangesResponse {
+        success: true,
+        data: Some(SqliteRepository::new(&conn).changes_since(since_seq.max(0), MAX_CHANGES)?),
+        error: None,
+    })
+}
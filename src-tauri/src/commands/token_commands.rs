@@ -0,0 +1,239 @@
+use crate::database::{self, get_database_path};
+use crate::models::*;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rusqlite::{OptionalExtension, Result};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+const TOKEN_PREFIX: &str = "jn_";
+
+// `last_used_at` is only refreshed once it's this old, so most requests don't write
+const LAST_USED_PRECISION_SECS: i64 = 60;
+const MCP_CLIENT_TOKEN_NAME: &str = "MCP clients";
+
+#[tauri::command]
+pub fn get_all_api_tokens() -> Result<ApiTokensListResponse, String> {
+    let conn = database::reader()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, token_prefix, scopes, revoked, created_at, last_used_at
+         FROM api_tokens ORDER BY id ASC",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let tokens = stmt
+        .query_map([], api_token_from_row)
+        .map_err(|e| format!("Failed to query API tokens: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read API token: {}", e))?;
+
+    Ok(ApiTokensListResponse {
+        success: true,
+        data: tokens,
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub fn create_api_token(request: CreateApiTokenRequest) -> Result<ApiTokenResponse, String> {
+    if request.name.trim().is_empty() {
+        return Err("Token name is required".to_string());
+    }
+    if request.scopes.is_empty() {
+        return Err("At least one scope is required".to_string());
+    }
+
+    let conn = database::writer()?;
+
+    let (token, plain_token) = issue_api_token(&conn, &request.name, &request.scopes)?;
+
+    Ok(ApiTokenResponse {
+        success: true,
+        data: Some(token),
+        token: Some(plain_token),
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub fn revoke_api_token(id: i64) -> Result<ApiTokenResponse, String> {
+    let conn = database::writer()?;
+
+    let rows_affected = conn
+        .execute("UPDATE api_tokens SET revoked = 1 WHERE id = ?", [id])
+        .map_err(|e| format!("Failed to revoke API token: {}", e))?;
+
+    if rows_affected == 0 {
+        return Ok(ApiTokenResponse {
+            success: false,
+            data: None,
+            token: None,
+            error: Some("API token not found".to_string()),
+        });
+    }
+
+    Ok(ApiTokenResponse {
+        success: true,
+        data: get_api_token_sync(id, &conn)?,
+        token: None,
+        error: None,
+    })
+}
+
+/// Look up a presented bearer token, returning it only if it exists and isn't revoked.
+/// Errors mean the lookup itself failed, not that the token is invalid.
+pub(crate) fn authenticate_api_token(plain_token: &str) -> Result<Option<ApiToken>, String> {
+    let token = database::reader()?
+        .query_row(
+            "SELECT id, name, token_prefix, scopes, revoked, created_at, last_used_at
+             FROM api_tokens WHERE token_hash = ? AND revoked = 0",
+            [hash_token(plain_token)],
+            api_token_from_row,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to look up API token: {}", e))?;
+    let Some(token) = token else {
+        return Ok(None);
+    };
+
+    // Skipped while a write is in progress, so requests never wait to authenticate
+    let now = Utc::now().timestamp();
+    let recently_used = token
+        .last_used_at
+        .is_some_and(|used| now - used.timestamp() < LAST_USED_PRECISION_SECS);
+    if !recently_used {
+        if let Some(conn) = database::database().try_writer() {
+            let _ = conn.execute(
+                "UPDATE api_tokens SET last_used_at = ? WHERE id = ?",
+                rusqlite::params![now, token.id],
+            );
+        }
+    }
+
+    Ok(Some(token))
+}
+
+/// The token written into MCP client configs. It is kept in a file next to the
+/// database so every client config can share it; a new one is issued if the
+/// file is missing or the stored token was revoked.
+pub(crate) fn get_mcp_client_token() -> Result<String, String> {
+    let token_path = get_mcp_token_path();
+
+    if let Ok(stored) = std::fs::read_to_string(&token_path) {
+        let stored = stored.trim();
+        if authenticate_api_token(stored)?.is_some() {
+            return Ok(stored.to_string());
+        }
+    }
+
+    let conn = database::writer()?;
+    let (_, plain_token) = issue_api_token(
+        &conn,
+        MCP_CLIENT_TOKEN_NAME,
+        &[TokenScope::Read, TokenScope::Write],
+    )?;
+
+    write_private_file(&token_path, &plain_token)
+        .map_err(|e| format!("Failed to store MCP client token: {}", e))?;
+
+    Ok(plain_token)
+}
+
+fn issue_api_token(
+    conn: &rusqlite::Connection,
+    name: &str,
+    scopes: &[TokenScope],
+) -> Result<(ApiToken, String), String> {
+    let plain_token = generate_token();
+    let scopes_json =
+        serde_json::to_string(scopes).map_err(|e| format!("Failed to serialize scopes: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO api_tokens (name, token_hash, token_prefix, scopes, created_at)
+         VALUES (?, ?, ?, ?, ?)",
+        rusqlite::params![
+            name,
+            hash_token(&plain_token),
+            &plain_token[..TOKEN_PREFIX.len() + 8],
+            scopes_json,
+            Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| format!("Failed to create API token: {}", e))?;
+
+    let token = get_api_token_sync(conn.last_insert_rowid(), conn)?
+        .ok_or_else(|| "Failed to read created API token".to_string())?;
+
+    Ok((token, plain_token))
+}
+
+fn get_api_token_sync(id: i64, conn: &rusqlite::Connection) -> Result<Option<ApiToken>, String> {
+    match conn.query_row(
+        "SELECT id, name, token_prefix, scopes, revoked, created_at, last_used_at
+         FROM api_tokens WHERE id = ?",
+        [id],
+        api_token_from_row,
+    ) {
+        Ok(token) => Ok(Some(token)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(format!("Failed to get API token: {}", e)),
+    }
+}
+
+fn api_token_from_row(row: &rusqlite::Row) -> rusqlite::Result<ApiToken> {
+    let scopes_json: String = row.get(3)?;
+    let scopes: Vec<TokenScope> = serde_json::from_str(&scopes_json).unwrap_or_default();
+
+    Ok(ApiToken {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        prefix: row.get(2)?,
+        scopes,
+        revoked: row.get::<_, i32>(4)? != 0,
+        created_at: row
+            .get::<_, Option<i64>>(5)?
+            .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+        last_used_at: row
+            .get::<_, Option<i64>>(6)?
+            .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+    })
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", TOKEN_PREFIX, hex::encode(bytes))
+}
+
+fn hash_token(plain_token: &str) -> String {
+    hex::encode(Sha256::digest(plain_token.as_bytes()))
+}
+
+fn get_mcp_token_path() -> PathBuf {
+    PathBuf::from(get_database_path()).with_file_name("juan-note-mcp-token")
+}
+
+fn write_private_file(path: &PathBuf, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    // Only the current user should be able to read the token, from the moment
+    // the file is created
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+
+    // The mode only applies to new files, so tighten one left by an older version
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(contents.as_bytes())
+}
@@ -0,0 +1,551 @@
+use super::mcp_commands::find_configured_server;
+use crate::database;
+use crate::events;
+use crate::http_server::blocking;
+use crate::mcp::client::{self, McpClient};
+use crate::models::*;
+use crate::repository::{NoteRepository, SqliteRepository, UpdateOutcome};
+use chrono::{DateTime, Utc};
+use rusqlite::Result;
+use std::time::Instant;
+
+/// Run a tool on a configured MCP server. The arguments are checked against the
+/// tool's input schema first; the output can be saved as a new note or appended
+/// to an existing one. Every call is recorded in `tool_runs`, including the ones
+/// that fail before reaching the tool or while writing the note.
+#[tauri::command]
+pub async fn call_mcp_tool(request: CallMcpToolRequest) -> Result<ToolRunResponse, String> {
+    let mut run = ToolRun {
+        id: None,
+        server_name: request.server_name.clone(),
+        server_provider: request.provider.clone(),
+        tool_name: request.tool_name.clone(),
+        arguments: request
+            .arguments
+            .clone()
+            .unwrap_or_else(|| serde_json::json!({})),
+        result: None,
+        output: None,
+        is_error: true,
+        error: None,
+        note_id: None,
+        duration_ms: None,
+        created_at: Some(Utc::now()),
+    };
+
+    if let Err(e) = run_tool(&request, &mut run).await {
+        run.is_error = true;
+        run.error = Some(e.clone());
+        // The run's own error is the one worth returning
+        if let Err(record_error) = record_tool_run(&run).await {
+            eprintln!("{}", record_error);
+        }
+        return Err(e);
+    }
+
+    run.id = Some(record_tool_run(&run).await?);
+
+    Ok(ToolRunResponse {
+        success: !run.is_error,
+        error: run.error.clone(),
+        data: Some(run),
+    })
+}
+
+// Fill in `run` as the call goes. A tool that answers with an error is a
+// finished run; everything else that goes wrong is returned.
+async fn run_tool(request: &CallMcpToolRequest, run: &mut ToolRun) -> Result<(), String> {
+    let save_as_note = request.save_as_note.unwrap_or(false);
+    if save_as_note && request.append_to_note_id.is_some() {
+        return Err("Use either save_as_note or append_to_note_id, not both".to_string());
+    }
+    if let Some(note_id) = request.append_to_note_id {
+        let note = blocking(move || {
+            let conn = database::reader()?;
+            SqliteRepository::new(&conn).get_note(note_id)
+        })
+        .await?;
+        if note.is_none() {
+            return Err(format!("Note {} not found", note_id));
+        }
+    }
+
+    let (provider, server) =
+        find_configured_server(&request.server_name, request.provider.as_deref()).await?;
+    run.server_name = server.name.clone();
+    run.server_provider = Some(provider);
+
+    let mut client = McpClient::connect(&server, client::DEFAULT_TIMEOUT).await?;
+
+    let tools = match client.list_tools().await {
+        Ok(tools) => tools,
+        Err(e) => {
+            client.close().await;
+            return Err(e);
+        }
+    };
+    let Some(tool) = tools.iter().find(|tool| {
+        tool.get("name").and_then(|name| name.as_str()) == Some(request.tool_name.as_str())
+    }) else {
+        client.close().await;
+        return Err(format!(
+            "Tool '{}' not found on server '{}'",
+            request.tool_name, server.name
+        ));
+    };
+
+    if let Some(schema) = tool.get("inputSchema") {
+        if let Err(e) = validate_arguments(schema, &run.arguments) {
+            client.close().await;
+            return Err(e);
+        }
+    }
+
+    let started = Instant::now();
+    let call_result = client
+        .call_tool(&request.tool_name, run.arguments.clone())
+        .await;
+    run.duration_ms = Some(started.elapsed().as_millis() as i64);
+    client.close().await;
+
+    match call_result {
+        Ok(result) => {
+            run.is_error = result
+                .get("isError")
+                .and_then(|is_error| is_error.as_bool())
+                .unwrap_or(false);
+            run.output = Some(result_text(&result));
+            run.result = Some(result);
+        }
+        Err(e) => run.error = Some(e),
+    }
+
+    // Failed calls are recorded but never written into notes
+    if !run.is_error && (save_as_note || request.append_to_note_id.is_some()) {
+        let request = request.clone();
+        let finished = run.clone();
+        run.note_id = blocking(move || {
+            let conn = database::writer()?;
+            write_output(&SqliteRepository::new(&conn), &request, &finished)
+        })
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_tool_runs(
+    note_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<ToolRunsListResponse, String> {
+    let conn = database::reader()?;
+
+    let limit = limit.unwrap_or(50).min(1000);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, server_name, server_provider, tool_name, arguments, result, is_error, error, note_id, duration_ms, created_at
+         FROM tool_runs WHERE (?1 IS NULL OR note_id = ?1) ORDER BY id DESC LIMIT ?2",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let runs = stmt
+        .query_map(rusqlite::params![note_id, limit], |row| {
+            let arguments: String = row.get(4)?;
+            let result: Option<String> = row.get(5)?;
+            let result: Option<serde_json::Value> =
+                result.and_then(|result| serde_json::from_str(&result).ok());
+
+            Ok(ToolRun {
+                id: row.get(0)?,
+                server_name: row.get(1)?,
+                server_provider: row.get(2)?,
+                tool_name: row.get(3)?,
+                arguments: serde_json::from_str(&arguments).unwrap_or_default(),
+                output: result.as_ref().map(result_text),
+                result,
+                is_error: row.get::<_, i32>(6)? != 0,
+                error: row.get(7)?,
+                note_id: row.get(8)?,
+                duration_ms: row.get(9)?,
+                created_at: row
+                    .get::<_, Option<i64>>(10)?
+                    .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            })
+        })
+        .map_err(|e| format!("Failed to query tool runs: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read tool run: {}", e))?;
+
+    Ok(ToolRunsListResponse {
+        success: true,
+        data: runs,
+        error: None,
+    })
+}
+
+fn validate_arguments(
+    schema: &serde_json::Value,
+    arguments: &serde_json::Value,
+) -> Result<(), String> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| format!("Tool has an invalid input schema: {}", e))?;
+
+    let errors: Vec<String> = validator
+        .iter_errors(arguments)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            if path.is_empty() {
+                error.to_string()
+            } else {
+                format!("{}: {}", path, error)
+            }
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Invalid arguments: {}", errors.join("; ")))
+    }
+}
+
+// Text blocks are kept as is; other content types are summarized
+fn result_text(result: &serde_json::Value) -> String {
+    let Some(content) = result.get("content").and_then(|content| content.as_array()) else {
+        return result
+            .get("structuredContent")
+            .map(|structured| structured.to_string())
+            .unwrap_or_default();
+    };
+
+    content
+        .iter()
+        .map(
+            |block| match block.get("type").and_then(|kind| kind.as_str()) {
+                Some("text") => block
+                    .get("text")
+                    .and_then(|text| text.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                Some("resource") => block
+                    .get("resource")
+                    .and_then(|resource| resource.get("text").or_else(|| resource.get("uri")))
+                    .and_then(|text| text.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                Some("resource_link") => format!(
+                    "[{}]",
+                    block
+                        .get("uri")
+                        .and_then(|uri| uri.as_str())
+                        .unwrap_or_default()
+                ),
+                Some(kind) => format!(
+                    "[{} content{}]",
+                    kind,
+                    block
+                        .get("mimeType")
+                        .and_then(|mime| mime.as_str())
+                        .map(|mime| format!(": {}", mime))
+                        .unwrap_or_default()
+                ),
+                None => String::new(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// Save the output of a finished run as a new note or append it to an existing
+// one, as the request asks. Returns the id of the note written.
+fn write_output(
+    notes: &impl NoteRepository,
+    request: &CallMcpToolRequest,
+    run: &ToolRun,
+) -> Result<Option<i64>, String> {
+    let output = run.output.clone().unwrap_or_default();
+
+    if request.save_as_note.unwrap_or(false) {
+        save_output_as_note(notes, request, run, output)
+    } else if let Some(note_id) = request.append_to_note_id {
+        append_output_to_note(notes, note_id, run, &output)?;
+        Ok(Some(note_id))
+    } else {
+        Ok(None)
+    }
+}
+
+fn save_output_as_note(
+    notes: &impl NoteRepository,
+    request: &CallMcpToolRequest,
+    run: &ToolRun,
+    output: String,
+) -> Result<Option<i64>, String> {
+    let title = request
+        .note_title
+        .clone()
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| format!("{} ({})", run.tool_name, run.server_name));
+
+    let note = notes.create_note(&CreateNoteRequest {
+        title,
+        content: output,
+        priority: None,
+        labels: Some(vec!["mcp".to_string(), format!("tool:{}", run.tool_name)]),
+        deadline: None,
+        reminder_minutes: None,
+        done: None,
+        state_id: None,
+        order: None,
+        section: None,
+    })?;
+    events::publish_note(ChangeOperation::Created, &note);
+
+    Ok(note.id)
+}
+
+fn append_output_to_note(
+    notes: &impl NoteRepository,
+    note_id: i64,
+    run: &ToolRun,
+    output: &str,
+) -> Result<(), String> {
+    let note = notes
+        .get_note(note_id)?
+        .ok_or_else(|| format!("Note {} not found", note_id))?;
+
+    let header = format!(
+        "--- {} ({}) at {} ---",
+        run.tool_name,
+        run.server_name,
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    );
+    let content = if note.content.is_empty() {
+        format!("{}\n{}", header, output)
+    } else {
+        format!("{}\n\n{}\n{}", note.content, header, output)
+    };
+
+    let outcome = notes.update_note(&UpdateNoteRequest {
+        id: note_id,
+        title: None,
+        content: Some(content),
+        priority: None,
+        labels: None,
+        deadline: None,
+        reminder_minutes: None,
+        done: None,
+        state_id: None,
+        order: None,
+        section: None,
+        // Fails rather than drop an edit made since the note was read
+        expected_version: Some(note.version),
+    })?;
+
+    match outcome {
+        UpdateOutcome::Updated(note) => {
+            events::publish_note(ChangeOperation::Updated, &note);
+            Ok(())
+        }
+        UpdateOutcome::NotFound => Err(format!("Note {} not found", note_id)),
+        UpdateOutcome::Conflict(current) => Err(format!(
+            "Conflict: note {} is at version {}, not {}",
+            note_id, current.version, note.version
+        )),
+    }
+}
+
+async fn record_tool_run(run: &ToolRun) -> Result<i64, String> {
+    let run = run.clone();
+    blocking(move || {
+        let conn = database::writer()?;
+        insert_tool_run(&conn, &run)
+    })
+    .await
+}
+
+fn insert_tool_run(conn: &rusqlite::Connection, run: &ToolRun) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO tool_runs (server_name, server_provider, tool_name, arguments, result, is_error, error, note_id, duration_ms, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            run.server_name,
+            run.server_provider,
+            run.tool_name,
+            run.arguments.to_string(),
+            run.result.as_ref().map(|result| result.to_string()),
+            run.is_error as i32,
+            run.error,
+            run.note_id,
+            run.duration_ms,
+            Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| format!("Failed to record tool run: {}", e))?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::run_migrations;
+    use rusqlite::Connection;
+    use serde_json::json;
+
+    fn migrated_connection() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn tool_request(save_as_note: bool, append_to_note_id: Option<i64>) -> CallMcpToolRequest {
+        CallMcpToolRequest {
+            server_name: "stub".to_string(),
+            provider: None,
+            tool_name: "fetch".to_string(),
+            arguments: None,
+            save_as_note: Some(save_as_note),
+            note_title: None,
+            append_to_note_id,
+        }
+    }
+
+    fn finished_run(output: &str) -> ToolRun {
+        ToolRun {
+            id: None,
+            server_name: "stub".to_string(),
+            server_provider: Some("cursor".to_string()),
+            tool_name: "fetch".to_string(),
+            arguments: json!({}),
+            result: None,
+            output: Some(output.to_string()),
+            is_error: false,
+            error: None,
+            note_id: None,
+            duration_ms: Some(5),
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn validates_arguments_against_the_input_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "url": { "type": "string" } },
+            "required": ["url"]
+        });
+
+        assert!(validate_arguments(&schema, &json!({ "url": "https://example.com" })).is_ok());
+
+        let missing = validate_arguments(&schema, &json!({})).unwrap_err();
+        assert!(missing.starts_with("Invalid arguments: "), "{}", missing);
+        assert!(missing.contains("url"), "{}", missing);
+
+        let wrong_type = validate_arguments(&schema, &json!({ "url": 3 })).unwrap_err();
+        assert!(wrong_type.contains("/url: "), "{}", wrong_type);
+    }
+
+    #[test]
+    fn rejects_an_invalid_input_schema() {
+        let error = validate_arguments(&json!({ "type": "nonsense" }), &json!({})).unwrap_err();
+
+        assert!(
+            error.starts_with("Tool has an invalid input schema"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn result_text_joins_content_blocks() {
+        let result = json!({
+            "content": [
+                { "type": "text", "text": "Hello" },
+                { "type": "resource", "resource": { "uri": "file:///a.txt", "text": "File text" } },
+                { "type": "resource", "resource": { "uri": "file:///b.bin" } },
+                { "type": "resource_link", "uri": "file:///c.txt" },
+                { "type": "image", "mimeType": "image/png", "data": "AAAA" }
+            ]
+        });
+
+        assert_eq!(
+            result_text(&result),
+            "Hello\n\nFile text\n\nfile:///b.bin\n\n[file:///c.txt]\n\n[image content: image/png]"
+        );
+    }
+
+    #[test]
+    fn result_text_falls_back_to_structured_content() {
+        let result = json!({ "structuredContent": { "temperature": 21 } });
+
+        assert_eq!(result_text(&result), r#"{"temperature":21}"#);
+        assert_eq!(result_text(&json!({})), "");
+    }
+
+    #[test]
+    fn saves_output_as_a_new_labelled_note() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+
+        let note_id = write_output(&notes, &tool_request(true, None), &finished_run("Output"))
+            .unwrap()
+            .unwrap();
+
+        let note = notes.get_note(note_id).unwrap().unwrap();
+        assert_eq!(note.title, "fetch (stub)");
+        assert_eq!(note.content, "Output");
+        assert_eq!(note.labels, ["mcp", "tool:fetch"]);
+    }
+
+    #[test]
+    fn appends_output_under_a_header() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+        let existing = notes
+            .create_note(&CreateNoteRequest {
+                title: "Log".to_string(),
+                content: "Earlier".to_string(),
+                priority: None,
+                labels: None,
+                deadline: None,
+                reminder_minutes: None,
+                done: None,
+                state_id: None,
+                order: None,
+                section: None,
+            })
+            .unwrap();
+        let note_id = existing.id.unwrap();
+
+        let written = write_output(
+            &notes,
+            &tool_request(false, Some(note_id)),
+            &finished_run("Later"),
+        )
+        .unwrap();
+        assert_eq!(written, Some(note_id));
+
+        let note = notes.get_note(note_id).unwrap().unwrap();
+        assert!(note.content.starts_with("Earlier\n\n--- fetch (stub) at "));
+        assert!(note.content.ends_with(" ---\nLater"));
+        assert_eq!(note.version, existing.version + 1);
+    }
+
+    #[test]
+    fn appending_to_a_missing_note_fails() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+
+        let error = write_output(
+            &notes,
+            &tool_request(false, Some(42)),
+            &finished_run("Lost"),
+        )
+        .unwrap_err();
+
+        assert_eq!(error, "Note 42 not found");
+    }
+}
@@ -0,0 +1,308 @@
+use crate::database;
+use crate::models::*;
+use crate::webhooks;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rusqlite::Result;
+
+#[tauri::command]
+pub fn get_all_webhooks() -> Result<WebhooksListResponse, String> {
+    let conn = database::reader()?;
+
+    let webhooks = query_webhooks(&conn, "SELECT id, url, events, secret, enabled, created_at, updated_at FROM webhooks ORDER BY id ASC")?;
+
+    Ok(WebhooksListResponse {
+        success: true,
+        data: webhooks,
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub fn create_webhook(request: CreateWebhookRequest) -> Result<WebhookResponse, String> {
+    validate_webhook_url(&request.url)?;
+
+    let conn = database::writer()?;
+
+    let events_json = serde_json::to_string(&request.events)
+        .map_err(|e| format!("Failed to serialize events: {}", e))?;
+    let secret = request.secret.unwrap_or_else(generate_secret);
+    let now = Utc::now().timestamp();
+
+    conn.execute(
+        "INSERT INTO webhooks (url, events, secret, enabled, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            request.url,
+            events_json,
+            secret,
+            request.enabled.unwrap_or(true) as i32,
+            now,
+            now
+        ],
+    )
+    .map_err(|e| format!("Failed to create webhook: {}", e))?;
+
+    let id = conn.last_insert_rowid();
+
+    // Retrieve the created webhook
+    get_webhook_sync(id, &conn)
+}
+
+#[tauri::command]
+pub fn update_webhook(request: UpdateWebhookRequest) -> Result<WebhookResponse, String> {
+    let conn = database::writer()?;
+
+    // Build dynamic update query
+    let mut set_parts = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(url) = &request.url {
+        validate_webhook_url(url)?;
+        set_parts.push("url = ?".to_string());
+        params.push(Box::new(url.clone()));
+    }
+
+    if let Some(events) = &request.events {
+        let events_json = serde_json::to_string(events)
+            .map_err(|e| format!("Failed to serialize events: {}", e))?;
+        set_parts.push("events = ?".to_string());
+        params.push(Box::new(events_json));
+    }
+
+    if let Some(secret) = &request.secret {
+        set_parts.push("secret = ?".to_string());
+        params.push(Box::new(secret.clone()));
+    }
+
+    if let Some(enabled) = request.enabled {
+        set_parts.push("enabled = ?".to_string());
+        params.push(Box::new(enabled as i32));
+    }
+
+    if set_parts.is_empty() {
+        return Err("No fields to update".to_string());
+    }
+
+    set_parts.push("updated_at = ?".to_string());
+    params.push(Box::new(Utc::now().timestamp()));
+
+    let query = format!("UPDATE webhooks SET {} WHERE id = ?", set_parts.join(", "));
+    params.push(Box::new(request.id));
+
+    let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+    let rows_affected = conn
+        .execute(&query, &param_refs[..])
+        .map_err(|e| format!("Failed to update webhook: {}", e))?;
+
+    if rows_affected == 0 {
+        return Ok(WebhookResponse {
+            success: false,
+            data: None,
+            error: Some("Webhook not found".to_string()),
+        });
+    }
+
+    // Return the updated webhook
+    get_webhook_sync(request.id, &conn)
+}
+
+#[tauri::command]
+pub fn delete_webhook(id: i64) -> Result<WebhookResponse, String> {
+    let conn = database::writer()?;
+
+    // First get the webhook for return
+    let webhook_result = get_webhook_sync(id, &conn)?;
+    if !webhook_result.success {
+        return Ok(webhook_result);
+    }
+
+    // Delivery log rows go with it through ON DELETE CASCADE
+    conn.execute("DELETE FROM webhooks WHERE id = ?", [id])
+        .map_err(|e| format!("Failed to delete webhook: {}", e))?;
+
+    Ok(webhook_result)
+}
+
+#[tauri::command]
+pub fn get_webhook_deliveries(
+    webhook_id: i64,
+    limit: Option<i64>,
+) -> Result<WebhookDeliveriesResponse, String> {
+    let conn = database::reader()?;
+
+    let limit = limit.unwrap_or(50).min(1000);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, webhook_id, delivery_id, event, payload, attempt, status_code, success, error, created_at
+         FROM webhook_deliveries WHERE webhook_id = ? ORDER BY id DESC LIMIT ?",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let deliveries = stmt
+        .query_map(rusqlite::params![webhook_id, limit], |row| {
+            Ok(WebhookDelivery {
+                id: row.get(0)?,
+                webhook_id: row.get(1)?,
+                delivery_id: row.get(2)?,
+                event: row.get(3)?,
+                payload: row.get(4)?,
+                attempt: row.get(5)?,
+                status_code: row.get(6)?,
+                success: row.get::<_, i32>(7)? != 0,
+                error: row.get(8)?,
+                created_at: row
+                    .get::<_, Option<i64>>(9)?
+                    .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            })
+        })
+        .map_err(|e| format!("Failed to query webhook deliveries: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read webhook delivery: {}", e))?;
+
+    Ok(WebhookDeliveriesResponse {
+        success: true,
+        data: deliveries,
+        error: None,
+    })
+}
+
+/// Send a `ping` event to a webhook right away, without retries, to check the endpoint.
+#[tauri::command]
+pub async fn test_webhook(id: i64) -> Result<WebhookDeliveriesResponse, String> {
+    let webhook = {
+        let conn = database::reader()?;
+        get_webhook_sync(id, &conn)?
+    };
+
+    let Some(webhook) = webhook.data else {
+        return Ok(WebhookDeliveriesResponse {
+            success: false,
+            data: Vec::new(),
+            error: Some("Webhook not found".to_string()),
+        });
+    };
+
+    let delivery = webhooks::send_ping(&webhook).await;
+
+    Ok(WebhookDeliveriesResponse {
+        success: delivery.success,
+        error: delivery.error.clone(),
+        data: vec![delivery],
+    })
+}
+
+/// Enabled webhooks whose event filter accepts `event_name`.
+pub(crate) fn get_webhooks_for_event(event_name: &str) -> Result<Vec<Webhook>, String> {
+    let conn = database::reader()?;
+
+    let webhooks = query_webhooks(&conn, "SELECT id, url, events, secret, enabled, created_at, updated_at FROM webhooks WHERE enabled = 1")?;
+
+    Ok(webhooks
+        .into_iter()
+        .filter(|webhook| webhook.accepts(event_name))
+        .collect())
+}
+
+pub(crate) fn record_webhook_delivery(delivery: &WebhookDelivery) -> Result<(), String> {
+    let conn = database::writer()?;
+    insert_webhook_delivery(&conn, delivery)
+}
+
+pub(crate) fn insert_webhook_delivery(
+    conn: &rusqlite::Connection,
+    delivery: &WebhookDelivery,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO webhook_deliveries (webhook_id, delivery_id, event, payload, attempt, status_code, success, error, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            delivery.webhook_id,
+            delivery.delivery_id,
+            delivery.event,
+            delivery.payload,
+            delivery.attempt,
+            delivery.status_code,
+            delivery.success as i32,
+            delivery.error,
+            Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| format!("Failed to record webhook delivery: {}", e))?;
+
+    Ok(())
+}
+
+fn query_webhooks(conn: &rusqlite::Connection, sql: &str) -> Result<Vec<Webhook>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let webhooks = stmt
+        .query_map([], webhook_from_row)
+        .map_err(|e| format!("Failed to query webhooks: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read webhook: {}", e))?;
+
+    Ok(webhooks)
+}
+
+fn get_webhook_sync(id: i64, conn: &rusqlite::Connection) -> Result<WebhookResponse, String> {
+    let webhook = conn.query_row(
+        "SELECT id, url, events, secret, enabled, created_at, updated_at FROM webhooks WHERE id = ?",
+        [id],
+        webhook_from_row,
+    );
+
+    match webhook {
+        Ok(webhook) => Ok(WebhookResponse {
+            success: true,
+            data: Some(webhook),
+            error: None,
+        }),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(WebhookResponse {
+            success: false,
+            data: None,
+            error: Some("Webhook not found".to_string()),
+        }),
+        Err(e) => Err(format!("Failed to get webhook: {}", e)),
+    }
+}
+
+fn webhook_from_row(row: &rusqlite::Row) -> rusqlite::Result<Webhook> {
+    let events_json: String = row.get(2)?;
+    let events: Vec<String> = serde_json::from_str(&events_json).unwrap_or_default();
+
+    Ok(Webhook {
+        id: Some(row.get(0)?),
+        url: row.get(1)?,
+        events,
+        secret: row.get(3)?,
+        enabled: row.get::<_, i32>(4)? != 0,
+        created_at: row
+            .get::<_, Option<i64>>(5)?
+            .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+        updated_at: row
+            .get::<_, Option<i64>>(6)?
+            .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+    })
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        Ok(parsed) => Err(format!(
+            "Unsupported webhook URL scheme: {}",
+            parsed.scheme()
+        )),
+        Err(e) => Err(format!("Invalid webhook URL: {}", e)),
+    }
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
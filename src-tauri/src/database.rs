@@ -1,21 +1,124 @@
 use rusqlite::{Connection, Result};
-use std::sync::{Arc, Mutex};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 mod migrations;
+pub mod search_index;
 
-pub type DbConnection = Arc<Mutex<Connection>>;
+// Readers open at once; each HTTP request or command holds one only while it queries
+const READER_POOL_SIZE: u32 = 8;
 
-pub fn establish_connection() -> Result<DbConnection> {
-    let db_path = get_database_path();
-    let conn = Connection::open(&db_path)?;
+// How long a caller waits for a free connection before giving up
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
 
-    // Enable WAL mode for better concurrency
-    conn.pragma_update(None, "journal_mode", "WAL")?;
-    conn.pragma_update(None, "synchronous", "NORMAL")?;
-    conn.pragma_update(None, "cache_size", -64000)?; // 64MB cache
-    conn.pragma_update(None, "foreign_keys", "ON")?;
+pub type DbPool = r2d2::Pool<SqliteConnectionManager>;
+pub type PooledConnection = r2d2::PooledConnection<SqliteConnectionManager>;
 
-    Ok(Arc::new(Mutex::new(conn)))
+/// Opens connections to a database file with the app's pragmas. Read
+/// connections are `query_only`, so a read path can't take the write lock.
+pub struct SqliteConnectionManager {
+    path: PathBuf,
+    read_only: bool,
+}
+
+impl r2d2::ManageConnection for SqliteConnectionManager {
+    type Connection = Connection;
+    type Error = rusqlite::Error;
+
+    fn connect(&self) -> Result<Connection> {
+        let conn = Connection::open(&self.path)?;
+
+        // WAL lets readers see the last commit while a write is in progress
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.pragma_update(None, "cache_size", -64000)?; // 64MB cache
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        if self.read_only {
+            conn.pragma_update(None, "query_only", "ON")?;
+        }
+
+        Ok(conn)
+    }
+
+    fn is_valid(&self, conn: &mut Connection) -> Result<()> {
+        conn.execute_batch("")
+    }
+
+    fn has_broken(&self, _conn: &mut Connection) -> bool {
+        false
+    }
+}
+
+/// Connections to one database file: a pool of readers and a single writer.
+/// Writes are serialized on the writer, while reads run in parallel and never
+/// wait for it.
+pub struct Database {
+    readers: DbPool,
+    writer: DbPool,
+}
+
+impl Database {
+    pub fn open(path: impl AsRef<Path>) -> std::result::Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+
+        // The writer is opened first so the file exists and is in WAL mode
+        // before the readers connect
+        let writer = r2d2::Pool::builder()
+            .max_size(1)
+            .connection_timeout(CONNECTION_TIMEOUT)
+            .build(SqliteConnectionManager {
+                path: path.clone(),
+                read_only: false,
+            })
+            .map_err(|e| format!("Failed to open database: {}", e))?;
+        let readers = r2d2::Pool::builder()
+            .max_size(READER_POOL_SIZE)
+            .connection_timeout(CONNECTION_TIMEOUT)
+            .build(SqliteConnectionManager {
+                path,
+                read_only: true,
+            })
+            .map_err(|e| format!("Failed to open database: {}", e))?;
+
+        Ok(Self { readers, writer })
+    }
+
+    pub fn reader(&self) -> std::result::Result<PooledConnection, String> {
+        self.readers
+            .get()
+            .map_err(|e| format!("No database connection available: {}", e))
+    }
+
+    /// The write connection; waits while another caller holds it.
+    pub fn writer(&self) -> std::result::Result<PooledConnection, String> {
+        self.writer
+            .get()
+            .map_err(|e| format!("Database is busy: {}", e))
+    }
+
+    /// The write connection if nobody holds it right now.
+    pub fn try_writer(&self) -> Option<PooledConnection> {
+        self.writer.try_get()
+    }
+}
+
+static DATABASE: OnceLock<Database> = OnceLock::new();
+
+/// The app's database, opened on first use.
+pub fn database() -> &'static Database {
+    DATABASE.get_or_init(|| Database::open(get_database_path()).expect("Failed to open database"))
+}
+
+/// A read-only connection to the app's database.
+pub fn reader() -> std::result::Result<PooledConnection, String> {
+    database().reader()
+}
+
+/// The app's write connection.
+pub fn writer() -> std::result::Result<PooledConnection, String> {
+    database().writer()
 }
 
 pub fn get_database_path() -> String {
@@ -26,10 +129,223 @@ pub fn get_database_path() -> String {
     db_path.to_string_lossy().to_string()
 }
 
-pub fn run_migrations(conn: &DbConnection) -> Result<()> {
-    let conn = conn.lock().unwrap();
+type MigrationFn = fn(&Connection) -> Result<()>;
+
+struct Migration {
+    version: i32,
+    up: MigrationFn,
+    // `None` when the migration can't be undone without losing data the
+    // earlier schema has nowhere to keep
+    down: Option<MigrationFn>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up: migrations::migration_001::up,
+        down: None,
+    },
+    Migration {
+        version: 2,
+        up: migrations::migration_002::up,
+        down: Some(migrations::migration_002::down),
+    },
+    Migration {
+        version: 3,
+        up: migrations::migration_003::up,
+        down: Some(migrations::migration_003::down),
+    },
+    Migration {
+        version: 4,
+        up: migrations::migration_004::up,
+        down: None,
+    },
+    Migration {
+        version: 5,
+        up: migrations::migration_005::up,
+        down: Some(migrations::migration_005::down),
+    },
+    Migration {
+        version: 6,
+        up: migrations::migration_006::up,
+        down: Some(migrations::migration_006::down),
+    },
+    Migration {
+        version: 7,
+        up: migrations::migration_007::up,
+        down: Some(migrations::migration_007::down),
+    },
+    Migration {
+        version: 8,
+        up: migrations::migration_008::up,
+        down: Some(migrations::migration_008::down),
+    },
+    Migration {
+        version: 9,
+        up: migrations::migration_009::up,
+        down: Some(migrations::migration_009::down),
+    },
+    Migration {
+        version: 10,
+        up: migrations::migration_010::up,
+        down: Some(migrations::migration_010::down),
+    },
+    Migration {
+        version: 11,
+        up: migrations::migration_011::up,
+        down: Some(migrations::migration_011::down),
+    },
+    Migration {
+        version: 12,
+        up: migrations::migration_012::up,
+        down: Some(migrations::migration_012::down),
+    },
+    Migration {
+        version: 13,
+        up: migrations::migration_013::up,
+        down: Some(migrations::migration_013::down),
+    },
+    Migration {
+        version: 14,
+        up: migrations::migration_014::up,
+        down: Some(migrations::migration_014::down),
+    },
+    Migration {
+        version: 15,
+        up: migrations::migration_015::up,
+        down: Some(migrations::migration_015::down),
+    },
+    Migration {
+        version: 16,
+        up: migrations::migration_016::up,
+        down: Some(migrations::migration_016::down),
+    },
+    Migration {
+        version: 17,
+        up: migrations::migration_017::up,
+        down: Some(migrations::migration_017::down),
+    },
+    Migration {
+        version: 18,
+        up: migrations::migration_018::up,
+        down: Some(migrations::migration_018::down),
+    },
+    Migration {
+        version: 19,
+        up: migrations::migration_019::up,
+        down: Some(migrations::migration_019::down),
+    },
+];
+
+/// The schema version this build migrates databases to.
+pub fn latest_schema_version() -> i32 {
+    latest_version(MIGRATIONS)
+}
+
+fn latest_version(migrations: &[Migration]) -> i32 {
+    migrations.last().map_or(0, |migration| migration.version)
+}
+
+/// Bring the database up to the latest schema. Each migration runs in its own
+/// transaction together with its `schema_migrations` row, so a failed migration
+/// leaves the database at the previous version. Fails without touching the
+/// database if it was migrated by a newer build, and fails if the schema
+/// doesn't match what the migrations should have produced.
+pub fn run_migrations(conn: &mut Connection) -> std::result::Result<(), String> {
+    apply_migrations(conn, MIGRATIONS)
+}
+
+fn apply_migrations(
+    conn: &mut Connection,
+    migrations: &[Migration],
+) -> std::result::Result<(), String> {
+    let latest_version = latest_version(migrations);
+    let current_version = schema_version(conn).map_err(|e| e.to_string())?;
+    if current_version > latest_version {
+        return Err(format!(
+            "Database schema version {} is newer than this build supports (version {}); \
+             update the app or restore a backup made with this version",
+            current_version,
+            latest_version
+        ));
+    }
+
+    // Run pending migrations
+    for migration in migrations.iter().filter(|m| m.version > current_version) {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        (migration.up)(&tx)
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT INTO schema_migrations (version) VALUES (?)",
+                    [migration.version],
+                )
+            })
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("Migration {} failed: {}", migration.version, e))?;
+    }
+
+    verify_schema(conn, migrations, latest_version)
+}
+
+/// Undo migrations down to `target_version` using their `down` functions,
+/// newest first, each in its own transaction. Nothing is undone if any of the
+/// migrations involved has no `down` function.
+pub fn rollback_migrations(
+    conn: &mut Connection,
+    target_version: i32,
+) -> std::result::Result<Vec<i32>, String> {
+    undo_migrations(conn, MIGRATIONS, target_version)
+}
+
+fn undo_migrations(
+    conn: &mut Connection,
+    migrations: &[Migration],
+    target_version: i32,
+) -> std::result::Result<Vec<i32>, String> {
+    let latest_version = latest_version(migrations);
+    let current_version = schema_version(conn).map_err(|e| e.to_string())?;
+    if current_version > latest_version {
+        return Err(format!(
+            "Database schema version {} is newer than this build supports (version {})",
+            current_version,
+            latest_version
+        ));
+    }
+
+    let to_undo: Vec<&Migration> = migrations
+        .iter()
+        .rev()
+        .filter(|m| m.version > target_version && m.version <= current_version)
+        .collect();
+    if let Some(migration) = to_undo.iter().find(|m| m.down.is_none()) {
+        return Err(format!(
+            "Migration {} can't be rolled back, so version {} is out of reach",
+            migration.version, target_version
+        ));
+    }
+
+    let mut undone = Vec::new();
+    for migration in to_undo {
+        let down = migration.down.expect("checked above");
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        down(&tx)
+            .and_then(|_| {
+                tx.execute(
+                    "DELETE FROM schema_migrations WHERE version = ?",
+                    [migration.version],
+                )
+            })
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("Rolling back migration {} failed: {}", migration.version, e))?;
+        undone.push(migration.version);
+    }
 
-    // Create migrations table if it doesn't exist
+    verify_schema(conn, migrations, target_version.clamp(0, current_version))?;
+    Ok(undone)
+}
+
+// The latest applied migration, creating the migrations table if needed
+fn schema_version(conn: &Connection) -> Result<i32> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_migrations (
             version INTEGER PRIMARY KEY,
@@ -38,31 +354,231 @@ pub fn run_migrations(conn: &DbConnection) -> Result<()> {
         [],
     )?;
 
-    // Get the latest applied migration version
-    let mut stmt = conn.prepare("SELECT COALESCE(MAX(version), 0) FROM schema_migrations")?;
-    let latest_version: i32 = stmt.query_row([], |row| row.get(0))?;
-
-    // Define migrations
-    let migrations: Vec<(i32, fn(&Connection) -> Result<()>)> = vec![
-        (1, migrations::migration_001::up),
-        (2, migrations::migration_002::up),
-        (3, migrations::migration_003::up),
-        (4, migrations::migration_004::up),
-        (5, migrations::migration_005::up),
-        (6, migrations::migration_006::up),
-        (7, migrations::migration_007::up),
-    ];
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+}
 
-    // Run pending migrations
-    for (version, migration_fn) in migrations {
-        if version > latest_version {
-            migration_fn(&conn)?;
-            conn.execute(
-                "INSERT INTO schema_migrations (version) VALUES (?)",
-                [version],
-            )?;
+/// Check that every table, column, index, trigger and view the migrations up
+/// to `version` create exists in the database, by running them on an empty
+/// in-memory database and comparing.
+fn verify_schema(
+    conn: &Connection,
+    migrations: &[Migration],
+    version: i32,
+) -> std::result::Result<(), String> {
+    let reference = Connection::open_in_memory().map_err(|e| e.to_string())?;
+    for migration in migrations.iter().filter(|m| m.version <= version) {
+        (migration.up)(&reference).map_err(|e| {
+            format!(
+                "Failed to build reference schema at migration {}: {}",
+                migration.version, e
+            )
+        })?;
+    }
+
+    let expected = schema_objects(&reference).map_err(|e| e.to_string())?;
+    let actual = schema_objects(conn).map_err(|e| e.to_string())?;
+    let missing: Vec<&String> = expected.difference(&actual).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Database schema doesn't match version {}; missing {}",
+        version,
+        missing
+            .iter()
+            .map(|object| object.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+// Schema objects as "table notes", "column notes.title", "index idx_notes_done"...
+fn schema_objects(conn: &Connection) -> Result<BTreeSet<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT type, name FROM sqlite_master
+         WHERE type IN ('table', 'index', 'trigger', 'view') AND name NOT LIKE 'sqlite_%'",
+    )?;
+    let objects = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut schema = BTreeSet::new();
+    for (kind, name) in objects {
+        if kind == "table" || kind == "view" {
+            let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?)")?;
+            for column in stmt.query_map([&name], |row| row.get::<_, String>(0))? {
+                schema.insert(format!("column {}.{}", name, column?));
+            }
         }
+        schema.insert(format!("{} {}", kind, name));
+    }
+
+    Ok(schema)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_log(conn: &Connection) -> Result<()> {
+        conn.execute("CREATE TABLE log (entry TEXT NOT NULL)", [])?;
+        Ok(())
+    }
+
+    fn drop_log(conn: &Connection) -> Result<()> {
+        conn.execute("DROP TABLE log", [])?;
+        Ok(())
+    }
+
+    fn create_first(conn: &Connection) -> Result<()> {
+        conn.execute("CREATE TABLE first (id INTEGER PRIMARY KEY)", [])?;
+        Ok(())
+    }
+
+    fn undo_first(conn: &Connection) -> Result<()> {
+        conn.execute("INSERT INTO log (entry) VALUES ('undo 2')", [])?;
+        conn.execute("DROP TABLE first", [])?;
+        Ok(())
+    }
+
+    fn create_second(conn: &Connection) -> Result<()> {
+        conn.execute("CREATE TABLE second (id INTEGER PRIMARY KEY)", [])?;
+        Ok(())
+    }
+
+    fn undo_second(conn: &Connection) -> Result<()> {
+        conn.execute("INSERT INTO log (entry) VALUES ('undo 3')", [])?;
+        conn.execute("DROP TABLE second", [])?;
+        Ok(())
+    }
+
+    // Creates a table, then fails
+    fn half_done(conn: &Connection) -> Result<()> {
+        conn.execute("CREATE TABLE third (id INTEGER PRIMARY KEY)", [])?;
+        conn.execute("INSERT INTO missing_table VALUES (1)", [])?;
+        Ok(())
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            up: create_log,
+            down: Some(drop_log),
+        },
+        Migration {
+            version: 2,
+            up: create_first,
+            down: Some(undo_first),
+        },
+        Migration {
+            version: 3,
+            up: create_second,
+            down: Some(undo_second),
+        },
+    ];
+
+    fn tables(conn: &Connection) -> BTreeSet<String> {
+        schema_objects(conn)
+            .unwrap()
+            .into_iter()
+            .filter_map(|object| object.strip_prefix("table ").map(str::to_string))
+            .collect()
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn failed_migration_rolls_back_only_its_own_transaction() {
+        let failing = [
+            Migration {
+                version: 1,
+                up: create_log,
+                down: None,
+            },
+            Migration {
+                version: 2,
+                up: create_first,
+                down: None,
+            },
+            Migration {
+                version: 3,
+                up: half_done,
+                down: None,
+            },
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let error = apply_migrations(&mut conn, &failing).unwrap_err();
+
+        assert!(error.starts_with("Migration 3 failed"), "{}", error);
+        assert_eq!(schema_version(&conn).unwrap(), 2);
+        let tables = tables(&conn);
+        assert!(tables.contains("first"));
+        assert!(!tables.contains("third"));
+    }
+
+    #[test]
+    fn refuses_a_database_newer_than_the_code() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&mut conn, TEST_MIGRATIONS).unwrap();
+        conn.execute("INSERT INTO schema_migrations (version) VALUES (4)", [])
+            .unwrap();
+
+        let error = apply_migrations(&mut conn, TEST_MIGRATIONS).unwrap_err();
+        assert!(error.contains("version 4 is newer"), "{}", error);
+        let error = undo_migrations(&mut conn, TEST_MIGRATIONS, 1).unwrap_err();
+        assert!(error.contains("version 4 is newer"), "{}", error);
+
+        // Nothing was undone
+        assert!(tables(&conn).contains("second"));
+    }
+
+    #[test]
+    fn rollback_runs_down_functions_newest_first() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&mut conn, TEST_MIGRATIONS).unwrap();
+
+        let undone = undo_migrations(&mut conn, TEST_MIGRATIONS, 1).unwrap();
+
+        assert_eq!(undone, [3, 2]);
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        let log: Vec<String> = conn
+            .prepare("SELECT entry FROM log ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(log, ["undo 3", "undo 2"]);
+    }
+
+    #[test]
+    fn rollback_refuses_to_pass_a_migration_without_down() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        let error = rollback_migrations(&mut conn, 0).unwrap_err();
+
+        assert!(error.contains("can't be rolled back"), "{}", error);
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+    }
+
+    #[test]
+    fn app_migrations_roll_back_and_reapply() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        // Migration 4 is the newest one that can't be undone
+        let undone = rollback_migrations(&mut conn, 4).unwrap();
+        assert_eq!(undone.first(), Some(&latest_schema_version()));
+        assert_eq!(undone.last(), Some(&5));
+        assert_eq!(schema_version(&conn).unwrap(), 4);
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+    }
+}
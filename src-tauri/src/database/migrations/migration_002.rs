@@ -13,5 +13,13 @@ pub fn up(conn: &Connection) -> rusqlite::Result<()> {
         [],
     )?;
 
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Remove the reminder columns
+    conn.execute("ALTER TABLE notes DROP COLUMN reminder_minutes", [])?;
+    conn.execute("ALTER TABLE notes DROP COLUMN deadline", [])?;
+
     Ok(())
 }
\ No newline at end of file
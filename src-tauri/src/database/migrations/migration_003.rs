@@ -13,5 +13,13 @@ pub fn up(conn: &Connection) -> rusqlite::Result<()> {
         [],
     )?;
 
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Indexed columns can't be dropped, so the index goes first
+    conn.execute("DROP INDEX IF EXISTS idx_notes_done", [])?;
+    conn.execute("ALTER TABLE notes DROP COLUMN done", [])?;
+
     Ok(())
 }
\ No newline at end of file
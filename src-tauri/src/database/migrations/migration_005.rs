@@ -27,5 +27,13 @@ pub fn up(conn: &Connection) -> rusqlite::Result<()> {
         [],
     )?;
 
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Indexed columns can't be dropped, so the index goes first
+    conn.execute("DROP INDEX IF EXISTS idx_notes_order", [])?;
+    conn.execute("ALTER TABLE notes DROP COLUMN \"order\"", [])?;
+
     Ok(())
 }
\ No newline at end of file
@@ -1,87 +1,125 @@
 use rusqlite::Connection;
 
 pub fn up(conn: &Connection) -> rusqlite::Result<()> {
-    // Convert TEXT timestamps to INTEGER timestamps for notes table
-    // First, create temporary columns (only if they don't exist)
-    let _ = conn.execute(
-        "ALTER TABLE notes ADD COLUMN created_at_temp INTEGER",
-        [],
-    ); // Ignore error if column already exists
+    // Convert TEXT timestamps to INTEGER timestamps. Safe to run again: columns
+    // that already hold integers are left alone.
+    convert_timestamp_column(conn, "notes", "created_at")?;
+    convert_timestamp_column(conn, "notes", "updated_at")?;
+    convert_timestamp_column(conn, "notes", "deadline")?;
 
-    let _ = conn.execute(
-        "ALTER TABLE notes ADD COLUMN updated_at_temp INTEGER",
-        [],
-    ); // Ignore error if column already exists
+    convert_timestamp_column(conn, "states", "created_at")?;
+    convert_timestamp_column(conn, "states", "updated_at")?;
 
-    let _ = conn.execute(
-        "ALTER TABLE notes ADD COLUMN deadline_temp INTEGER",
-        [],
-    ); // Ignore error if column already exists
+    Ok(())
+}
 
-    // Convert TEXT timestamps to INTEGER timestamps
-    // For notes that have TEXT timestamps, convert them
-    conn.execute(
-        "
-        UPDATE notes
-        SET created_at_temp = CASE
-            WHEN typeof(created_at) = 'text' THEN strftime('%s', created_at)
-            ELSE created_at
-        END,
-        updated_at_temp = CASE
-            WHEN typeof(updated_at) = 'text' THEN strftime('%s', updated_at)
-            ELSE updated_at
-        END,
-        deadline_temp = CASE
-            WHEN typeof(deadline) = 'text' AND deadline IS NOT NULL THEN strftime('%s', deadline)
-            ELSE deadline
-        END
-        ",
-        [],
-    )?;
+pub fn down(_conn: &Connection) -> rusqlite::Result<()> {
+    // Earlier versions read integer timestamps too, so there is nothing to undo
+    Ok(())
+}
 
-    // Drop old columns and rename temp columns (only if they exist)
-    let _ = conn.execute("ALTER TABLE notes DROP COLUMN created_at", []); // Ignore error if column doesn't exist
-    let _ = conn.execute("ALTER TABLE notes DROP COLUMN updated_at", []); // Ignore error if column doesn't exist
-    let _ = conn.execute("ALTER TABLE notes DROP COLUMN deadline", []); // Ignore error if column doesn't exist
+fn convert_timestamp_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<()> {
+    let temp_column = format!("{}_temp", column);
 
-    let _ = conn.execute("ALTER TABLE notes RENAME COLUMN created_at_temp TO created_at", []); // Ignore error if column doesn't exist
-    let _ = conn.execute("ALTER TABLE notes RENAME COLUMN updated_at_temp TO updated_at", []); // Ignore error if column doesn't exist
-    let _ = conn.execute("ALTER TABLE notes RENAME COLUMN deadline_temp TO deadline", []); // Ignore error if column doesn't exist
+    // An earlier version of this migration could stop halfway and leave the
+    // temporary column behind. The original column is still the one the app
+    // writes to, so the copy is dropped, or renamed back if the original is gone.
+    if column_type(conn, table, &temp_column)?.is_some() {
+        let statement = if column_type(conn, table, column)?.is_some() {
+            format!("ALTER TABLE {} DROP COLUMN {}", table, temp_column)
+        } else {
+            format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                table, temp_column, column
+            )
+        };
+        conn.execute(&statement, [])?;
+    }
 
-    // Convert TEXT timestamps to INTEGER timestamps for states table
-    // First, create temporary columns (only if they don't exist)
-    let _ = conn.execute(
-        "ALTER TABLE states ADD COLUMN created_at_temp INTEGER",
-        [],
-    ); // Ignore error if column already exists
+    let Some(declared_type) = column_type(conn, table, column)? else {
+        return Ok(());
+    };
+
+    // Columns declared INTEGER store converted values as integers, so they
+    // can be converted in place
+    if declared_type.eq_ignore_ascii_case("INTEGER") {
+        conn.execute(
+            &format!(
+                "UPDATE {table} SET {column} = CAST(strftime('%s', {column}) AS INTEGER)
+                 WHERE typeof({column}) = 'text'"
+            ),
+            [],
+        )?;
+        return Ok(());
+    }
+
+    // Any other type is replaced by an INTEGER column. Indexed columns can't be
+    // dropped, so the indexes on it are dropped and recreated around the swap.
+    let indexes = column_indexes(conn, table, column)?;
 
-    let _ = conn.execute(
-        "ALTER TABLE states ADD COLUMN updated_at_temp INTEGER",
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} INTEGER", table, temp_column),
+        [],
+    )?;
+    conn.execute(
+        &format!(
+            "UPDATE {table} SET {temp_column} = CASE
+                WHEN typeof({column}) = 'text' THEN CAST(strftime('%s', {column}) AS INTEGER)
+                ELSE {column}
+             END"
+        ),
         [],
-    ); // Ignore error if column already exists
+    )?;
 
-    // Convert TEXT timestamps to INTEGER timestamps
+    for (name, _) in &indexes {
+        conn.execute(&format!("DROP INDEX {}", name), [])?;
+    }
+    conn.execute(&format!("ALTER TABLE {} DROP COLUMN {}", table, column), [])?;
     conn.execute(
-        "
-        UPDATE states
-        SET created_at_temp = CASE
-            WHEN typeof(created_at) = 'text' THEN strftime('%s', created_at)
-            ELSE created_at
-        END,
-        updated_at_temp = CASE
-            WHEN typeof(updated_at) = 'text' THEN strftime('%s', updated_at)
-            ELSE updated_at
-        END
-        ",
+        &format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            table, temp_column, column
+        ),
         [],
     )?;
+    for (_, sql) in &indexes {
+        conn.execute(sql, [])?;
+    }
 
-    // Drop old columns and rename temp columns (only if they exist)
-    let _ = conn.execute("ALTER TABLE states DROP COLUMN created_at", []); // Ignore error if column doesn't exist
-    let _ = conn.execute("ALTER TABLE states DROP COLUMN updated_at", []); // Ignore error if column doesn't exist
+    Ok(())
+}
 
-    let _ = conn.execute("ALTER TABLE states RENAME COLUMN created_at_temp TO created_at", []); // Ignore error if column doesn't exist
-    let _ = conn.execute("ALTER TABLE states RENAME COLUMN updated_at_temp TO updated_at", []); // Ignore error if column doesn't exist
+// Declared type of a column, or `None` if the table has no such column
+fn column_type(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT name, type FROM pragma_table_info(?)")?;
+    let columns = stmt.query_map([table], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
 
-    Ok(())
+    for entry in columns {
+        let (name, declared_type) = entry?;
+        if name == column {
+            return Ok(Some(declared_type));
+        }
+    }
+
+    Ok(None)
+}
+
+// Name and CREATE statement of every index that covers the column
+fn column_indexes(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.name, m.sql FROM sqlite_master m
+         WHERE m.type = 'index' AND m.tbl_name = ?1 AND m.sql IS NOT NULL
+           AND EXISTS (SELECT 1 FROM pragma_index_info(m.name) i WHERE i.name = ?2)",
+    )?;
+    let indexes = stmt
+        .query_map([table, column], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(indexes)
 }
\ No newline at end of file
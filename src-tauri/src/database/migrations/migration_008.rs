@@ -0,0 +1,51 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Create webhooks table for notifying local scripts about changes
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            events TEXT NOT NULL DEFAULT '[]',
+            secret TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER DEFAULT (strftime('%s', 'now')),
+            updated_at INTEGER DEFAULT (strftime('%s', 'now'))
+        )",
+        [],
+    )?;
+
+    // Create delivery log, one row per attempt
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+            delivery_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            attempt INTEGER NOT NULL,
+            status_code INTEGER,
+            success INTEGER NOT NULL DEFAULT 0,
+            error TEXT,
+            created_at INTEGER DEFAULT (strftime('%s', 'now'))
+        )",
+        [],
+    )?;
+
+    // Create indexes for performance
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id, created_at)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Delivery log first, since it references webhooks
+    conn.execute("DROP INDEX IF EXISTS idx_webhook_deliveries_webhook_id", [])?;
+    conn.execute("DROP TABLE IF EXISTS webhook_deliveries", [])?;
+    conn.execute("DROP TABLE IF EXISTS webhooks", [])?;
+
+    Ok(())
+}
\ No newline at end of file
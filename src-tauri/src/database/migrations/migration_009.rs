@@ -0,0 +1,27 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Create API tokens table for authenticating REST API clients
+    // Only a SHA-256 hash of each token is stored; the token itself is shown once
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS api_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            token_prefix TEXT NOT NULL,
+            scopes TEXT NOT NULL DEFAULT '[]',
+            revoked INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER DEFAULT (strftime('%s', 'now')),
+            last_used_at INTEGER
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DROP TABLE IF EXISTS api_tokens", [])?;
+
+    Ok(())
+}
\ No newline at end of file
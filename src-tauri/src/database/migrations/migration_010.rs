@@ -0,0 +1,36 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Create tool_runs table so notes written by MCP tools can be traced back to the call
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tool_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            server_name TEXT NOT NULL,
+            server_provider TEXT,
+            tool_name TEXT NOT NULL,
+            arguments TEXT NOT NULL,
+            result TEXT,
+            is_error INTEGER NOT NULL DEFAULT 0,
+            error TEXT,
+            note_id INTEGER REFERENCES notes(id) ON DELETE SET NULL,
+            duration_ms INTEGER,
+            created_at INTEGER DEFAULT (strftime('%s', 'now'))
+        )",
+        [],
+    )?;
+
+    // Create indexes for performance
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_tool_runs_note_id ON tool_runs(note_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DROP INDEX IF EXISTS idx_tool_runs_note_id", [])?;
+    conn.execute("DROP TABLE IF EXISTS tool_runs", [])?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,16 @@
+use rusqlite::Connection;
+
+use super::migration_006;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Migration 006 used to ignore failed DROP and RENAME statements. On
+    // databases where the index on notes.created_at made the DROP fail it left
+    // created_at_temp behind and the timestamps unconverted, while still being
+    // recorded as applied. The fixed version finishes the job.
+    migration_006::up(conn)
+}
+
+pub fn down(_conn: &Connection) -> rusqlite::Result<()> {
+    // Nothing to undo, the schema is the same as after migration 010
+    Ok(())
+}
\ No newline at end of file
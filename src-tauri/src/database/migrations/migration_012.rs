@@ -0,0 +1,56 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // notes_fts is an external-content table, so the index has to be told the
+    // old values to remove them. The triggers from migration 001 deleted and
+    // updated it like a normal table, which lets the index drift from the notes.
+    conn.execute("DROP TRIGGER IF EXISTS notes_fts_delete", [])?;
+    conn.execute("DROP TRIGGER IF EXISTS notes_fts_update", [])?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_delete AFTER DELETE ON notes
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content) VALUES ('delete', old.id, old.title, old.content);
+         END",
+        [],
+    )?;
+
+    // Only edits to indexed columns touch the index
+    conn.execute(
+        "CREATE TRIGGER notes_fts_update AFTER UPDATE OF title, content ON notes
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content) VALUES ('delete', old.id, old.title, old.content);
+             INSERT INTO notes_fts(rowid, title, content) VALUES (new.id, new.title, new.content);
+         END",
+        [],
+    )?;
+
+    // Start from an index that matches the notes
+    conn.execute("INSERT INTO notes_fts(notes_fts) VALUES ('rebuild')", [])?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Put back the triggers from migration 001
+    conn.execute("DROP TRIGGER IF EXISTS notes_fts_delete", [])?;
+    conn.execute("DROP TRIGGER IF EXISTS notes_fts_update", [])?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_delete AFTER DELETE ON notes
+         BEGIN
+             DELETE FROM notes_fts WHERE rowid = old.id;
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_update AFTER UPDATE ON notes
+         BEGIN
+             UPDATE notes_fts SET title = new.title, content = new.content WHERE rowid = new.id;
+         END",
+        [],
+    )?;
+
+    Ok(())
+}
\ No newline at end of file
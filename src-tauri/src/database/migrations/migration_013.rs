@@ -0,0 +1,148 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Replace the title/content index with one that also covers labels, section
+    // and the name of the note's state. FTS5 can't add columns, so the table
+    // and its triggers are recreated.
+    drop_search_index(conn)?;
+
+    // The index's content table: the indexed text of each note. Labels are
+    // indexed as their JSON array, which the tokenizer splits into the labels.
+    conn.execute(
+        "CREATE VIEW notes_search AS
+         SELECT n.id, n.title, n.content, n.labels, n.section, s.name AS state
+         FROM notes n LEFT JOIN states s ON s.id = n.state_id",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE VIRTUAL TABLE notes_fts USING fts5(
+            title, content, labels, section, state,
+            content=notes_search,
+            content_rowid=id
+        )",
+        [],
+    )?;
+
+    // Removing a row from an external-content index needs the values it was
+    // indexed with, so each trigger passes the old ones with 'delete'
+    conn.execute(
+        "CREATE TRIGGER notes_fts_insert AFTER INSERT ON notes
+         BEGIN
+             INSERT INTO notes_fts(rowid, title, content, labels, section, state)
+             VALUES (new.id, new.title, new.content, new.labels, new.section,
+                     (SELECT name FROM states WHERE id = new.state_id));
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_delete AFTER DELETE ON notes
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content, labels, section, state)
+             VALUES ('delete', old.id, old.title, old.content, old.labels, old.section,
+                     (SELECT name FROM states WHERE id = old.state_id));
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_update AFTER UPDATE OF title, content, labels, section, state_id ON notes
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content, labels, section, state)
+             VALUES ('delete', old.id, old.title, old.content, old.labels, old.section,
+                     (SELECT name FROM states WHERE id = old.state_id));
+             INSERT INTO notes_fts(rowid, title, content, labels, section, state)
+             VALUES (new.id, new.title, new.content, new.labels, new.section,
+                     (SELECT name FROM states WHERE id = new.state_id));
+         END",
+        [],
+    )?;
+
+    // Renaming or deleting a state changes the indexed text of its notes
+    conn.execute(
+        "CREATE TRIGGER states_fts_update AFTER UPDATE OF name ON states
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content, labels, section, state)
+             SELECT 'delete', id, title, content, labels, section, old.name FROM notes WHERE state_id = old.id;
+             INSERT INTO notes_fts(rowid, title, content, labels, section, state)
+             SELECT id, title, content, labels, section, new.name FROM notes WHERE state_id = new.id;
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER states_fts_delete AFTER DELETE ON states
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content, labels, section, state)
+             SELECT 'delete', id, title, content, labels, section, old.name FROM notes WHERE state_id = old.id;
+             INSERT INTO notes_fts(rowid, title, content, labels, section, state)
+             SELECT id, title, content, labels, section, NULL FROM notes WHERE state_id = old.id;
+         END",
+        [],
+    )?;
+
+    // Backfill the new index from the existing notes
+    conn.execute("INSERT INTO notes_fts(notes_fts) VALUES ('rebuild')", [])?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Back to the title/content index and the triggers from migration 012
+    drop_search_index(conn)?;
+
+    conn.execute(
+        "CREATE VIRTUAL TABLE notes_fts USING fts5(
+            title, content,
+            content=notes,
+            content_rowid=id
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_insert AFTER INSERT ON notes
+         BEGIN
+             INSERT INTO notes_fts(rowid, title, content) VALUES (new.id, new.title, new.content);
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_delete AFTER DELETE ON notes
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content) VALUES ('delete', old.id, old.title, old.content);
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER notes_fts_update AFTER UPDATE OF title, content ON notes
+         BEGIN
+             INSERT INTO notes_fts(notes_fts, rowid, title, content) VALUES ('delete', old.id, old.title, old.content);
+             INSERT INTO notes_fts(rowid, title, content) VALUES (new.id, new.title, new.content);
+         END",
+        [],
+    )?;
+
+    conn.execute("INSERT INTO notes_fts(notes_fts) VALUES ('rebuild')", [])?;
+
+    Ok(())
+}
+
+fn drop_search_index(conn: &Connection) -> rusqlite::Result<()> {
+    for trigger in [
+        "notes_fts_insert",
+        "notes_fts_delete",
+        "notes_fts_update",
+        "states_fts_update",
+        "states_fts_delete",
+    ] {
+        conn.execute(&format!("DROP TRIGGER IF EXISTS {}", trigger), [])?;
+    }
+    conn.execute("DROP TABLE IF EXISTS notes_fts", [])?;
+    conn.execute("DROP VIEW IF EXISTS notes_search", [])?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,65 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Trigram index over titles and labels, for finding notes whose words are
+    // close to a misspelled query
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_trigram USING fts5(
+            title, labels,
+            content=notes_search,
+            content_rowid=id,
+            tokenize='trigram'
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_trigram_insert AFTER INSERT ON notes
+         BEGIN
+             INSERT INTO notes_trigram(rowid, title, labels) VALUES (new.id, new.title, new.labels);
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_trigram_delete AFTER DELETE ON notes
+         BEGIN
+             INSERT INTO notes_trigram(notes_trigram, rowid, title, labels) VALUES ('delete', old.id, old.title, old.labels);
+         END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS notes_trigram_update AFTER UPDATE OF title, labels ON notes
+         BEGIN
+             INSERT INTO notes_trigram(notes_trigram, rowid, title, labels) VALUES ('delete', old.id, old.title, old.labels);
+             INSERT INTO notes_trigram(rowid, title, labels) VALUES (new.id, new.title, new.labels);
+         END",
+        [],
+    )?;
+
+    // Terms of the full-text index with the number of notes containing each,
+    // for "did you mean" suggestions
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts_vocab USING fts5vocab(notes_fts, row)",
+        [],
+    )?;
+
+    // Backfill the trigram index from the existing notes
+    conn.execute(
+        "INSERT INTO notes_trigram(notes_trigram) VALUES ('rebuild')",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DROP TABLE IF EXISTS notes_fts_vocab", [])?;
+    conn.execute("DROP TRIGGER IF EXISTS notes_trigram_insert", [])?;
+    conn.execute("DROP TRIGGER IF EXISTS notes_trigram_delete", [])?;
+    conn.execute("DROP TRIGGER IF EXISTS notes_trigram_update", [])?;
+    conn.execute("DROP TABLE IF EXISTS notes_trigram", [])?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,25 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Create note_embeddings table holding one vector per note for semantic search
+    // The model and a hash of the embedded text show when a vector is out of date
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS note_embeddings (
+            note_id INTEGER PRIMARY KEY REFERENCES notes(id) ON DELETE CASCADE,
+            model TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            dimensions INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            updated_at INTEGER DEFAULT (strftime('%s', 'now'))
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DROP TABLE IF EXISTS note_embeddings", [])?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,18 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Index matching the note list order, so pages of the list are read
+    // straight from the index after the previous page's last note
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_notes_list_order ON notes(\"order\" ASC, created_at DESC, id DESC)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DROP INDEX IF EXISTS idx_notes_list_order", [])?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,138 @@
+use rusqlite::Connection;
+
+// Triggers that stamp every change to notes and states with the next change
+// sequence number, and record deletions as tombstones
+const CHANGE_TRIGGERS: &[&str] = &[
+    "notes_change_insert",
+    "notes_change_update",
+    "notes_change_delete",
+    "states_change_insert",
+    "states_change_update",
+    "states_change_delete",
+];
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Single-row counter behind change_seq, so numbers keep increasing even
+    // after the most recently changed row is deleted
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS change_sequence (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            value INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // Notes and states that existed before change tracking count as changed in
+    // ID order, notes first, so a first sync from 0 returns them. Each gets a
+    // number of its own, which paging through the changes relies on.
+    for table in ["notes", "states"] {
+        conn.execute(
+            &format!(
+                "ALTER TABLE {} ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 0",
+                table
+            ),
+            [],
+        )?;
+        conn.execute(
+            &format!(
+                "CREATE INDEX IF NOT EXISTS idx_{0}_change_seq ON {0}(change_seq)",
+                table
+            ),
+            [],
+        )?;
+    }
+    conn.execute("UPDATE notes SET change_seq = id", [])?;
+    conn.execute(
+        "UPDATE states SET change_seq = id + (SELECT IFNULL(MAX(id), 0) FROM notes)",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO change_sequence (id, value)
+         SELECT 1, (SELECT IFNULL(MAX(id), 0) FROM notes) + (SELECT IFNULL(MAX(id), 0) FROM states)",
+        [],
+    )?;
+
+    // Deleted notes and states, by the sequence number of their deletion
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tombstones (
+            entity TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            change_seq INTEGER NOT NULL,
+            deleted_at INTEGER DEFAULT (strftime('%s', 'now')),
+            PRIMARY KEY (entity, entity_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_tombstones_change_seq ON tombstones(change_seq)",
+        [],
+    )?;
+
+    for (table, entity) in [("notes", "note"), ("states", "state")] {
+        // A reused ID belongs to a new row, so its old tombstone goes
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER {0}_change_insert AFTER INSERT ON {0}
+                 BEGIN
+                     UPDATE change_sequence SET value = value + 1 WHERE id = 1;
+                     UPDATE {0} SET change_seq = (SELECT value FROM change_sequence WHERE id = 1)
+                     WHERE id = new.id;
+                     DELETE FROM tombstones WHERE entity = '{1}' AND entity_id = new.id;
+                 END",
+                table, entity
+            ),
+            [],
+        )?;
+
+        // The stamp itself is an update of the row, which must not count again
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER {0}_change_update AFTER UPDATE ON {0}
+                 WHEN new.change_seq = old.change_seq
+                 BEGIN
+                     UPDATE change_sequence SET value = value + 1 WHERE id = 1;
+                     UPDATE {0} SET change_seq = (SELECT value FROM change_sequence WHERE id = 1)
+                     WHERE id = new.id;
+                 END",
+                table
+            ),
+            [],
+        )?;
+
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER {0}_change_delete AFTER DELETE ON {0}
+                 BEGIN
+                     UPDATE change_sequence SET value = value + 1 WHERE id = 1;
+                     INSERT OR REPLACE INTO tombstones (entity, entity_id, change_seq)
+                     VALUES ('{1}', old.id, (SELECT value FROM change_sequence WHERE id = 1));
+                 END",
+                table, entity
+            ),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    for trigger in CHANGE_TRIGGERS {
+        conn.execute(&format!("DROP TRIGGER IF EXISTS {}", trigger), [])?;
+    }
+
+    conn.execute("DROP TABLE IF EXISTS tombstones", [])?;
+
+    // Indexed columns can't be dropped, so the indexes go first
+    for table in ["notes", "states"] {
+        conn.execute(
+            &format!("DROP INDEX IF EXISTS idx_{}_change_seq", table),
+            [],
+        )?;
+        conn.execute(&format!("ALTER TABLE {} DROP COLUMN change_seq", table), [])?;
+    }
+
+    conn.execute("DROP TABLE IF EXISTS change_sequence", [])?;
+
+    Ok(())
+}
\ No newline at end of file
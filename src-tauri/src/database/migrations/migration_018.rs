@@ -0,0 +1,67 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Add version column to notes and states for optimistic concurrency: an
+    // update can require the version it was based on
+    for table in ["notes", "states"] {
+        conn.execute(
+            &format!(
+                "ALTER TABLE {} ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+                table
+            ),
+            [],
+        )?;
+    }
+
+    // Every update, through the app or not, bumps the version. It is set
+    // along with change_seq so the row is only rewritten once.
+    for table in ["notes", "states"] {
+        conn.execute(
+            &format!("DROP TRIGGER IF EXISTS {}_change_update", table),
+            [],
+        )?;
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER {0}_change_update AFTER UPDATE ON {0}
+                 WHEN new.change_seq = old.change_seq
+                 BEGIN
+                     UPDATE change_sequence SET value = value + 1 WHERE id = 1;
+                     UPDATE {0} SET
+                         change_seq = (SELECT value FROM change_sequence WHERE id = 1),
+                         version = old.version + 1
+                     WHERE id = new.id;
+                 END",
+                table
+            ),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    // Back to the triggers of migration 17, which only set change_seq
+    for table in ["notes", "states"] {
+        conn.execute(
+            &format!("DROP TRIGGER IF EXISTS {}_change_update", table),
+            [],
+        )?;
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER {0}_change_update AFTER UPDATE ON {0}
+                 WHEN new.change_seq = old.change_seq
+                 BEGIN
+                     UPDATE change_sequence SET value = value + 1 WHERE id = 1;
+                     UPDATE {0} SET change_seq = (SELECT value FROM change_sequence WHERE id = 1)
+                     WHERE id = new.id;
+                 END",
+                table
+            ),
+            [],
+        )?;
+        conn.execute(&format!("ALTER TABLE {} DROP COLUMN version", table), [])?;
+    }
+
+    Ok(())
+}
\ No newline at end of file
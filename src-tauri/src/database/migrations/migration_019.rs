@@ -0,0 +1,23 @@
+use rusqlite::Connection;
+
+pub fn up(conn: &Connection) -> rusqlite::Result<()> {
+    // Notes without a creation time sort as if created at 0, and the list
+    // order index has to use the same expression for pages to be read from it
+    conn.execute("DROP INDEX IF EXISTS idx_notes_list_order", [])?;
+    conn.execute(
+        "CREATE INDEX idx_notes_list_order ON notes(\"order\" ASC, IFNULL(created_at, 0) DESC, id DESC)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub fn down(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DROP INDEX IF EXISTS idx_notes_list_order", [])?;
+    conn.execute(
+        "CREATE INDEX idx_notes_list_order ON notes(\"order\" ASC, created_at DESC, id DESC)",
+        [],
+    )?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -4,4 +4,16 @@ pub mod migration_003;
 pub mod migration_004;
 pub mod migration_005;
 pub mod migration_006;
-pub mod migration_007;
\ No newline at end of file
+pub mod migration_007;
+pub mod migration_008;
+pub mod migration_009;
+pub mod migration_010;
+pub mod migration_011;
+pub mod migration_012;
+pub mod migration_013;
+pub mod migration_014;
+pub mod migration_015;
+pub mod migration_016;
+pub mod migration_017;
+pub mod migration_018;
+pub mod migration_019;
\ No newline at end of file
@@ -0,0 +1,44 @@
+// Maintenance of the FTS5 indexes behind note search: notes_fts for full-text
+// search and notes_trigram for typo-tolerant matching, both with their content
+// in the notes_search view. Triggers keep them in step with the notes row by
+// row; these cover what they can't, such as a database file restored from a
+// backup or written by another tool.
+
+use rusqlite::{Connection, ErrorCode, Result};
+
+const INDEXES: &[&str] = &["notes_fts", "notes_trigram"];
+
+/// Rebuild the indexes from the notes table.
+pub fn rebuild(conn: &Connection) -> Result<()> {
+    for index in INDEXES {
+        conn.execute(
+            &format!("INSERT INTO {index}({index}) VALUES ('rebuild')"),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Run FTS5's integrity check, including the comparison against the notes
+/// table. Returns what is wrong when an index doesn't match, and an error only
+/// when the check itself couldn't run.
+pub fn check(conn: &Connection) -> Result<Option<String>> {
+    for index in INDEXES {
+        let result = conn.execute(
+            &format!("INSERT INTO {index}({index}, rank) VALUES ('integrity-check', 1)"),
+            [],
+        );
+
+        match result {
+            Ok(_) => {}
+            Err(rusqlite::Error::SqliteFailure(e, message))
+                if e.code == ErrorCode::DatabaseCorrupt =>
+            {
+                let problem = message.unwrap_or_else(|| e.to_string());
+                return Ok(Some(format!("{}: {}", index, problem)));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
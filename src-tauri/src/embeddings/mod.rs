@@ -0,0 +1,101 @@
+// Semantic search over notes. Each note's title and content are turned into a
+// vector by a small sentence-embedding model run locally on the CPU, and notes
+// are ranked by cosine similarity to a query or to another note. The model is
+// only compiled in with the `semantic-search` feature, and its files are read
+// from disk, never downloaded.
+
+#[cfg(feature = "semantic-search")]
+mod model;
+#[cfg(feature = "semantic-search")]
+mod worker;
+
+#[cfg(feature = "semantic-search")]
+pub use worker::run_indexing_worker;
+
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+use crate::database::get_database_path;
+use crate::settings;
+
+// Files a model directory needs, as published with sentence-transformers models
+pub const MODEL_FILES: &[&str] = &["config.json", "tokenizer.json", "model.safetensors"];
+
+const DEFAULT_MODEL: &str = "all-MiniLM-L6-v2";
+
+pub const NOT_AVAILABLE: &str =
+    "Semantic search isn't included in this build; build with `--features semantic-search`";
+
+/// Whether this build can compute embeddings.
+pub fn available() -> bool {
+    cfg!(feature = "semantic-search")
+}
+
+/// Directory the model is loaded from: the `embedding_model_dir` setting, or
+/// `juan-note-models/all-MiniLM-L6-v2` next to the database.
+pub fn model_dir() -> PathBuf {
+    match settings::current().embedding_model_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(get_database_path())
+            .with_file_name("juan-note-models")
+            .join(DEFAULT_MODEL),
+    }
+}
+
+/// Name stored with each vector, so vectors from different models are never
+/// compared and a model change re-embeds every note.
+pub fn model_name() -> String {
+    model_dir()
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string())
+}
+
+/// The text embedded for a note.
+pub fn note_text(title: &str, content: &str) -> String {
+    format!("{}\n{}", title, content)
+}
+
+pub fn content_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+/// Embed each text, returning unit-length vectors.
+#[cfg(feature = "semantic-search")]
+pub fn embed(texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    model::get(&model_dir())?.embed(texts)
+}
+
+#[cfg(not(feature = "semantic-search"))]
+pub fn embed(_texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    Err(NOT_AVAILABLE.to_string())
+}
+
+pub fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+pub fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Cosine similarity from -1 to 1; 0 when the vectors can't be compared.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
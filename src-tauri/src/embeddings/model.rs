@@ -0,0 +1,125 @@
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use super::MODEL_FILES;
+
+// Longer notes are embedded from their first tokens; small sentence models
+// are trained on short passages anyway
+const MAX_TOKENS: usize = 256;
+
+// The directory a model was loaded from, and the model
+type LoadedModel = (PathBuf, Arc<Model>);
+
+// Loaded once and kept while the model directory setting stays the same
+static MODEL: OnceLock<Mutex<Option<LoadedModel>>> = OnceLock::new();
+
+/// The model in `dir`, loading it on first use.
+pub fn get(dir: &Path) -> Result<Arc<Model>, String> {
+    let mut loaded = MODEL.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if let Some((loaded_dir, model)) = loaded.as_ref() {
+        if loaded_dir == dir {
+            return Ok(model.clone());
+        }
+    }
+
+    let model = Arc::new(Model::load(dir)?);
+    *loaded = Some((dir.to_path_buf(), model.clone()));
+    Ok(model)
+}
+
+/// A BERT sentence-embedding model such as all-MiniLM-L6-v2, run on the CPU.
+pub struct Model {
+    bert: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl Model {
+    fn load(dir: &Path) -> Result<Self, String> {
+        let missing: Vec<&str> = MODEL_FILES
+            .iter()
+            .copied()
+            .filter(|file| !dir.join(file).is_file())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Model files missing from {}: {}",
+                dir.display(),
+                missing.join(", ")
+            ));
+        }
+
+        let device = Device::Cpu;
+
+        let config = std::fs::read_to_string(dir.join("config.json"))
+            .map_err(|e| format!("Failed to read model config: {}", e))?;
+        let config: Config = serde_json::from_str(&config)
+            .map_err(|e| format!("Failed to parse model config: {}", e))?;
+
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(|e| format!("Failed to load tokenizer: {}", e))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| format!("Failed to configure tokenizer: {}", e))?;
+
+        let weights = std::fs::read(dir.join("model.safetensors"))
+            .map_err(|e| format!("Failed to read model weights: {}", e))?;
+        let vb = VarBuilder::from_buffered_safetensors(weights, DTYPE, &device)
+            .map_err(|e| format!("Failed to load model weights: {}", e))?;
+        let bert =
+            BertModel::load(vb, &config).map_err(|e| format!("Failed to load model: {}", e))?;
+
+        Ok(Model {
+            bert,
+            tokenizer,
+            device,
+        })
+    }
+
+    /// Mean-pooled, L2-normalized sentence embeddings, one per text.
+    pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| format!("Failed to tokenize: {}", e))?;
+
+        self.forward(&encodings)
+            .map_err(|e| format!("Failed to compute embeddings: {}", e))
+    }
+
+    fn forward(&self, encodings: &[tokenizers::Encoding]) -> candle_core::Result<Vec<Vec<f32>>> {
+        let ids = encodings
+            .iter()
+            .map(|encoding| Tensor::new(encoding.get_ids(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()?;
+        let masks = encodings
+            .iter()
+            .map(|encoding| Tensor::new(encoding.get_attention_mask(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()?;
+
+        let ids = Tensor::stack(&ids, 0)?;
+        let mask = Tensor::stack(&masks, 0)?;
+        let token_type_ids = ids.zeros_like()?;
+        let hidden = self.bert.forward(&ids, &token_type_ids, Some(&mask))?;
+
+        // Average the token vectors, leaving out padding
+        let mask = mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let pooled = summed.broadcast_div(&mask.sum(1)?)?;
+
+        let norms = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+        pooled.broadcast_div(&norms)?.to_vec2::<f32>()
+    }
+}
@@ -0,0 +1,68 @@
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+use crate::commands::semantic_commands::index_notes;
+use crate::events;
+use crate::models::{ChangeEntity, ChangeEvent, ChangeOperation};
+
+/// Keep note vectors up to date: catch up on notes changed while the app
+/// wasn't running, then re-embed notes as change events come in. Unchanged
+/// text isn't embedded again, and deleted notes lose their vector through the
+/// foreign key.
+pub async fn run_indexing_worker() {
+    let mut receiver = events::subscribe();
+    // Until the model files are in place every batch fails the same way
+    let mut last_error = None;
+
+    index(None, &mut last_error).await;
+
+    loop {
+        match receiver.recv().await {
+            Ok(event) if wants_embedding(&event) => {
+                // Events that arrived meanwhile are embedded in the same batch
+                let mut note_ids = vec![event.id];
+                let mut lagged = false;
+                loop {
+                    match receiver.try_recv() {
+                        Ok(event) if wants_embedding(&event) => note_ids.push(event.id),
+                        Ok(_) => {}
+                        Err(TryRecvError::Lagged(_)) => lagged = true,
+                        Err(_) => break,
+                    }
+                }
+                note_ids.sort_unstable();
+                note_ids.dedup();
+
+                let note_ids = (!lagged).then_some(note_ids);
+                index(note_ids, &mut last_error).await;
+            }
+            Ok(_) => {}
+            // Missed events: the stored hashes show which notes changed
+            Err(RecvError::Lagged(_)) => index(None, &mut last_error).await,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+fn wants_embedding(event: &ChangeEvent) -> bool {
+    event.entity == ChangeEntity::Note
+        && matches!(
+            event.operation,
+            ChangeOperation::Created | ChangeOperation::Updated
+        )
+}
+
+// Embed the given notes, or every note whose vector is missing or stale
+async fn index(note_ids: Option<Vec<i64>>, last_error: &mut Option<String>) {
+    let result = tokio::task::spawn_blocking(move || index_notes(note_ids.as_deref()))
+        .await
+        .unwrap_or_else(|e| Err(format!("Embedding task failed: {}", e)));
+
+    match result {
+        Ok(_) => *last_error = None,
+        Err(e) if last_error.as_ref() != Some(&e) => {
+            eprintln!("Failed to update note embeddings: {}", e);
+            *last_error = Some(e);
+        }
+        Err(_) => {}
+    }
+}
@@ -0,0 +1,53 @@
+use crate::models::{ChangeEntity, ChangeEvent, ChangeOperation, Note, State};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+// Slow subscribers that fall further behind than this miss events and get a lag notice
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+static EVENT_BUS: OnceLock<broadcast::Sender<ChangeEvent>> = OnceLock::new();
+
+fn get_event_bus() -> &'static broadcast::Sender<ChangeEvent> {
+    EVENT_BUS.get_or_init(|| broadcast::channel(EVENT_BUS_CAPACITY).0)
+}
+
+/// Subscribe to every change published after this call.
+pub fn subscribe() -> broadcast::Receiver<ChangeEvent> {
+    get_event_bus().subscribe()
+}
+
+pub fn publish(
+    entity: ChangeEntity,
+    id: i64,
+    operation: ChangeOperation,
+    data: Option<serde_json::Value>,
+) {
+    let event = ChangeEvent {
+        entity,
+        id,
+        operation,
+        data,
+        timestamp: Utc::now(),
+    };
+
+    // Sending only fails when nobody is listening, which is fine
+    let _ = get_event_bus().send(event);
+}
+
+pub fn publish_note(operation: ChangeOperation, note: &Note) {
+    if let Some(id) = note.id {
+        publish(ChangeEntity::Note, id, operation, to_value(note));
+    }
+}
+
+pub fn publish_state(operation: ChangeOperation, state: &State) {
+    if let Some(id) = state.id {
+        publish(ChangeEntity::State, id, operation, to_value(state));
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Option<serde_json::Value> {
+    serde_json::to_value(value).ok()
+}
@@ -0,0 +1,247 @@
+// Typo-tolerant matching for note search, used when full-text search finds
+// little: candidate notes come from the trigram index, and titles and index
+// terms are compared to the query by edit distance.
+
+use std::collections::HashSet;
+
+// Longer words are only matched exactly or by prefix; comparing them costs
+// time quadratic in their length and they're rarely typos of one another
+const MAX_FUZZY_WORD_LENGTH: usize = 32;
+
+/// Lowercased words of `text`, split on anything that isn't a letter or digit.
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Edit distance between two words, counted in characters: insertions,
+/// deletions, substitutions and swaps of adjacent characters each count as one.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between the first i chars of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+// How many typos a word may contain and still match; short words have to be exact
+fn max_edits(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+// The edit distance from `word` to `candidate` if it's within `word`'s typo
+// allowance. Words over the length cap or too different in length are ruled
+// out before computing it.
+fn typo_distance(word: &str, candidate: &str) -> Option<usize> {
+    let (length, candidate_length) = (word.chars().count(), candidate.chars().count());
+    let edits = max_edits(word);
+    if edits == 0
+        || length.max(candidate_length) > MAX_FUZZY_WORD_LENGTH
+        || length.abs_diff(candidate_length) > edits
+    {
+        return None;
+    }
+
+    let distance = edit_distance(word, candidate);
+    (distance <= edits).then_some(distance)
+}
+
+/// FTS5 query for the trigram index that matches any note sharing a trigram
+/// with the query, or `None` if the query has no word of three or more letters.
+pub fn trigram_query(query: &str) -> Option<String> {
+    let mut seen = HashSet::new();
+    let mut trigrams = Vec::new();
+    for word in words(query) {
+        let chars: Vec<char> = word.chars().collect();
+        for window in chars.windows(3) {
+            let trigram: String = window.iter().collect();
+            if seen.insert(trigram.clone()) {
+                trigrams.push(format!("\"{}\"", trigram));
+            }
+        }
+    }
+
+    (!trigrams.is_empty()).then(|| trigrams.join(" OR "))
+}
+
+/// How well `text` matches the query words, from 0 to 1, or `None` if some
+/// query word has no word in `text` within its typo allowance. A word that is
+/// the start of a word in `text` matches fully.
+pub fn match_score(query_words: &[String], text: &str) -> Option<f64> {
+    if query_words.is_empty() {
+        return None;
+    }
+    let text_words = words(text);
+
+    let mut total = 0.0;
+    for query_word in query_words {
+        let length = query_word.chars().count();
+        let best = text_words
+            .iter()
+            .filter_map(|word| {
+                if word.starts_with(query_word.as_str()) {
+                    return Some(1.0);
+                }
+                typo_distance(query_word, word)
+                    .map(|distance| 1.0 - distance as f64 / length.max(word.chars().count()) as f64)
+            })
+            .max_by(f64::total_cmp)?;
+        total += best;
+    }
+
+    Some(total / query_words.len() as f64)
+}
+
+/// The term closest to `word` within its typo allowance, preferring terms
+/// found in more notes. `terms` are `(term, note count)` pairs.
+pub fn closest_term(word: &str, terms: &[(String, i64)]) -> Option<String> {
+    terms
+        .iter()
+        .filter_map(|(term, notes)| {
+            let distance = typo_distance(word, term)?;
+            (distance > 0).then_some((distance, -notes, term))
+        })
+        .min()
+        .map(|(_, _, term)| term.clone())
+}
+
+/// Lengths a term can have and still be within `word`'s typo allowance.
+pub fn term_length_range(word: &str) -> (usize, usize) {
+    let length = word.chars().count();
+    let edits = max_edits(word);
+    (length.saturating_sub(edits), length + edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_lowercased_words() {
+        assert_eq!(
+            words("Buy MILK, eggs & 2 apples!"),
+            ["buy", "milk", "eggs", "2", "apples"]
+        );
+        assert!(words(" -- ").is_empty());
+    }
+
+    #[test]
+    fn counts_edits_including_adjacent_swaps() {
+        assert_eq!(edit_distance("groceries", "groceries"), 0);
+        assert_eq!(edit_distance("groceries", "grocerie"), 1);
+        assert_eq!(edit_distance("grocries", "groceries"), 1);
+        assert_eq!(edit_distance("groceries", "grocaries"), 1);
+        assert_eq!(edit_distance("groceries", "grocereis"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        // Characters, not bytes
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn allows_more_typos_in_longer_words() {
+        assert_eq!(max_edits("cat"), 0);
+        assert_eq!(max_edits("cats"), 1);
+        assert_eq!(max_edits("meeting"), 1);
+        assert_eq!(max_edits("meetings"), 2);
+    }
+
+    #[test]
+    fn typo_distance_respects_the_allowance_and_length_cap() {
+        assert_eq!(typo_distance("cat", "cut"), None);
+        assert_eq!(typo_distance("cats", "cuts"), Some(1));
+        assert_eq!(typo_distance("cats", "cutes"), None);
+        assert_eq!(typo_distance("meetings", "metings"), Some(1));
+
+        let long = "a".repeat(MAX_FUZZY_WORD_LENGTH + 1);
+        let typo = format!("{}b", &long[1..]);
+        assert_eq!(typo_distance(&long, &typo), None);
+    }
+
+    #[test]
+    fn trigram_query_quotes_and_dedups_trigrams() {
+        assert_eq!(
+            trigram_query("Banana nab").as_deref(),
+            Some(r#""ban" OR "ana" OR "nan" OR "nab""#)
+        );
+        assert_eq!(trigram_query("to do"), None);
+        assert_eq!(trigram_query(""), None);
+    }
+
+    #[test]
+    fn match_score_rewards_prefixes_and_close_words() {
+        let query = strings(&["groc"]);
+        assert_eq!(match_score(&query, "Groceries for Sunday"), Some(1.0));
+
+        let query = strings(&["grocries"]);
+        let score = match_score(&query, "groceries").unwrap();
+        assert!((score - (1.0 - 1.0 / 9.0)).abs() < 1e-9, "{}", score);
+
+        // Every query word has to match something
+        let query = strings(&["grocries", "zebra"]);
+        assert_eq!(match_score(&query, "groceries list"), None);
+        assert_eq!(match_score(&[], "groceries"), None);
+    }
+
+    #[test]
+    fn closest_term_prefers_fewer_edits_then_more_notes() {
+        let terms = vec![
+            ("planning".to_string(), 1),
+            ("plannings".to_string(), 50),
+            ("melting".to_string(), 2),
+            ("welding".to_string(), 9),
+        ];
+
+        // One edit away beats two edits away, however many notes have the term
+        assert_eq!(
+            closest_term("plannning", &terms).as_deref(),
+            Some("planning")
+        );
+        // Equally close terms go to the one in more notes
+        assert_eq!(closest_term("melding", &terms).as_deref(), Some("welding"));
+        // The word itself is never suggested
+        assert_eq!(
+            closest_term("planning", &terms).as_deref(),
+            Some("plannings")
+        );
+        assert_eq!(closest_term("xyzzy", &terms), None);
+    }
+
+    #[test]
+    fn term_length_range_widens_with_the_allowance() {
+        assert_eq!(term_length_range("cat"), (3, 3));
+        assert_eq!(term_length_range("notes"), (4, 6));
+        assert_eq!(term_length_range("meetings"), (6, 10));
+    }
+}
@@ -1,26 +1,48 @@
 use axum::{
-    extract::Json,
-    response::Json as JsonResponse,
-    routing::{delete, get, patch, post, put},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Json, Query,
+    },
+    handler::Handler,
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json as JsonResponse, Response,
+    },
+    middleware,
+    routing::{on, MethodFilter, MethodRouter},
     Router,
 };
 use serde_json::json;
+use chrono::{DateTime, Utc};
+use std::convert::Infallible;
 use std::net::SocketAddr;
-use tower_http::cors::{Any, CorsLayer};
-
-// TODO: Add authentication middleware
-// - Implement JWT token validation
-// - Add Bearer token extraction from Authorization header
-// - Validate token against user sessions
-// - Return 401 Unauthorized for invalid/missing tokens
-// - Consider rate limiting for API endpoints
-
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::auth;
+use crate::calendar;
 use crate::commands::*;
+use crate::events;
+use crate::mcp;
+use crate::openapi;
+use crate::origin_guard;
+use crate::rate_limit;
+use crate::settings;
 use crate::models::*;
 
 // Note Management Endpoints
-async fn create_note_handler(Json(request): Json<CreateNoteRequest>) -> JsonResponse<NoteResponse> {
-    match create_note(request) {
+#[utoipa::path(
+    post, path = "/notes", tag = "notes",
+    request_body = CreateNoteRequest,
+    responses((status = 200, description = "The created note", body = NoteResponse))
+)]
+pub(crate) async fn create_note_handler(Json(request): Json<CreateNoteRequest>) -> JsonResponse<NoteResponse> {
+    match blocking(move || create_note(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(NoteResponse {
             success: false,
@@ -30,19 +52,32 @@ async fn create_note_handler(Json(request): Json<CreateNoteRequest>) -> JsonResp
     }
 }
 
-async fn get_note_handler(axum::extract::Path(id): axum::extract::Path<i64>) -> JsonResponse<NoteResponse> {
-    match get_note(id) {
-        Ok(response) => JsonResponse(response),
+#[utoipa::path(
+    get, path = "/notes/{id}", tag = "notes",
+    params(("id" = i64, Path, description = "Note ID")),
+    responses((status = 200, description = "The note, or `success: false` if it doesn't exist; the ETag header holds its version", body = NoteResponse))
+)]
+pub(crate) async fn get_note_handler(axum::extract::Path(id): axum::extract::Path<i64>) -> Response {
+    match blocking(move || get_note(id)).await {
+        Ok(response) => {
+            let version = response.data.as_ref().map(|note| note.version);
+            versioned_response(response.success, version, response)
+        }
         Err(e) => JsonResponse(NoteResponse {
             success: false,
             data: None,
             error: Some(e),
-        }),
+        })
+        .into_response(),
     }
 }
 
-async fn get_all_notes_handler() -> JsonResponse<NotesListResponse> {
-    match get_all_notes() {
+#[utoipa::path(
+    get, path = "/notes", tag = "notes",
+    responses((status = 200, description = "All notes", body = NotesListResponse))
+)]
+pub(crate) async fn get_all_notes_handler() -> JsonResponse<NotesListResponse> {
+    match blocking(get_all_notes).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(NotesListResponse {
             success: false,
@@ -52,26 +87,106 @@ async fn get_all_notes_handler() -> JsonResponse<NotesListResponse> {
     }
 }
 
-async fn update_note_handler(
+#[utoipa::path(
+    get, path = "/notes/page", tag = "notes",
+    params(ListNotesQuery),
+    responses(
+        (status = 200, description = "A page of the note list, with its entity tag in the ETag header", body = NotesPageResponse),
+        (status = 304, description = "The page's entity tag is one given in If-None-Match")
+    )
+)]
+pub(crate) async fn list_notes_handler(
+    headers: HeaderMap,
+    Query(query): Query<ListNotesQuery>,
+) -> Response {
+    let response = match blocking(move || list_notes(query.into())).await {
+        Ok(response) => response,
+        Err(e) => {
+            return JsonResponse(NotesPageResponse {
+                success: false,
+                data: Vec::new(),
+                total_count: 0,
+                next_cursor: None,
+                etag: None,
+                error: Some(e),
+            })
+            .into_response()
+        }
+    };
+
+    let etag = format!("\"{}\"", response.etag.as_deref().unwrap_or_default());
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == etag || tag == "*")
+        });
+
+    if unchanged {
+        (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response()
+    } else {
+        ([(header::ETAG, etag)], JsonResponse(response)).into_response()
+    }
+}
+
+#[utoipa::path(
+    put, path = "/notes/{id}", tag = "notes",
+    params(
+        ("id" = i64, Path, description = "Note ID"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the note is still at this version, e.g. `\"3\"`; takes precedence over `expected_version`")
+    ),
+    request_body(content = UpdateNoteRequest, description = "Fields to change; the path ID takes precedence over `id`"),
+    responses(
+        (status = 200, description = "The updated note; the ETag header holds its new version", body = NoteResponse),
+        (status = 400, description = "If-Match isn't a version", body = NoteResponse),
+        (status = 409, description = "The note isn't at the expected version; `data` holds it as it is now", body = NoteResponse)
+    )
+)]
+pub(crate) async fn update_note_handler(
     axum::extract::Path(id): axum::extract::Path<i64>,
+    headers: HeaderMap,
     Json(mut request): Json<UpdateNoteRequest>,
-) -> JsonResponse<NoteResponse> {
+) -> Response {
     request.id = id;
-    match update_note(request) {
-        Ok(response) => JsonResponse(response),
+    let expected_version = match if_match_version(&headers) {
+        Ok(expected_version) => expected_version,
+        Err(e) => {
+            let body = NoteResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+            };
+            return (StatusCode::BAD_REQUEST, JsonResponse(body)).into_response();
+        }
+    };
+    request.expected_version = expected_version.or(request.expected_version);
+
+    match blocking(move || update_note(request)).await {
+        Ok(response) => {
+            let version = response.data.as_ref().map(|note| note.version);
+            versioned_response(response.success, version, response)
+        }
         Err(e) => JsonResponse(NoteResponse {
             success: false,
             data: None,
             error: Some(e),
-        }),
+        })
+        .into_response(),
     }
 }
 
-async fn delete_note_handler(
+#[utoipa::path(
+    delete, path = "/notes/{id}", tag = "notes",
+    params(("id" = i64, Path, description = "Note ID")),
+    responses((status = 200, description = "The deleted note", body = NoteResponse))
+)]
+pub(crate) async fn delete_note_handler(
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> JsonResponse<NoteResponse> {
     let request = DeleteNoteRequest { id };
-    match delete_note(request) {
+    match blocking(move || delete_note(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(NoteResponse {
             success: false,
@@ -81,18 +196,30 @@ async fn delete_note_handler(
     }
 }
 
-async fn search_notes_handler(Json(request): Json<SearchRequest>) -> JsonResponse<NotesListResponse> {
-    match search_notes(request) {
+#[utoipa::path(
+    post, path = "/notes/search", tag = "notes",
+    request_body = SearchRequest,
+    responses((status = 200, description = "Notes matching the full-text query, plus typo-tolerant matches when it finds few", body = SearchNotesResponse))
+)]
+pub(crate) async fn search_notes_handler(Json(request): Json<SearchRequest>) -> JsonResponse<SearchNotesResponse> {
+    match blocking(move || search_notes(request)).await {
         Ok(response) => JsonResponse(response),
-        Err(e) => JsonResponse(NotesListResponse {
+        Err(e) => JsonResponse(SearchNotesResponse {
             success: false,
             data: Vec::new(),
+            suggestion: None,
             error: Some(e),
         }),
     }
 }
 
-async fn update_note_done_handler(
+#[utoipa::path(
+    patch, path = "/notes/{id}/done", tag = "notes",
+    params(("id" = i64, Path, description = "Note ID")),
+    request_body(content = Object, description = "`{\"done\": true}`; a missing `done` counts as false"),
+    responses((status = 200, description = "The updated note", body = NoteResponse))
+)]
+pub(crate) async fn update_note_done_handler(
     axum::extract::Path(id): axum::extract::Path<i64>,
     Json(request): Json<serde_json::Value>,
 ) -> JsonResponse<NoteResponse> {
@@ -101,7 +228,7 @@ async fn update_note_done_handler(
         .unwrap_or(false);
 
     let request = UpdateNoteDoneRequest { id, done };
-    match update_note_done(request) {
+    match blocking(move || update_note_done(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(NoteResponse {
             success: false,
@@ -111,9 +238,44 @@ async fn update_note_done_handler(
     }
 }
 
+// Calendar feed of note deadlines
+#[utoipa::path(
+    get, path = "/calendar.ics", tag = "calendar",
+    params(CalendarQuery),
+    responses(
+        (status = 200, description = "iCalendar feed with one entry per note that has a deadline; done notes are COMPLETED (events get a [done] summary prefix) and reminder_minutes becomes a VALARM", body = String, content_type = "text/calendar"),
+        (status = 400, description = "Unknown state", body = String)
+    )
+)]
+pub(crate) async fn calendar_feed_handler(Query(query): Query<CalendarQuery>) -> impl IntoResponse {
+    let (notes, states) = match blocking(|| Ok((get_all_notes()?, get_all_states()?))).await {
+        Ok((notes, states)) => (notes.data, states.data),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    let filtered = match calendar::filter_notes(&notes, &states, &query) {
+        Ok(filtered) => filtered,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let body = calendar::build_calendar(&filtered, &query);
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"juan-note.ics\""),
+        ],
+        body,
+    )
+        .into_response()
+}
+
 // State Management Endpoints
-async fn get_all_states_handler() -> JsonResponse<StatesListResponse> {
-    match get_all_states() {
+#[utoipa::path(
+    get, path = "/states", tag = "states",
+    responses((status = 200, description = "All states, by position", body = StatesListResponse))
+)]
+pub(crate) async fn get_all_states_handler() -> JsonResponse<StatesListResponse> {
+    match blocking(get_all_states).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(StatesListResponse {
             success: false,
@@ -123,8 +285,13 @@ async fn get_all_states_handler() -> JsonResponse<StatesListResponse> {
     }
 }
 
-async fn create_state_handler(Json(request): Json<CreateStateRequest>) -> JsonResponse<StateResponse> {
-    match create_state(request) {
+#[utoipa::path(
+    post, path = "/states", tag = "states",
+    request_body = CreateStateRequest,
+    responses((status = 200, description = "The created state", body = StateResponse))
+)]
+pub(crate) async fn create_state_handler(Json(request): Json<CreateStateRequest>) -> JsonResponse<StateResponse> {
+    match blocking(move || create_state(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(StateResponse {
             success: false,
@@ -134,25 +301,61 @@ async fn create_state_handler(Json(request): Json<CreateStateRequest>) -> JsonRe
     }
 }
 
-async fn update_state_handler(
+#[utoipa::path(
+    put, path = "/states/{id}", tag = "states",
+    params(
+        ("id" = i64, Path, description = "State ID"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the state is still at this version, e.g. `\"3\"`; takes precedence over `expected_version`")
+    ),
+    request_body(content = UpdateStateRequest, description = "Fields to change; the path ID takes precedence over `id`"),
+    responses(
+        (status = 200, description = "The updated state; the ETag header holds its new version", body = StateResponse),
+        (status = 400, description = "If-Match isn't a version", body = StateResponse),
+        (status = 409, description = "The state isn't at the expected version; `data` holds it as it is now", body = StateResponse)
+    )
+)]
+pub(crate) async fn update_state_handler(
     axum::extract::Path(id): axum::extract::Path<i64>,
+    headers: HeaderMap,
     Json(mut request): Json<UpdateStateRequest>,
-) -> JsonResponse<StateResponse> {
+) -> Response {
     request.id = id;
-    match update_state(request) {
-        Ok(response) => JsonResponse(response),
+    let expected_version = match if_match_version(&headers) {
+        Ok(expected_version) => expected_version,
+        Err(e) => {
+            let body = StateResponse {
+                success: false,
+                data: None,
+                error: Some(e),
+            };
+            return (StatusCode::BAD_REQUEST, JsonResponse(body)).into_response();
+        }
+    };
+    request.expected_version = expected_version.or(request.expected_version);
+
+    match blocking(move || update_state(request)).await {
+        Ok(response) => {
+            let version = response.data.as_ref().map(|state| state.version);
+            versioned_response(response.success, version, response)
+        }
         Err(e) => JsonResponse(StateResponse {
             success: false,
             data: None,
             error: Some(e),
-        }),
+        })
+        .into_response(),
     }
 }
 
-async fn delete_state_handler(
+#[utoipa::path(
+    delete, path = "/states/{id}", tag = "states",
+    params(("id" = i64, Path, description = "State ID")),
+    responses((status = 200, description = "The deleted state", body = StateResponse))
+)]
+pub(crate) async fn delete_state_handler(
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> JsonResponse<StateResponse> {
-    match delete_state(id) {
+    match blocking(move || delete_state(id)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(StateResponse {
             success: false,
@@ -163,8 +366,44 @@ async fn delete_state_handler(
 }
 
 // Bulk Operations Endpoints
-async fn bulk_delete_notes_handler(Json(request): Json<BulkDeleteRequest>) -> JsonResponse<BulkOperationResponse> {
-    match bulk_delete_notes(request) {
+type BulkRejection = (StatusCode, JsonResponse<BulkOperationResponse>);
+
+// Reject bulk requests that touch more notes than the configured limit
+fn check_bulk_size(note_ids: &[i64]) -> Result<(), BulkRejection> {
+    let max_bulk_ids = settings::current().limits.max_bulk_ids;
+    if note_ids.len() <= max_bulk_ids {
+        return Ok(());
+    }
+
+    Err((
+        StatusCode::PAYLOAD_TOO_LARGE,
+        JsonResponse(BulkOperationResponse {
+            success: false,
+            successful_count: 0,
+            failed_count: note_ids.len(),
+            errors: None,
+            error: Some(format!(
+                "Bulk requests are limited to {} notes, got {}",
+                max_bulk_ids,
+                note_ids.len()
+            )),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post, path = "/bulk/notes/delete", tag = "bulk",
+    description = "Delete notes",
+    request_body = BulkDeleteRequest,
+    responses(
+        (status = 200, description = "Counts of notes that were and weren't updated", body = BulkOperationResponse),
+        (status = 413, description = "More note_ids than the configured limit", body = BulkOperationResponse)
+    )
+)]
+pub(crate) async fn bulk_delete_notes_handler(Json(request): Json<BulkDeleteRequest>) -> Result<JsonResponse<BulkOperationResponse>, BulkRejection> {
+    check_bulk_size(&request.note_ids)?;
+
+    Ok(match blocking(move || bulk_delete_notes(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(BulkOperationResponse {
             success: false,
@@ -173,11 +412,22 @@ async fn bulk_delete_notes_handler(Json(request): Json<BulkDeleteRequest>) -> Js
             errors: Some(vec![e]),
             error: Some("Bulk delete failed".to_string()),
         }),
-    }
+    })
 }
 
-async fn bulk_update_priority_handler(Json(request): Json<BulkUpdatePriorityRequest>) -> JsonResponse<BulkOperationResponse> {
-    match bulk_update_notes_priority(request) {
+#[utoipa::path(
+    patch, path = "/bulk/notes/priority", tag = "bulk",
+    description = "Set the priority of notes",
+    request_body = BulkUpdatePriorityRequest,
+    responses(
+        (status = 200, description = "Counts of notes that were and weren't updated", body = BulkOperationResponse),
+        (status = 413, description = "More note_ids than the configured limit", body = BulkOperationResponse)
+    )
+)]
+pub(crate) async fn bulk_update_priority_handler(Json(request): Json<BulkUpdatePriorityRequest>) -> Result<JsonResponse<BulkOperationResponse>, BulkRejection> {
+    check_bulk_size(&request.note_ids)?;
+
+    Ok(match blocking(move || bulk_update_notes_priority(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(BulkOperationResponse {
             success: false,
@@ -186,11 +436,22 @@ async fn bulk_update_priority_handler(Json(request): Json<BulkUpdatePriorityRequ
             errors: Some(vec![e]),
             error: Some("Bulk priority update failed".to_string()),
         }),
-    }
+    })
 }
 
-async fn bulk_update_done_handler(Json(request): Json<BulkUpdateDoneRequest>) -> JsonResponse<BulkOperationResponse> {
-    match bulk_update_notes_done(request) {
+#[utoipa::path(
+    patch, path = "/bulk/notes/done", tag = "bulk",
+    description = "Mark notes done or not done",
+    request_body = BulkUpdateDoneRequest,
+    responses(
+        (status = 200, description = "Counts of notes that were and weren't updated", body = BulkOperationResponse),
+        (status = 413, description = "More note_ids than the configured limit", body = BulkOperationResponse)
+    )
+)]
+pub(crate) async fn bulk_update_done_handler(Json(request): Json<BulkUpdateDoneRequest>) -> Result<JsonResponse<BulkOperationResponse>, BulkRejection> {
+    check_bulk_size(&request.note_ids)?;
+
+    Ok(match blocking(move || bulk_update_notes_done(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(BulkOperationResponse {
             success: false,
@@ -199,11 +460,22 @@ async fn bulk_update_done_handler(Json(request): Json<BulkUpdateDoneRequest>) ->
             errors: Some(vec![e]),
             error: Some("Bulk done update failed".to_string()),
         }),
-    }
+    })
 }
 
-async fn bulk_update_state_handler(Json(request): Json<BulkUpdateStateRequest>) -> JsonResponse<BulkOperationResponse> {
-    match bulk_update_notes_state(request) {
+#[utoipa::path(
+    patch, path = "/bulk/notes/state", tag = "bulk",
+    description = "Move notes to a state",
+    request_body = BulkUpdateStateRequest,
+    responses(
+        (status = 200, description = "Counts of notes that were and weren't updated", body = BulkOperationResponse),
+        (status = 413, description = "More note_ids than the configured limit", body = BulkOperationResponse)
+    )
+)]
+pub(crate) async fn bulk_update_state_handler(Json(request): Json<BulkUpdateStateRequest>) -> Result<JsonResponse<BulkOperationResponse>, BulkRejection> {
+    check_bulk_size(&request.note_ids)?;
+
+    Ok(match blocking(move || bulk_update_notes_state(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(BulkOperationResponse {
             success: false,
@@ -212,11 +484,22 @@ async fn bulk_update_state_handler(Json(request): Json<BulkUpdateStateRequest>)
             errors: Some(vec![e]),
             error: Some("Bulk state update failed".to_string()),
         }),
-    }
+    })
 }
 
-async fn bulk_update_order_handler(Json(request): Json<BulkUpdateOrderRequest>) -> JsonResponse<BulkOperationResponse> {
-    match bulk_update_notes_order(request) {
+#[utoipa::path(
+    patch, path = "/bulk/notes/order", tag = "bulk",
+    description = "Set the order of notes; `orders` matches `note_ids` by position",
+    request_body = BulkUpdateOrderRequest,
+    responses(
+        (status = 200, description = "Counts of notes that were and weren't updated", body = BulkOperationResponse),
+        (status = 413, description = "More note_ids than the configured limit", body = BulkOperationResponse)
+    )
+)]
+pub(crate) async fn bulk_update_order_handler(Json(request): Json<BulkUpdateOrderRequest>) -> Result<JsonResponse<BulkOperationResponse>, BulkRejection> {
+    check_bulk_size(&request.note_ids)?;
+
+    Ok(match blocking(move || bulk_update_notes_order(request)).await {
         Ok(response) => JsonResponse(response),
         Err(e) => JsonResponse(BulkOperationResponse {
             success: false,
@@ -225,439 +508,187 @@ async fn bulk_update_order_handler(Json(request): Json<BulkUpdateOrderRequest>)
             errors: Some(vec![e]),
             error: Some("Bulk order update failed".to_string()),
         }),
+    })
+}
+
+// Incremental Sync Endpoint
+#[utoipa::path(
+    get, path = "/changes", tag = "sync",
+    params(ChangesQuery),
+    responses((status = 200, description = "Notes and states changed or deleted after `since`, oldest change first; ask again from `latest_seq` while `has_more` is set", body = ChangesResponse))
+)]
+pub(crate) async fn get_changes_handler(Query(query): Query<ChangesQuery>) -> JsonResponse<ChangesResponse> {
+    let since_seq = query.since.unwrap_or(0);
+    match blocking(move || get_changes(since_seq)).await {
+        Ok(response) => JsonResponse(response),
+        Err(e) => JsonResponse(ChangesResponse {
+            success: false,
+            data: None,
+            error: Some(e),
+        }),
+    }
+}
+
+// API Token Endpoints (admin scope)
+#[utoipa::path(
+    get, path = "/tokens", tag = "tokens",
+    responses((status = 200, description = "All API tokens; only a prefix of each token is shown", body = ApiTokensListResponse))
+)]
+pub(crate) async fn get_all_api_tokens_handler() -> JsonResponse<ApiTokensListResponse> {
+    match blocking(get_all_api_tokens).await {
+        Ok(response) => JsonResponse(response),
+        Err(e) => JsonResponse(ApiTokensListResponse {
+            success: false,
+            data: Vec::new(),
+            error: Some(e),
+        }),
+    }
+}
+
+#[utoipa::path(
+    post, path = "/tokens", tag = "tokens",
+    request_body = CreateApiTokenRequest,
+    responses((status = 200, description = "The created token; `token` holds the full value and is only returned once", body = ApiTokenResponse))
+)]
+pub(crate) async fn create_api_token_handler(Json(request): Json<CreateApiTokenRequest>) -> JsonResponse<ApiTokenResponse> {
+    match blocking(move || create_api_token(request)).await {
+        Ok(response) => JsonResponse(response),
+        Err(e) => JsonResponse(ApiTokenResponse {
+            success: false,
+            data: None,
+            token: None,
+            error: Some(e),
+        }),
+    }
+}
+
+#[utoipa::path(
+    delete, path = "/tokens/{id}", tag = "tokens",
+    params(("id" = i64, Path, description = "API token ID")),
+    responses((status = 200, description = "The revoked token", body = ApiTokenResponse))
+)]
+pub(crate) async fn revoke_api_token_handler(
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> JsonResponse<ApiTokenResponse> {
+    match blocking(move || revoke_api_token(id)).await {
+        Ok(response) => JsonResponse(response),
+        Err(e) => JsonResponse(ApiTokenResponse {
+            success: false,
+            data: None,
+            token: None,
+            error: Some(e),
+        }),
+    }
+}
+
+// Change Event Endpoints
+#[utoipa::path(
+    get, path = "/events", tag = "events",
+    params(EventsQuery),
+    responses((status = 200, description = "Server-Sent Events: a `change` event per change, and `lagged` with the number of missed events when the client fell behind and should refetch", body = ChangeEvent, content_type = "text/event-stream"))
+)]
+pub(crate) async fn events_sse_handler(
+    Query(query): Query<EventsQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(events::subscribe()).filter_map(move |received| {
+        match received {
+            Ok(event) if query.matches(&event) => Some(Ok(Event::default()
+                .event("change")
+                .json_data(&event)
+                .unwrap_or_else(|_| Event::default().event("change")))),
+            Ok(_) => None,
+            // Tell the client it missed events so it can resync with a full fetch
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                Some(Ok(Event::default().event("lagged").data(skipped.to_string())))
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    get, path = "/ws", tag = "events",
+    params(EventsQuery),
+    responses((status = 101, description = "WebSocket with one JSON change event per message, or `{\"type\": \"lagged\", \"skipped\": n}` when events were missed", body = ChangeEvent))
+)]
+pub(crate) async fn events_ws_handler(
+    ws: WebSocketUpgrade,
+    Query(query): Query<EventsQuery>,
+) -> Response {
+    ws.on_upgrade(move |socket| stream_events_to_socket(socket, query))
+}
+
+async fn stream_events_to_socket(mut socket: WebSocket, query: EventsQuery) {
+    let mut receiver = events::subscribe();
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                // Clients only listen; anything but a close frame is ignored
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            received = receiver.recv() => {
+                let payload = match received {
+                    Ok(event) if query.matches(&event) => serde_json::to_string(&event),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => serde_json::to_string(&json!({
+                        "type": "lagged",
+                        "skipped": skipped
+                    })),
+                    Err(RecvError::Closed) => break,
+                };
+
+                let Ok(payload) = payload else { continue };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+        }
     }
 }
 
 // Root API documentation endpoint
-async fn api_documentation() -> JsonResponse<serde_json::Value> {
+#[utoipa::path(
+    get, path = "/", tag = "meta",
+    responses((status = 200, description = "Where to find this document and how to authenticate", body = Object))
+)]
+pub(crate) async fn api_documentation() -> JsonResponse<serde_json::Value> {
+    let port = http_server_status()
+        .port
+        .unwrap_or_else(|| settings::current().server_port);
+    let base_url = format!("http://localhost:{}", port);
+
+    // Endpoints, parameters and schemas are described by the OpenAPI document
     JsonResponse(json!({
         "service": "Juan Note API",
         "version": "1.0.0",
         "description": "REST API for Juan Note application - Note and task management",
-        "base_url": "http://localhost:3001",
+        "base_url": base_url,
+        "openapi": format!("{}/openapi.json", base_url),
         "authentication": {
-            "required": false,
-            "type": "Bearer Token (planned for future)",
-            "header": "Authorization: Bearer <token>"
-        },
-        "endpoints": {
-            "api_documentation": {
-                "path": "/",
-                "method": "GET",
-                "description": "Get complete API documentation and usage instructions",
-                "response": "This comprehensive API documentation (you're reading it!)"
-            },
-            "health": {
-                "path": "/health",
-                "method": "GET",
-                "description": "Check API server health and status",
-                "response": {
-                    "status": "ok",
-                    "service": "juan-note-api",
-                    "version": "1.0.0"
-                }
-            },
-            "get_all_notes": {
-                "path": "/notes",
-                "method": "GET",
-                "description": "Retrieve all notes with optional filtering",
-                "parameters": {
-                    "query": "Optional search query string",
-                    "limit": "Optional maximum number of results (default: 50, max: 1000)",
-                    "offset": "Optional pagination offset (default: 0)"
-                },
-                "response": {
-                    "success": true,
-                    "data": [
-                        {
-                            "id": 1,
-                            "title": "Note title",
-                            "content": "Note content",
-                            "created_at": "2024-01-01T00:00:00Z",
-                            "updated_at": "2024-01-01T00:00:00Z",
-                            "priority": 0,
-                            "labels": ["tag1", "tag2"],
-                            "deadline": "2024-12-31T23:59:59Z",
-                            "reminder_minutes": 0,
-                            "done": false,
-                            "state_id": 1,
-                            "order": 0
-                        }
-                    ],
-                    "error": null
-                }
-            },
-            "create_note": {
-                "path": "/notes",
-                "method": "POST",
-                "description": "Create a new note",
-                "body": {
-                    "title": "Required: Note title",
-                    "content": "Required: Note content",
-                    "priority": "Optional: Priority level (0-5, default: 0)",
-                    "labels": "Optional: Array of string labels",
-                    "deadline": "Optional: ISO 8601 datetime string",
-                    "reminder_minutes": "Optional: Minutes before deadline to remind (default: 0)",
-                    "done": "Optional: Completion status (default: false)",
-                    "state_id": "Optional: State ID for kanban",
-                    "order": "Optional: Display order (default: 0)"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "title": "New note",
-                        "content": "Note content",
-                        "created_at": "2024-01-01T00:00:00Z",
-                        "updated_at": "2024-01-01T00:00:00Z",
-                        "priority": 0,
-                        "labels": [],
-                        "deadline": null,
-                        "reminder_minutes": 0,
-                        "done": false,
-                        "state_id": null,
-                        "order": 0
-                    },
-                    "error": null
-                }
-            },
-            "get_note": {
-                "path": "/notes/{id}",
-                "method": "GET",
-                "description": "Get a specific note by ID",
-                "parameters": {
-                    "id": "Required: Note ID (integer)"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "title": "Note title",
-                        "content": "Note content",
-                        "created_at": "2024-01-01T00:00:00Z",
-                        "updated_at": "2024-01-01T00:00:00Z",
-                        "priority": 0,
-                        "labels": ["tag1"],
-                        "deadline": "2024-12-31T23:59:59Z",
-                        "reminder_minutes": 60,
-                        "done": false,
-                        "state_id": 1,
-                        "order": 0
-                    },
-                    "error": null
-                }
-            },
-            "update_note": {
-                "path": "/notes/{id}",
-                "method": "PUT",
-                "description": "Update an existing note (partial updates allowed)",
-                "parameters": {
-                    "id": "Required: Note ID (integer)"
-                },
-                "body": {
-                    "title": "Optional: New title",
-                    "content": "Optional: New content",
-                    "priority": "Optional: New priority (0-5)",
-                    "labels": "Optional: New array of labels",
-                    "deadline": "Optional: New deadline (ISO 8601)",
-                    "reminder_minutes": "Optional: New reminder minutes",
-                    "done": "Optional: New completion status",
-                    "state_id": "Optional: New state ID",
-                    "order": "Optional: New display order"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "title": "Updated title",
-                        "content": "Updated content",
-                        "updated_at": "2024-01-01T12:00:00Z"
-                    },
-                    "error": null
-                }
-            },
-            "delete_note": {
-                "path": "/notes/{id}",
-                "method": "DELETE",
-                "description": "Delete a note by ID",
-                "parameters": {
-                    "id": "Required: Note ID (integer)"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "title": "Deleted note title",
-                        "content": "Deleted note content"
-                    },
-                    "error": null
-                }
-            },
-            "update_note_done": {
-                "path": "/notes/{id}/done",
-                "method": "PATCH",
-                "description": "Update the completion status of a note",
-                "parameters": {
-                    "id": "Required: Note ID (integer)"
-                },
-                "body": {
-                    "done": "Required: New completion status (boolean)"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "done": true,
-                        "updated_at": "2024-01-01T12:00:00Z"
-                    },
-                    "error": null
-                }
-            },
-            "search_notes": {
-                "path": "/notes/search",
-                "method": "POST",
-                "description": "Search notes using full-text search",
-                "body": {
-                    "query": "Required: Search query string",
-                    "limit": "Optional: Max results (default: 50, max: 1000)",
-                    "offset": "Optional: Pagination offset (default: 0)"
-                },
-                "response": {
-                    "success": true,
-                    "data": [
-                        {
-                            "id": 1,
-                            "title": "Matching note",
-                            "content": "Content with search terms"
-                        }
-                    ],
-                    "error": null
-                }
-            },
-            "get_all_states": {
-                "path": "/states",
-                "method": "GET",
-                "description": "Get all available states for kanban board",
-                "response": {
-                    "success": true,
-                    "data": [
-                        {
-                            "id": 1,
-                            "name": "To Do",
-                            "position": 0,
-                            "color": "#3b82f6",
-                            "created_at": "2024-01-01T00:00:00Z",
-                            "updated_at": "2024-01-01T00:00:00Z"
-                        }
-                    ],
-                    "error": null
-                }
-            },
-            "create_state": {
-                "path": "/states",
-                "method": "POST",
-                "description": "Create a new state for kanban board",
-                "body": {
-                    "name": "Required: State name",
-                    "position": "Required: Display position (integer)",
-                    "color": "Optional: Hex color code (e.g., #3b82f6)"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "name": "New State",
-                        "position": 1,
-                        "color": "#10b981",
-                        "created_at": "2024-01-01T00:00:00Z",
-                        "updated_at": "2024-01-01T00:00:00Z"
-                    },
-                    "error": null
-                }
-            },
-            "update_state": {
-                "path": "/states/{id}",
-                "method": "PUT",
-                "description": "Update an existing state",
-                "parameters": {
-                    "id": "Required: State ID (integer)"
-                },
-                "body": {
-                    "name": "Optional: New name",
-                    "position": "Optional: New position",
-                    "color": "Optional: New color"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "name": "Updated State",
-                        "position": 2,
-                        "updated_at": "2024-01-01T12:00:00Z"
-                    },
-                    "error": null
-                }
-            },
-            "delete_state": {
-                "path": "/states/{id}",
-                "method": "DELETE",
-                "description": "Delete a state by ID",
-                "parameters": {
-                    "id": "Required: State ID (integer)"
-                },
-                "response": {
-                    "success": true,
-                    "data": {
-                        "id": 1,
-                        "name": "Deleted State"
-                    },
-                    "error": null
-                }
-            },
-            "bulk_delete_notes": {
-                "path": "/bulk/notes/delete",
-                "method": "POST",
-                "description": "Delete multiple notes at once",
-                "body": {
-                    "note_ids": "Required: Array of note IDs to delete"
-                },
-                "response": {
-                    "success": true,
-                    "successful_count": 5,
-                    "failed_count": 0,
-                    "errors": null,
-                    "error": null
-                }
-            },
-            "bulk_update_priority": {
-                "path": "/bulk/notes/priority",
-                "method": "PATCH",
-                "description": "Update priority for multiple notes",
-                "body": {
-                    "note_ids": "Required: Array of note IDs",
-                    "priority": "Required: New priority level (0-5)"
-                },
-                "response": {
-                    "success": true,
-                    "successful_count": 3,
-                    "failed_count": 0,
-                    "errors": null,
-                    "error": null
-                }
-            },
-            "bulk_update_done": {
-                "path": "/bulk/notes/done",
-                "method": "PATCH",
-                "description": "Update completion status for multiple notes",
-                "body": {
-                    "note_ids": "Required: Array of note IDs",
-                    "done": "Required: New completion status (boolean)"
-                },
-                "response": {
-                    "success": true,
-                    "successful_count": 2,
-                    "failed_count": 0,
-                    "errors": null,
-                    "error": null
-                }
-            },
-            "bulk_update_state": {
-                "path": "/bulk/notes/state",
-                "method": "PATCH",
-                "description": "Update state for multiple notes",
-                "body": {
-                    "note_ids": "Required: Array of note IDs",
-                    "state_id": "Required: New state ID"
-                },
-                "response": {
-                    "success": true,
-                    "successful_count": 4,
-                    "failed_count": 0,
-                    "errors": null,
-                    "error": null
-                }
-            },
-            "bulk_update_order": {
-                "path": "/bulk/notes/order",
-                "method": "PATCH",
-                "description": "Update display order for multiple notes",
-                "body": {
-                    "note_ids": "Required: Array of note IDs",
-                    "orders": "Required: Array of order values (same length as note_ids)"
-                },
-                "response": {
-                    "success": true,
-                    "successful_count": 3,
-                    "failed_count": 0,
-                    "errors": null,
-                    "error": null
-                }
-            }
-        },
-        "usage_instructions": {
-            "getting_started": [
-                "Start by visiting the root endpoint '/' to get this complete API documentation",
-                "Use '/health' to check if the API server is running",
-                "All endpoints return JSON responses with 'success', 'data', and 'error' fields"
-            ],
-            "general": [
-                "Use 'success: true' to check if the operation succeeded",
-                "Error messages are provided in the 'error' field when success is false",
-                "All timestamps are in ISO 8601 format (UTC)",
-                "Note IDs and State IDs are integers",
-                "Priority levels range from 0 (lowest) to 5 (highest)"
-            ],
-            "search": [
-                "Search supports full-text search across title and content",
-                "Use simple keywords or phrases",
-                "Search is case-insensitive",
-                "Results are ordered by relevance and creation date"
-            ],
-            "bulk_operations": [
-                "Bulk operations are atomic - either all succeed or all fail",
-                "Check 'successful_count' and 'failed_count' in response",
-                "Individual errors are listed in the 'errors' array",
-                "Use bulk operations for better performance with multiple items"
-            ],
-            "states": [
-                "States are used for kanban board organization",
-                "Each note can belong to one state",
-                "States have position for ordering in the UI",
-                "Colors help visually distinguish states"
-            ],
-            "error_handling": [
-                "Always check the 'success' field first",
-                "Common errors: invalid ID, missing required fields, database errors",
-                "Network errors will result in connection failures",
-                "Invalid JSON in request body returns 400 Bad Request"
-            ]
-        },
-        "examples": {
-            "api_documentation": {
-                "curl": "curl http://localhost:3001/",
-                "description": "Get complete API documentation and usage instructions"
-            },
-            "create_note": {
-                "curl": "curl -X POST http://localhost:3001/notes -H 'Content-Type: application/json' -d '{\"title\":\"My Note\",\"content\":\"Note content\"}'",
-                "response": "{\"success\":true,\"data\":{\"id\":1,\"title\":\"My Note\",\"content\":\"Note content\"},\"error\":null}"
-            },
-            "search_notes": {
-                "curl": "curl -X POST http://localhost:3001/notes/search -H 'Content-Type: application/json' -d '{\"query\":\"important\"}'",
-                "response": "{\"success\":true,\"data\":[{\"id\":1,\"title\":\"Important Task\",\"content\":\"This is important\"}],\"error\":null}"
+            "required": true,
+            "type": "Bearer Token",
+            "header": "Authorization: Bearer <token>",
+            "query_parameter": "access_token=<token> (GET requests only, for SSE, WebSocket and calendar clients)",
+            "scopes": {
+                "read": "GET endpoints, POST /notes/search and POST /mcp",
+                "write": "Everything that creates, updates or deletes notes and states (includes read)",
+                "admin": "Managing API tokens under /tokens (includes write)"
             },
-            "bulk_update": {
-                "curl": "curl -X PATCH http://localhost:3001/bulk/notes/done -H 'Content-Type: application/json' -d '{\"note_ids\":[1,2,3],\"done\":true}'",
-                "response": "{\"success\":true,\"successful_count\":3,\"failed_count\":0,\"errors\":null,\"error\":null}"
-            }
+            "public_endpoints": ["/", "/health", "/openapi.json"]
         }
     }))
 }
 
 // Health check endpoint
-async fn health_check() -> JsonResponse<serde_json::Value> {
-    // TODO: Add authentication check for health endpoint
-    // - Verify if user is authenticated
-    // - Return user info in health response
+#[utoipa::path(
+    get, path = "/health", tag = "meta",
+    responses((status = 200, description = "The server is running", body = Object, example = json!({"status": "ok", "service": "juan-note-api", "version": "1.0.0"})))
+)]
+pub(crate) async fn health_check() -> JsonResponse<serde_json::Value> {
+    // Health stays public so clients can detect a running app before authenticating
     JsonResponse(json!({
         "status": "ok",
         "service": "juan-note-api",
@@ -665,70 +696,321 @@ async fn health_check() -> JsonResponse<serde_json::Value> {
     }))
 }
 
-pub fn create_router() -> Router {
-    // TODO: Add authentication middleware layer
-    // - Create auth middleware that validates JWT tokens
-    // - Apply to all routes except health check
-    // - Extract user ID from token for request context
+/// Run a command on the blocking thread pool. Commands wait on SQLite, and
+/// doing that on the async workers would hold up every other request.
+pub(crate) async fn blocking<T, F>(command: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(command)
+        .await
+        .map_err(|e| format!("Command failed: {}", e))?
+}
 
-    Router::new()
-        // API documentation (root endpoint)
-        .route("/", get(api_documentation))
-        // Health check
-        .route("/health", get(health_check))
+/// The version an If-Match header requires, as sent in the ETag of a note or
+/// state. No header, or `*`, means any version.
+fn if_match_version(headers: &HeaderMap) -> Result<Option<i64>, String> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
 
-        // Note management
-        .route("/notes", post(create_note_handler))
-        .route("/notes", get(get_all_notes_handler))
-        .route("/notes/search", post(search_notes_handler))
-        .route("/notes/:id", get(get_note_handler))
-        .route("/notes/:id", put(update_note_handler))
-        .route("/notes/:id", delete(delete_note_handler))
-        .route("/notes/:id/done", patch(update_note_done_handler))
+    // Versions are exact, but a weak tag names the same version
+    value
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("If-Match must be a version like \"3\", got {}", value))
+}
 
-        // State management
-        .route("/states", get(get_all_states_handler))
-        .route("/states", post(create_state_handler))
-        .route("/states/:id", put(update_state_handler))
-        .route("/states/:id", delete(delete_state_handler))
+// Responses about one note or state carry its version as ETag. A failed update
+// that still returns the row lost a version conflict, and gets a 409.
+fn versioned_response(success: bool, version: Option<i64>, body: impl serde::Serialize) -> Response {
+    let status = match version {
+        Some(_) if !success => StatusCode::CONFLICT,
+        _ => StatusCode::OK,
+    };
+    let mut response = (status, JsonResponse(body)).into_response();
+    if let Some(version) = version {
+        if let Ok(etag) = header::HeaderValue::from_str(&format!("\"{}\"", version)) {
+            response.headers_mut().insert(header::ETAG, etag);
+        }
+    }
+    response
+}
+
+// A route as registered with axum: method, path and handler
+pub(crate) type ApiRoute = (Method, &'static str, MethodRouter);
 
+fn route<H, T>(method: Method, path: &'static str, handler: H) -> ApiRoute
+where
+    H: Handler<T, ()>,
+    T: 'static,
+{
+    let filter = MethodFilter::try_from(method.clone()).expect("unsupported HTTP method");
+    (method, path, on(filter, handler))
+}
+
+// Every route the API serves. Each one must also be described in the OpenAPI
+// document, which the tests check.
+pub(crate) fn api_routes() -> Vec<ApiRoute> {
+    vec![
+        // API documentation (root endpoint), OpenAPI document and health check
+        route(Method::GET, "/", api_documentation),
+        route(Method::GET, "/openapi.json", openapi::openapi_json),
+        route(Method::GET, "/health", health_check),
+        // Note management
+        route(Method::POST, "/notes", create_note_handler),
+        route(Method::GET, "/notes", get_all_notes_handler),
+        route(Method::GET, "/notes/page", list_notes_handler),
+        route(Method::POST, "/notes/search", search_notes_handler),
+        route(Method::GET, "/notes/:id", get_note_handler),
+        route(Method::PUT, "/notes/:id", update_note_handler),
+        route(Method::DELETE, "/notes/:id", delete_note_handler),
+        route(Method::PATCH, "/notes/:id/done", update_note_done_handler),
+        // Calendar feed
+        route(Method::GET, "/calendar.ics", calendar_feed_handler),
+        // Change events
+        route(Method::GET, "/events", events_sse_handler),
+        route(Method::GET, "/ws", events_ws_handler),
+        // State management
+        route(Method::GET, "/states", get_all_states_handler),
+        route(Method::POST, "/states", create_state_handler),
+        route(Method::PUT, "/states/:id", update_state_handler),
+        route(Method::DELETE, "/states/:id", delete_state_handler),
         // Bulk operations
-        .route("/bulk/notes/delete", post(bulk_delete_notes_handler))
-        .route("/bulk/notes/priority", patch(bulk_update_priority_handler))
-        .route("/bulk/notes/done", patch(bulk_update_done_handler))
-        .route("/bulk/notes/state", patch(bulk_update_state_handler))
-        .route("/bulk/notes/order", patch(bulk_update_order_handler))
-
-        // TODO: Restrict CORS for production
-        // - Remove allow_origin(Any) and allow_methods(Any)
-        // - Specify allowed origins and methods explicitly
-        // - Add proper CORS headers for security
-        .layer(CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any))
-}
-
-pub async fn start_http_server() -> Result<(), Box<dyn std::error::Error>> {
+        route(Method::POST, "/bulk/notes/delete", bulk_delete_notes_handler),
+        route(Method::PATCH, "/bulk/notes/priority", bulk_update_priority_handler),
+        route(Method::PATCH, "/bulk/notes/done", bulk_update_done_handler),
+        route(Method::PATCH, "/bulk/notes/state", bulk_update_state_handler),
+        route(Method::PATCH, "/bulk/notes/order", bulk_update_order_handler),
+        // Incremental sync
+        route(Method::GET, "/changes", get_changes_handler),
+        // Model Context Protocol
+        route(Method::POST, "/mcp", mcp::http::mcp_handler),
+        // API token management
+        route(Method::GET, "/tokens", get_all_api_tokens_handler),
+        route(Method::POST, "/tokens", create_api_token_handler),
+        route(Method::DELETE, "/tokens/:id", revoke_api_token_handler),
+    ]
+}
+
+pub fn create_router() -> Router {
+    let limits = settings::current().limits;
+
+    api_routes()
+        .into_iter()
+        .fold(Router::new(), |router, (_, path, handler)| router.route(path, handler))
+
+        // Runs after authentication, which identifies the token
+        .layer(middleware::from_fn(rate_limit::limit_by_token))
+
+        // Every route except /, /health and /openapi.json requires an API token
+        .layer(middleware::from_fn(auth::require_api_token))
+        .layer(middleware::from_fn(rate_limit::limit_by_client))
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+
+        .layer(origin_guard::cors_layer())
+
+        // Outermost, so DNS rebinding and cross-site requests are turned away first
+        .layer(middleware::from_fn(origin_guard::validate_host_and_origin))
+}
+
+// Ports tried when the configured one is taken
+const FALLBACK_PORTS: std::ops::RangeInclusive<u16> = 3001..=3100;
+// How long in-flight requests get to finish when the server is stopped
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct ServerState {
+    addr: Option<SocketAddr>,
+    started_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+static SERVER_STATE: OnceLock<Mutex<ServerState>> = OnceLock::new();
+
+fn server_state() -> &'static Mutex<ServerState> {
+    SERVER_STATE.get_or_init(|| Mutex::new(ServerState::default()))
+}
+
+/// Bind the API server and serve it in the background. The configured port is
+/// tried first, then the fallback range; the port that was bound is saved in
+/// the settings so MCP client configs keep working across restarts.
+pub async fn start_http_server() -> Result<SocketAddr, String> {
+    if let Some(addr) = server_state().lock().unwrap().addr {
+        return Err(format!("HTTP server is already running on {}", addr));
+    }
+
+    let listener = match bind_listener().await {
+        Ok(listener) => listener,
+        Err(e) => {
+            server_state().lock().unwrap().last_error = Some(e.clone());
+            return Err(e);
+        }
+    };
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read bound address: {}", e))?;
+
+    if settings::current().server_port != addr.port() {
+        if let Err(e) = settings::update(|settings| settings.server_port = addr.port()) {
+            eprintln!("{}", e);
+        }
+    }
+
+    println!("Starting Juan Note API server on http://{}", addr);
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
     let app = create_router();
 
-    // Try to find an available port starting from 3001
-    let mut port = 3001;
-    let addr = loop {
-        let addr = SocketAddr::from(([127, 0, 0, 1], port));
-        if tokio::net::TcpListener::bind(addr).await.is_ok() {
-            break addr;
+    // Hold the lock while spawning so the task can't clear the state before it's set
+    let mut state = server_state().lock().unwrap();
+    if let Some(running) = state.addr {
+        return Err(format!("HTTP server is already running on {}", running));
+    }
+
+    let task = tokio::spawn(async move {
+        // Peer addresses are needed for per-client rate limiting
+        let result = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        })
+        .await;
+
+        let mut state = server_state().lock().unwrap();
+        if let Err(e) = result {
+            eprintln!("HTTP server stopped with an error: {}", e);
+            state.last_error = Some(format!("HTTP server stopped with an error: {}", e));
+        }
+        // Only clear the state if it still describes this server
+        if state.addr == Some(addr) {
+            state.addr = None;
+            state.started_at = None;
+            state.shutdown = None;
+            state.task = None;
         }
-        port += 1;
-        if port > 3100 {
-            return Err("No available ports found".into());
+    });
+
+    state.addr = Some(addr);
+    state.started_at = Some(Utc::now());
+    state.last_error = None;
+    state.shutdown = Some(shutdown_tx);
+    state.task = Some(task);
+
+    Ok(addr)
+}
+
+/// Stop the API server, giving open requests a moment to finish. Returns
+/// `false` if it wasn't running.
+pub async fn stop_http_server() -> Result<bool, String> {
+    let (shutdown, task) = {
+        let mut state = server_state().lock().unwrap();
+        if state.addr.is_none() {
+            return Ok(false);
         }
+        state.addr = None;
+        state.started_at = None;
+        (state.shutdown.take(), state.task.take())
     };
 
-    println!("Starting Juan Note API server on http://{}", addr);
+    if let Some(shutdown) = shutdown {
+        let _ = shutdown.send(());
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    if let Some(mut task) = task {
+        // Event streams never finish on their own, so don't wait for them forever
+        if tokio::time::timeout(SHUTDOWN_GRACE_PERIOD, &mut task)
+            .await
+            .is_err()
+        {
+            task.abort();
+        }
+    }
 
-    Ok(())
-}
\ No newline at end of file
+    println!("Stopped Juan Note API server");
+    Ok(true)
+}
+
+pub fn http_server_status() -> HttpServerStatus {
+    let state = server_state().lock().unwrap();
+
+    HttpServerStatus {
+        server_running: state.addr.is_some(),
+        address: state.addr.map(|addr| addr.to_string()),
+        port: state.addr.map(|addr| addr.port()),
+        configured_port: settings::current().server_port,
+        started_at: state.started_at,
+        uptime_seconds: state
+            .started_at
+            .map(|started_at| (Utc::now() - started_at).num_seconds()),
+        last_error: state.last_error.clone(),
+    }
+}
+
+async fn bind_listener() -> Result<tokio::net::TcpListener, String> {
+    let configured_port = settings::current().server_port;
+
+    let candidates = std::iter::once(configured_port)
+        .chain(FALLBACK_PORTS.filter(|port| *port != configured_port));
+
+    let mut last_error = None;
+    for port in candidates {
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(format!("{}: {}", addr, e)),
+        }
+    }
+
+    Err(format!(
+        "No available ports found (last error: {})",
+        last_error.unwrap_or_default()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_version_from_if_match() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(if_match_version(&headers), Ok(None));
+
+        for (value, expected) in [("\"3\"", Some(3)), ("W/\"4\"", Some(4)), ("*", None)] {
+            headers.insert(header::IF_MATCH, value.parse().unwrap());
+            assert_eq!(if_match_version(&headers), Ok(expected));
+        }
+
+        headers.insert(header::IF_MATCH, "\"abc\"".parse().unwrap());
+        assert!(if_match_version(&headers).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_an_unreadable_if_match() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, "\"latest\"".parse().unwrap());
+        let request = UpdateStateRequest {
+            id: 1,
+            name: Some("Doing".to_string()),
+            position: None,
+            color: None,
+            expected_version: None,
+        };
+
+        let response =
+            update_state_handler(axum::extract::Path(1), headers, Json(request)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
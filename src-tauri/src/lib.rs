@@ -1,9 +1,24 @@
+mod auth;
+mod calendar;
 mod commands;
-mod database;
+// Public for the benchmarks in benches/
+pub mod database;
+mod embeddings;
+mod events;
+mod fuzzy;
 mod http_server;
+mod mcp;
 mod models;
+mod openapi;
+mod origin_guard;
+mod rate_limit;
+// Public for the benchmarks in benches/
+pub mod repository;
+mod settings;
+mod webhooks;
 
 use commands::*;
+use tauri::Emitter;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -22,9 +37,16 @@ pub fn run() {
             create_note,
             get_note,
             get_all_notes,
+            list_notes,
             update_note,
             delete_note,
             search_notes,
+            check_search_index,
+            rebuild_search_index,
+            semantic_search,
+            similar_notes,
+            get_embedding_status,
+            reindex_embeddings,
             update_note_done,
             reorder_note,
             get_all_states,
@@ -37,18 +59,40 @@ pub fn run() {
             bulk_update_notes_done,
             bulk_update_notes_state,
             bulk_update_notes_order,
+            get_changes,
             scan_mcp_configs,
             query_mcp_functions,
             add_juan_note_mcp_server,
             remove_juan_note_mcp_server,
+            get_mcp_providers,
+            preview_mcp_config_changes,
+            verify_mcp_integrations,
+            repair_mcp_integrations,
             get_mcp_server_config,
             check_http_server_status,
             get_server_port,
-            get_launch_info
+            get_launch_info,
+            start_http_server,
+            stop_http_server,
+            restart_http_server,
+            get_all_webhooks,
+            create_webhook,
+            update_webhook,
+            delete_webhook,
+            get_webhook_deliveries,
+            test_webhook,
+            get_all_api_tokens,
+            create_api_token,
+            revoke_api_token,
+            get_settings,
+            update_settings,
+            call_mcp_tool,
+            get_tool_runs
         ])
         .setup(|app| {
-            // Initialize database on startup
-            let _ = initialize_db();
+            // Initialize database on startup. A database migrated by a newer
+            // build, or one whose schema is broken, stops the app from starting.
+            initialize_db().map_err(|e| format!("Failed to initialize database: {}", e))?;
 
             // Start HTTP server in background
             let app_handle = app.handle().clone();
@@ -58,8 +102,75 @@ pub fn run() {
                 }
             });
 
+            // Deliver change events to registered webhooks
+            tauri::async_runtime::spawn(webhooks::run_delivery_worker());
+
+            // Keep note embeddings up to date for semantic search
+            #[cfg(feature = "semantic-search")]
+            tauri::async_runtime::spawn(embeddings::run_indexing_worker());
+
+            // Forward change events to the webview so it sees edits made over HTTP/MCP
+            let mut change_events = events::subscribe();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match change_events.recv().await {
+                        Ok(event) => {
+                            let _ = app_handle.emit("change-event", &event);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                            let _ = app_handle.emit("change-event-lagged", skipped);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
             Ok(())
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Run as a headless MCP server over stdin/stdout (`juan-note --mcp-stdio`),
+/// without creating a window. Uses the same database as the app.
+pub fn run_mcp_stdio() {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start async runtime");
+
+    runtime.block_on(async {
+        if let Err(e) = initialize_db() {
+            eprintln!("Failed to initialize database: {}", e);
+            std::process::exit(1);
+        }
+
+        // Changes made by MCP clients still reach webhooks
+        tokio::spawn(webhooks::run_delivery_worker());
+        #[cfg(feature = "semantic-search")]
+        tokio::spawn(embeddings::run_indexing_worker());
+
+        if let Err(e) = mcp::stdio::serve().await {
+            eprintln!("MCP stdio server failed: {}", e);
+            std::process::exit(1);
+        }
+    });
+}
+
+/// Roll the database schema back to `target_version`
+/// (`juan-note --migrate-down <version>`), for going back to an older build.
+pub fn run_migrate_down(target_version: i32) {
+    let result = database::writer()
+        .and_then(|mut conn| database::rollback_migrations(&mut conn, target_version));
+
+    match result {
+        Ok(undone) if undone.is_empty() => {
+            println!("Database is already at version {} or older", target_version)
+        }
+        Ok(undone) => println!(
+            "Rolled back migrations {:?}; database is now at version {}",
+            undone, target_version
+        ),
+        Err(e) => {
+            eprintln!("Failed to roll back migrations: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
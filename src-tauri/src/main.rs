@@ -2,5 +2,24 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Launched by an MCP client: serve MCP over stdio instead of opening the app
+    if std::env::args().any(|arg| arg == "--mcp-stdio") {
+        juan_note_lib::run_mcp_stdio();
+        return;
+    }
+
+    // Undo migrations before going back to an older build
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--migrate-down") {
+        match args.get(position + 1).and_then(|v| v.parse::<i32>().ok()) {
+            Some(target_version) => juan_note_lib::run_migrate_down(target_version),
+            None => {
+                eprintln!("Usage: juan-note --migrate-down <version>");
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
     juan_note_lib::run()
 }
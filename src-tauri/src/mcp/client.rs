@@ -0,0 +1,496 @@
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use super::{SERVER_NAME, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::models::McpServerConfig;
+
+// Upper bound for connecting and for each request, so a hung server can't block the caller
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+// Servers with more pages of tools than this are assumed to be paging forever
+const MAX_TOOL_PAGES: usize = 100;
+
+enum Transport {
+    Stdio {
+        child: Box<Child>,
+        stdin: ChildStdin,
+        stdout: Lines<BufReader<ChildStdout>>,
+    },
+    Http {
+        client: reqwest::Client,
+        url: String,
+        headers: Vec<(String, String)>,
+        session_id: Option<String>,
+    },
+}
+
+/// A connection to another MCP server, launched over stdio or reached over HTTP.
+pub struct McpClient {
+    transport: Transport,
+    next_id: i64,
+    timeout: Duration,
+}
+
+impl McpClient {
+    /// Connect and perform the initialize handshake.
+    pub async fn connect(server: &McpServerConfig, timeout: Duration) -> Result<Self, String> {
+        let transport = match &server.url {
+            Some(url) => Transport::Http {
+                client: reqwest::Client::builder()
+                    .timeout(timeout)
+                    .build()
+                    .map_err(|e| format!("Failed to build HTTP client: {}", e))?,
+                url: url.clone(),
+                headers: string_pairs(server.headers.as_ref()),
+                session_id: None,
+            },
+            None => spawn_stdio(server)?,
+        };
+
+        let mut client = Self {
+            transport,
+            next_id: 1,
+            timeout,
+        };
+
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+                    "capabilities": {},
+                    "clientInfo": { "name": SERVER_NAME, "version": env!("CARGO_PKG_VERSION") }
+                }),
+            )
+            .await?;
+        client
+            .notify("notifications/initialized", json!({}))
+            .await?;
+
+        Ok(client)
+    }
+
+    /// All tools the server offers, following `nextCursor` pagination.
+    pub async fn list_tools(&mut self) -> Result<Vec<Value>, String> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut seen_cursors = std::collections::HashSet::new();
+
+        for _ in 0..MAX_TOOL_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+
+            if let Some(page) = result.get("tools").and_then(Value::as_array) {
+                tools.extend(page.iter().cloned());
+            }
+
+            cursor = result
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            match &cursor {
+                None => return Ok(tools),
+                Some(next) if !seen_cursors.insert(next.clone()) => {
+                    return Err(format!("Server repeated the tools/list cursor '{}'", next))
+                }
+                Some(_) => {}
+            }
+        }
+
+        Err(format!(
+            "Server returned more than {} pages of tools",
+            MAX_TOOL_PAGES
+        ))
+    }
+
+    pub async fn call_tool(&mut self, name: &str, arguments: Value) -> Result<Value, String> {
+        self.request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+
+    /// Shut the connection down; stdio servers are killed if they don't exit.
+    pub async fn close(self) {
+        match self.transport {
+            Transport::Stdio {
+                mut child, stdin, ..
+            } => {
+                // Closing stdin is how stdio servers are asked to exit
+                drop(stdin);
+                if tokio::time::timeout(Duration::from_secs(2), child.wait())
+                    .await
+                    .is_err()
+                {
+                    let _ = child.kill().await;
+                }
+            }
+            Transport::Http {
+                client,
+                url,
+                headers,
+                session_id: Some(session_id),
+            } => {
+                let mut request = client.delete(&url).header(SESSION_HEADER, session_id);
+                for (name, value) in &headers {
+                    request = request.header(name, value);
+                }
+                let _ = request.send().await;
+            }
+            Transport::Http { .. } => {}
+        }
+    }
+
+    pub async fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let response = tokio::time::timeout(self.timeout, self.exchange(message, id))
+            .await
+            .map_err(|_| format!("Timed out waiting for {} response", method))??;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!(
+                "{} failed: {}",
+                method,
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+            ));
+        }
+
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| format!("{} response has no result", method))
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> Result<(), String> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+
+        match &mut self.transport {
+            Transport::Stdio { stdin, .. } => write_line(stdin, &message).await,
+            Transport::Http { .. } => self.post(&message).await.map(|_| ()),
+        }
+    }
+
+    // Send a request and wait for the response with the same id
+    async fn exchange(&mut self, message: Value, id: i64) -> Result<Value, String> {
+        match &mut self.transport {
+            Transport::Stdio { stdin, stdout, .. } => {
+                write_line(stdin, &message).await?;
+
+                loop {
+                    let line = stdout
+                        .next_line()
+                        .await
+                        .map_err(|e| format!("Failed to read from server: {}", e))?
+                        .ok_or_else(|| "Server closed its output".to_string())?;
+
+                    // Servers may log or send notifications on stdout as well
+                    let Ok(incoming) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+
+                    if incoming.get("method").is_some() {
+                        if let Some(reply) = reply_to_server_request(&incoming) {
+                            write_line(stdin, &reply).await?;
+                        }
+                        continue;
+                    }
+
+                    if incoming.get("id").and_then(Value::as_i64) == Some(id) {
+                        return Ok(incoming);
+                    }
+                }
+            }
+            Transport::Http { .. } => {
+                let messages = self.post(&message).await?;
+                messages
+                    .into_iter()
+                    .find(|incoming| incoming.get("id").and_then(Value::as_i64) == Some(id))
+                    .ok_or_else(|| "Server sent no response".to_string())
+            }
+        }
+    }
+
+    // POST one message over streamable HTTP and collect the JSON-RPC messages in the reply
+    async fn post(&mut self, message: &Value) -> Result<Vec<Value>, String> {
+        let Transport::Http {
+            client,
+            url,
+            headers,
+            session_id,
+        } = &mut self.transport
+        else {
+            return Err("Not an HTTP connection".to_string());
+        };
+
+        let mut request = client
+            .post(url.as_str())
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
+            .json(message);
+        for (name, value) in headers.iter() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(session_id) = session_id.as_ref() {
+            request = request.header(SESSION_HEADER, session_id.as_str());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach server: {}", e))?;
+
+        if let Some(new_session) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            *session_id = Some(new_session.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Server responded with {}", status));
+        }
+
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+
+        if body.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if is_event_stream {
+            return Ok(parse_event_stream(&body));
+        }
+
+        match serde_json::from_str::<Value>(&body) {
+            Ok(Value::Array(messages)) => Ok(messages),
+            Ok(message) => Ok(vec![message]),
+            Err(e) => Err(format!("Invalid JSON from server: {}", e)),
+        }
+    }
+}
+
+fn spawn_stdio(server: &McpServerConfig) -> Result<Transport, String> {
+    if server.command.is_empty() {
+        return Err(format!("Server '{}' has no command or url", server.name));
+    }
+
+    let mut command = Command::new(&server.command);
+    command
+        .args(server.args.clone().unwrap_or_default())
+        .envs(string_pairs(server.env.as_ref()))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start '{}': {}", server.command, e))?;
+
+    let stdin = child.stdin.take().ok_or("Failed to open server stdin")?;
+    let stdout = child.stdout.take().ok_or("Failed to open server stdout")?;
+
+    Ok(Transport::Stdio {
+        child: Box::new(child),
+        stdin,
+        stdout: BufReader::new(stdout).lines(),
+    })
+}
+
+async fn write_line(stdin: &mut ChildStdin, message: &Value) -> Result<(), String> {
+    let mut line = message.to_string();
+    line.push('\n');
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to server: {}", e))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to write to server: {}", e))
+}
+
+// Answer pings; decline anything else the server asks of us (sampling, roots, ...)
+fn reply_to_server_request(incoming: &Value) -> Option<Value> {
+    let id = incoming.get("id")?.clone();
+
+    Some(match incoming.get("method").and_then(Value::as_str) {
+        Some("ping") => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": super::METHOD_NOT_FOUND, "message": "Not supported by this client" }
+        }),
+    })
+}
+
+fn parse_event_stream(body: &str) -> Vec<Value> {
+    let mut messages = Vec::new();
+    let mut data = String::new();
+
+    // Events are separated by blank lines; data may span several `data:` lines
+    for line in body.lines().chain(std::iter::once("")) {
+        if let Some(chunk) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(chunk.strip_prefix(' ').unwrap_or(chunk));
+        } else if line.is_empty() && !data.is_empty() {
+            if let Ok(message) = serde_json::from_str(&data) {
+                messages.push(message);
+            }
+            data.clear();
+        }
+    }
+
+    messages
+}
+
+fn string_pairs(value: Option<&Value>) -> Vec<(String, String)> {
+    value
+        .and_then(Value::as_object)
+        .map(|object| {
+            object
+                .iter()
+                .filter_map(|(key, value)| {
+                    let value = match value {
+                        Value::String(value) => value.clone(),
+                        Value::Number(_) | Value::Bool(_) => value.to_string(),
+                        _ => return None,
+                    };
+                    Some((key.clone(), value))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode, Uri};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+    use axum::{Json, Router};
+
+    const STUB_SESSION: &str = "stub-session";
+
+    // A streamable HTTP server with two pages of tools, the first sent as an event
+    // stream. Under /looping it hands out the same cursor forever.
+    async fn stub_server(uri: Uri, headers: HeaderMap, Json(message): Json<Value>) -> Response {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let in_session = headers
+            .get(SESSION_HEADER)
+            .is_some_and(|value| value == STUB_SESSION);
+
+        let result = match method {
+            "initialize" => {
+                let result = json!({
+                    "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "stub", "version": "1.0.0" }
+                });
+                let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                return ([(SESSION_HEADER, STUB_SESSION)], Json(reply)).into_response();
+            }
+            _ if !in_session => return StatusCode::BAD_REQUEST.into_response(),
+            "notifications/initialized" => return StatusCode::ACCEPTED.into_response(),
+            "tools/list" if uri.path() == "/looping" => {
+                json!({ "tools": [{ "name": "again" }], "nextCursor": "same" })
+            }
+            "tools/list" => match message.pointer("/params/cursor").and_then(Value::as_str) {
+                None => {
+                    let reply = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": { "tools": [{ "name": "first" }], "nextCursor": "2" }
+                    });
+                    return (
+                        [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+                        format!("event: message\ndata: {}\n\n", reply),
+                    )
+                        .into_response();
+                }
+                Some(_) => json!({ "tools": [{ "name": "second" }] }),
+            },
+            _ => return StatusCode::NOT_FOUND.into_response(),
+        };
+
+        Json(json!({ "jsonrpc": "2.0", "id": id, "result": result })).into_response()
+    }
+
+    async fn connect_to_stub(path: &str) -> McpClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(path, post(stub_server).delete(|| async { StatusCode::OK }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let server = McpServerConfig {
+            name: "stub".to_string(),
+            command: String::new(),
+            args: None,
+            env: None,
+            url: Some(format!("http://{}{}", addr, path)),
+            headers: None,
+        };
+        McpClient::connect(&server, DEFAULT_TIMEOUT).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_tools_over_streamable_http() {
+        let mut client = connect_to_stub("/mcp").await;
+        let tools = client.list_tools().await.unwrap();
+        client.close().await;
+
+        let names: Vec<&str> = tools
+            .iter()
+            .filter_map(|tool| tool.get("name").and_then(Value::as_str))
+            .collect();
+        assert_eq!(names, ["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn stops_when_the_server_repeats_a_cursor() {
+        let mut client = connect_to_stub("/looping").await;
+        let error = client.list_tools().await.unwrap_err();
+        client.close().await;
+
+        assert!(error.contains("repeated"), "{}", error);
+    }
+
+    #[test]
+    fn parses_multi_line_event_data() {
+        let body = "event: message\ndata: {\"id\":\ndata: 1}\n\ndata: not json\n\n";
+
+        assert_eq!(parse_event_stream(body), vec![json!({ "id": 1 })]);
+    }
+}
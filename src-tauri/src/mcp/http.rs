@@ -0,0 +1,90 @@
+use axum::{
+    body::Bytes,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use rand::RngCore;
+use serde_json::Value;
+
+use super::{contains_request, error_response, handle_payload, McpContext, RpcError, PARSE_ERROR};
+use crate::models::{ApiToken, TokenScope};
+
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Streamable HTTP transport. Every POST carries one JSON-RPC message or batch
+/// and requests are answered with a plain JSON body; the server never starts
+/// an SSE stream, so GET isn't routed and gets 405.
+#[utoipa::path(
+    post,
+    path = "/mcp",
+    tag = "mcp",
+    description = "JSON-RPC 2.0 over the MCP streamable HTTP transport. Tools cover notes, \
+        states and bulk operations (those that change data need the write scope), notes are \
+        resources at note://{id}, and prompts are listed by prompts/list. The same server is \
+        available over stdio by launching the app with --mcp-stdio.",
+    request_body(content = Object, description = "A JSON-RPC message or batch"),
+    responses(
+        (status = 200, description = "JSON-RPC response or batch of responses; `initialize` \
+            also returns an Mcp-Session-Id header", body = Object),
+        (status = 202, description = "The payload only held notifications or responses"),
+        (status = 400, description = "The body isn't valid JSON", body = Object)
+    )
+)]
+pub async fn mcp_handler(token: Option<Extension<ApiToken>>, body: Bytes) -> Response {
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_response(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+                )),
+            )
+                .into_response()
+        }
+    };
+
+    let context = McpContext {
+        can_write: token.is_some_and(|Extension(token)| token.has_scope(TokenScope::Write)),
+    };
+
+    let starts_session = is_initialize(&payload);
+    let expects_response = contains_request(&payload);
+
+    // Tool calls run commands that wait on the database
+    let response =
+        match tokio::task::spawn_blocking(move || handle_payload(payload, &context)).await {
+            Ok(response) => response,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+
+    if !expects_response {
+        return StatusCode::ACCEPTED.into_response();
+    }
+    let Some(response) = response else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+
+    let mut response = Json(response).into_response();
+    if starts_session {
+        if let Ok(session_id) = HeaderValue::from_str(&generate_session_id()) {
+            response.headers_mut().insert(SESSION_HEADER, session_id);
+        }
+    }
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+fn is_initialize(payload: &Value) -> bool {
+    payload.get("method").and_then(Value::as_str) == Some("initialize")
+}
+
+fn generate_session_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
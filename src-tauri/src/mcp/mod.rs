@@ -0,0 +1,239 @@
+// Native MCP server: JSON-RPC 2.0 over streamable HTTP (`POST /mcp`) and stdio,
+// exposing the note, state and bulk commands as tools, notes as resources and
+// a few prompts.
+
+pub mod client;
+pub mod http;
+mod prompts;
+pub mod providers;
+mod resources;
+pub mod stdio;
+mod tools;
+
+use serde_json::{json, Value};
+
+pub const SERVER_NAME: &str = "juan-note";
+
+// Newest first; the first one is offered when the client asks for something else
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+// MCP-specific: `resources/read` for a uri that doesn't exist
+pub const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// What the caller on the other end of a transport is allowed to do.
+#[derive(Debug, Clone, Copy)]
+pub struct McpContext {
+    // False for read-only API tokens; tools that change data are refused
+    pub can_write: bool,
+}
+
+#[derive(Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+/// Handle a decoded JSON-RPC payload, which may be a single message or a batch.
+/// Returns `None` when nothing needs to be sent back (notifications and responses).
+pub fn handle_payload(payload: Value, context: &McpContext) -> Option<Value> {
+    match payload {
+        Value::Array(messages) if messages.is_empty() => Some(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Empty batch"),
+        )),
+        Value::Array(messages) => {
+            let responses: Vec<Value> = messages
+                .into_iter()
+                .filter_map(|message| handle_message(message, context))
+                .collect();
+            (!responses.is_empty()).then(|| Value::Array(responses))
+        }
+        message => handle_message(message, context),
+    }
+}
+
+/// Whether a payload contains at least one request, i.e. something that expects a response.
+pub fn contains_request(payload: &Value) -> bool {
+    match payload {
+        Value::Array(messages) => messages.iter().any(is_request),
+        message => is_request(message),
+    }
+}
+
+fn is_request(message: &Value) -> bool {
+    message.get("method").is_some() && message.get("id").is_some()
+}
+
+fn handle_message(message: Value, context: &McpContext) -> Option<Value> {
+    let Value::Object(message) = message else {
+        return Some(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Message must be an object"),
+        ));
+    };
+
+    let id = message.get("id").cloned();
+
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        // Responses to requests we never send; nothing to do
+        if message.contains_key("result") || message.contains_key("error") {
+            return None;
+        }
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "Missing method"),
+        ));
+    };
+
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = dispatch(method, params, context);
+
+    // Notifications never get a response, not even an error
+    let id = id?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn dispatch(method: &str, params: Value, context: &McpContext) -> Result<Value, RpcError> {
+    match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "notifications/initialized" | "notifications/cancelled" => Ok(Value::Null),
+        "tools/list" => Ok(tools::list()),
+        "tools/call" => tools::call(params, context),
+        "resources/list" => resources::list(),
+        "resources/templates/list" => Ok(resources::templates()),
+        "resources/read" => resources::read(params),
+        "prompts/list" => Ok(prompts::list()),
+        "prompts/get" => prompts::get(params),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let protocol_version = requested
+        .filter(|version| SUPPORTED_PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": {
+            "tools": { "listChanged": false },
+            "resources": { "subscribe": false, "listChanged": false },
+            "prompts": { "listChanged": false }
+        },
+        "serverInfo": {
+            "name": SERVER_NAME,
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "instructions": "Juan Note is a note and task manager. Notes have a title, content, priority (0-5), labels, an optional deadline and a state (a kanban column). Use get_all_states to find state ids before moving notes between states."
+    })
+}
+
+pub fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READ_ONLY: McpContext = McpContext { can_write: false };
+
+    fn request(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params })
+    }
+
+    #[test]
+    fn initialize_agrees_on_a_supported_protocol_version() {
+        let response = handle_payload(
+            request("initialize", json!({ "protocolVersion": "2025-03-26" })),
+            &READ_ONLY,
+        )
+        .unwrap();
+
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(response["result"]["serverInfo"]["name"], SERVER_NAME);
+
+        // Unknown versions get the newest one we support
+        let response = handle_payload(
+            request("initialize", json!({ "protocolVersion": "1999-01-01" })),
+            &READ_ONLY,
+        )
+        .unwrap();
+        assert_eq!(
+            response["result"]["protocolVersion"],
+            SUPPORTED_PROTOCOL_VERSIONS[0]
+        );
+    }
+
+    #[test]
+    fn lists_tools_with_their_schemas() {
+        let response = handle_payload(request("tools/list", Value::Null), &READ_ONLY).unwrap();
+
+        let tools = response["result"]["tools"].as_array().unwrap();
+        let create = tools
+            .iter()
+            .find(|tool| tool["name"] == "create_note")
+            .unwrap();
+        assert_eq!(create["inputSchema"]["type"], "object");
+        assert_eq!(create["annotations"]["readOnlyHint"], false);
+    }
+
+    #[test]
+    fn unknown_methods_are_not_found() {
+        let response = handle_payload(request("notes/explode", Value::Null), &READ_ONLY).unwrap();
+
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+
+        assert!(!contains_request(&notification));
+        assert_eq!(handle_payload(notification, &READ_ONLY), None);
+    }
+
+    #[test]
+    fn write_tools_are_refused_to_read_only_callers() {
+        let params = json!({ "name": "create_note", "arguments": { "title": "Nope" } });
+        let response = handle_payload(request("tools/call", params), &READ_ONLY).unwrap();
+
+        assert_eq!(response["result"]["isError"], true);
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("write scope"), "{}", text);
+    }
+}
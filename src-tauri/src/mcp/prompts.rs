@@ -0,0 +1,200 @@
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+
+use super::{RpcError, INTERNAL_ERROR};
+use crate::commands::*;
+use crate::models::{Note, State};
+
+struct Prompt {
+    name: &'static str,
+    description: &'static str,
+    // (name, description, required)
+    arguments: &'static [(&'static str, &'static str, bool)],
+}
+
+const PROMPTS: &[Prompt] = &[
+    Prompt {
+        name: "summarize_notes",
+        description: "Summarize open notes, optionally limited to a label or section",
+        arguments: &[
+            ("label", "Only include notes with this label", false),
+            ("section", "Only include notes in this section", false),
+        ],
+    },
+    Prompt {
+        name: "plan_upcoming",
+        description: "Plan the work for notes whose deadline is coming up",
+        arguments: &[("days", "How many days ahead to look (default 7)", false)],
+    },
+    Prompt {
+        name: "triage_note",
+        description: "Suggest a priority, labels and state for a note",
+        arguments: &[("id", "Note ID", true)],
+    },
+];
+
+pub fn list() -> Value {
+    let prompts: Vec<Value> = PROMPTS
+        .iter()
+        .map(|prompt| {
+            let arguments: Vec<Value> = prompt
+                .arguments
+                .iter()
+                .map(|(name, description, required)| {
+                    json!({ "name": name, "description": description, "required": required })
+                })
+                .collect();
+
+            json!({
+                "name": prompt.name,
+                "description": prompt.description,
+                "arguments": arguments
+            })
+        })
+        .collect();
+
+    json!({ "prompts": prompts })
+}
+
+pub fn get(params: Value) -> Result<Value, RpcError> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params("Missing prompt name"))?;
+
+    // Prompt arguments are always strings
+    let argument = |key: &str| {
+        params
+            .get("arguments")
+            .and_then(|arguments| arguments.get(key))
+            .and_then(Value::as_str)
+            .filter(|value| !value.is_empty())
+    };
+
+    let notes = get_all_notes()
+        .map_err(|e| RpcError::new(INTERNAL_ERROR, e))?
+        .data;
+    let states = get_all_states()
+        .map_err(|e| RpcError::new(INTERNAL_ERROR, e))?
+        .data;
+
+    let (description, text) = match name {
+        "summarize_notes" => {
+            let label = argument("label");
+            let section = argument("section");
+            let selected: Vec<&Note> = notes
+                .iter()
+                .filter(|note| !note.done)
+                .filter(|note| label.is_none_or(|l| note.labels.iter().any(|nl| nl == l)))
+                .filter(|note| section.is_none_or(|s| note.section == s))
+                .collect();
+
+            (
+                "Summarize open notes",
+                format!(
+                    "Summarize these open notes from Juan Note. Group related notes, call out anything urgent or blocked, and keep it short.\n\n{}",
+                    format_notes(&selected, &states)
+                ),
+            )
+        }
+        "plan_upcoming" => {
+            let days: i64 = match argument("days") {
+                Some(days) => days
+                    .parse()
+                    .map_err(|_| RpcError::invalid_params("days must be a number"))?,
+                None => 7,
+            };
+            let horizon = Utc::now() + Duration::days(days);
+
+            let mut selected: Vec<&Note> = notes
+                .iter()
+                .filter(|note| !note.done)
+                .filter(|note| note.deadline.is_some_and(|deadline| deadline <= horizon))
+                .collect();
+            selected.sort_by_key(|note| note.deadline);
+
+            (
+                "Plan upcoming deadlines",
+                format!(
+                    "These notes are due within the next {} days (overdue ones included). Propose an order to work on them and flag any that look unrealistic.\n\n{}",
+                    days,
+                    format_notes(&selected, &states)
+                ),
+            )
+        }
+        "triage_note" => {
+            let id: i64 = argument("id")
+                .ok_or_else(|| RpcError::invalid_params("Missing required argument: id"))?
+                .parse()
+                .map_err(|_| RpcError::invalid_params("id must be a number"))?;
+            let note = notes
+                .iter()
+                .find(|note| note.id == Some(id))
+                .ok_or_else(|| RpcError::invalid_params(format!("Note {} not found", id)))?;
+
+            let state_names: Vec<String> = states
+                .iter()
+                .map(|state| format!("{} (id {})", state.name, state.id.unwrap_or_default()))
+                .collect();
+
+            (
+                "Triage a note",
+                format!(
+                    "Suggest a priority (0-5), labels and a state for this note. Available states: {}. Reply with the update_note arguments you would use.\n\n{}",
+                    state_names.join(", "),
+                    format_notes(&[note], &states)
+                ),
+            )
+        }
+        _ => {
+            return Err(RpcError::invalid_params(format!(
+                "Unknown prompt: {}",
+                name
+            )))
+        }
+    };
+
+    Ok(json!({
+        "description": description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": text }
+        }]
+    }))
+}
+
+fn format_notes(notes: &[&Note], states: &[State]) -> String {
+    if notes.is_empty() {
+        return "(no matching notes)".to_string();
+    }
+
+    notes
+        .iter()
+        .map(|note| {
+            let state = note
+                .state_id
+                .and_then(|state_id| states.iter().find(|state| state.id == Some(state_id)))
+                .map(|state| state.name.as_str())
+                .unwrap_or("none");
+
+            let mut line = format!(
+                "- #{} {} [priority {}, state {}]",
+                note.id.unwrap_or_default(),
+                note.title,
+                note.priority,
+                state
+            );
+            if let Some(deadline) = note.deadline {
+                line.push_str(&format!(" due {}", deadline.format("%Y-%m-%d %H:%M UTC")));
+            }
+            if !note.labels.is_empty() {
+                line.push_str(&format!(" labels: {}", note.labels.join(", ")));
+            }
+            if !note.content.is_empty() {
+                line.push_str(&format!("\n  {}", note.content.replace('\n', "\n  ")));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
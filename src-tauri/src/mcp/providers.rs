@@ -0,0 +1,575 @@
+// Config writers for the MCP clients we know about. Each client keeps its
+// servers under its own key with its own entry shape, so an edit only ever
+// touches that key in that client's config file.
+
+use chrono::Local;
+use serde_json::{json, Map, Value};
+use similar::TextDiff;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How a client should reach Juan Note.
+pub enum ServerTarget {
+    Stdio {
+        command: String,
+        args: Vec<String>,
+    },
+    Http {
+        url: String,
+        headers: Map<String, Value>,
+    },
+}
+
+pub struct Provider {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Key of the name → server map. Taken literally: Amp's `amp.mcpServers`
+    /// is a single flat key, not a nested object.
+    pub servers_key: &'static str,
+    config_path: fn(&Path) -> PathBuf,
+    // The client counts as installed when this directory exists
+    app_dir: fn(&Path) -> PathBuf,
+    // `None` when the client can't use that transport from its config file
+    entry: fn(&ServerTarget) -> Option<Value>,
+}
+
+pub static PROVIDERS: &[Provider] = &[
+    Provider {
+        id: "claude-desktop",
+        name: "Claude Desktop",
+        servers_key: "mcpServers",
+        config_path: |home| app_support_dir(home).join("Claude/claude_desktop_config.json"),
+        app_dir: |home| app_support_dir(home).join("Claude"),
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => Some(json!({
+                "command": command,
+                "args": args
+            })),
+            ServerTarget::Http { .. } => None,
+        },
+    },
+    Provider {
+        id: "claude-code",
+        name: "Claude Code",
+        servers_key: "mcpServers",
+        config_path: |home| home.join(".claude.json"),
+        app_dir: |home| home.join(".claude"),
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => Some(json!({
+                "type": "stdio",
+                "command": command,
+                "args": args,
+                "env": {}
+            })),
+            ServerTarget::Http { url, headers } => Some(json!({
+                "type": "http",
+                "url": url,
+                "headers": headers
+            })),
+        },
+    },
+    Provider {
+        id: "opencode",
+        name: "opencode",
+        servers_key: "mcp",
+        config_path: |home| home.join(".config/opencode/opencode.json"),
+        app_dir: |home| home.join(".config/opencode"),
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => {
+                let mut parts = vec![command.clone()];
+                parts.extend(args.iter().cloned());
+                Some(json!({
+                    "type": "local",
+                    "command": parts,
+                    "enabled": true
+                }))
+            }
+            ServerTarget::Http { url, headers } => Some(json!({
+                "type": "remote",
+                "url": url,
+                "headers": headers,
+                "enabled": true
+            })),
+        },
+    },
+    Provider {
+        id: "gemini",
+        name: "Gemini CLI",
+        servers_key: "mcpServers",
+        config_path: |home| home.join(".gemini/settings.json"),
+        app_dir: |home| home.join(".gemini"),
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => Some(json!({
+                "command": command,
+                "args": args
+            })),
+            ServerTarget::Http { url, headers } => Some(json!({
+                "httpUrl": url,
+                "headers": headers
+            })),
+        },
+    },
+    Provider {
+        id: "cline",
+        name: "Cline",
+        servers_key: "mcpServers",
+        config_path: |home| cline_dir(home).join("settings/cline_mcp_settings.json"),
+        app_dir: cline_dir,
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => Some(json!({
+                "command": command,
+                "args": args,
+                "disabled": false,
+                "autoApprove": []
+            })),
+            ServerTarget::Http { url, headers } => Some(json!({
+                "type": "streamableHttp",
+                "url": url,
+                "headers": headers,
+                "disabled": false,
+                "autoApprove": []
+            })),
+        },
+    },
+    Provider {
+        // Continue reads every file in ~/.continue/mcpServers, so Juan Note gets its own
+        id: "continue",
+        name: "Continue",
+        servers_key: "mcpServers",
+        config_path: |home| home.join(".continue/mcpServers/juan-note.json"),
+        app_dir: |home| home.join(".continue"),
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => Some(json!({
+                "command": command,
+                "args": args
+            })),
+            ServerTarget::Http { .. } => None,
+        },
+    },
+    Provider {
+        id: "amp",
+        name: "Amp",
+        servers_key: "amp.mcpServers",
+        config_path: |home| home.join(".config/amp/settings.json"),
+        app_dir: |home| home.join(".config/amp"),
+        entry: |target| match target {
+            ServerTarget::Stdio { command, args } => Some(json!({
+                "command": command,
+                "args": args
+            })),
+            ServerTarget::Http { url, headers } => Some(json!({
+                "url": url,
+                "headers": headers
+            })),
+        },
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static Provider> {
+    PROVIDERS.iter().find(|provider| provider.id == id)
+}
+
+/// A config file edit that hasn't been written yet.
+pub struct PlannedEdit {
+    pub path: PathBuf,
+    pub existed: bool,
+    pub before: String,
+    pub after: String,
+}
+
+impl Provider {
+    pub fn config_path(&self, home: &Path) -> PathBuf {
+        (self.config_path)(home)
+    }
+
+    pub fn is_installed(&self, home: &Path) -> bool {
+        (self.app_dir)(home).is_dir() || self.config_path(home).is_file()
+    }
+
+    pub fn entry(&self, target: &ServerTarget) -> Result<Value, String> {
+        (self.entry)(target).ok_or_else(|| {
+            let transport = match target {
+                ServerTarget::Stdio { .. } => "stdio",
+                ServerTarget::Http { .. } => "HTTP",
+            };
+            format!(
+                "{} can't be configured with {} servers from its config file",
+                self.name, transport
+            )
+        })
+    }
+
+    /// The configured servers in a parsed config file.
+    pub fn servers<'a>(&self, config: &'a Value) -> Option<&'a Map<String, Value>> {
+        config.get(self.servers_key)?.as_object()
+    }
+
+    /// Work out the new contents of this client's config with `server_name` set
+    /// to `entry`, or removed when `entry` is `None`. Nothing is written.
+    pub fn plan_edit(
+        &self,
+        home: &Path,
+        server_name: &str,
+        entry: Option<Value>,
+    ) -> Result<PlannedEdit, String> {
+        let path = self.config_path(home);
+        let existed = path.is_file();
+        let before = if existed {
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read config file: {}", e))?
+        } else {
+            String::new()
+        };
+
+        let mut config = if before.trim().is_empty() {
+            Value::Object(Map::new())
+        } else {
+            serde_json::from_str::<Value>(&before)
+                .map_err(|e| format!("Failed to parse config file: {}", e))?
+        };
+        let root = config
+            .as_object_mut()
+            .ok_or_else(|| "Config file is not a JSON object".to_string())?;
+
+        match entry {
+            Some(entry) => {
+                let servers = root
+                    .entry(self.servers_key)
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                    .ok_or_else(|| {
+                        format!("'{}' in the config is not an object", self.servers_key)
+                    })?;
+                if servers.get(server_name) == Some(&entry) {
+                    return Ok(PlannedEdit::unchanged(path, existed, before));
+                }
+                servers.insert(server_name.to_string(), entry);
+            }
+            None => {
+                let removed = root
+                    .get_mut(self.servers_key)
+                    .and_then(Value::as_object_mut)
+                    .and_then(|servers| servers.remove(server_name));
+                if removed.is_none() {
+                    return Ok(PlannedEdit::unchanged(path, existed, before));
+                }
+            }
+        }
+
+        let mut after = serde_json::to_string_pretty(&config)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        if !existed || before.ends_with('\n') {
+            after.push('\n');
+        }
+
+        Ok(PlannedEdit {
+            path,
+            existed,
+            before,
+            after,
+        })
+    }
+}
+
+impl PlannedEdit {
+    fn unchanged(path: PathBuf, existed: bool, contents: String) -> Self {
+        PlannedEdit {
+            path,
+            existed,
+            before: contents.clone(),
+            after: contents,
+        }
+    }
+
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+
+    pub fn diff(&self) -> String {
+        let path = self.path.to_string_lossy();
+        let old_name = if self.existed {
+            path.as_ref()
+        } else {
+            "/dev/null"
+        };
+        unified_diff(old_name, &path, &self.before, &self.after)
+    }
+
+    /// Write the new contents, backing up the existing file first.
+    /// Returns the backup's path when there was a file to back up.
+    pub fn apply(&self) -> Result<Option<PathBuf>, String> {
+        if !self.changed() {
+            return Ok(None);
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let backup_path = if self.existed {
+            let stem = format!(
+                "{}.juan-note-backup-{}",
+                file_name,
+                Local::now().format("%Y%m%d%H%M%S")
+            );
+            // Never overwrite an earlier backup taken in the same second
+            let backup_path = (0..)
+                .map(|n| match n {
+                    0 => self.path.with_file_name(&stem),
+                    n => self.path.with_file_name(format!("{}-{}", stem, n)),
+                })
+                .find(|candidate| !candidate.exists())
+                .unwrap_or_default();
+            fs::copy(&self.path, &backup_path)
+                .map_err(|e| format!("Failed to back up config file: {}", e))?;
+            Some(backup_path)
+        } else {
+            None
+        };
+
+        // Write next to the original and rename over it, so a client reading the
+        // file never sees it half-written
+        let temp_path = self
+            .path
+            .with_file_name(format!("{}.juan-note-tmp", file_name));
+        fs::write(&temp_path, &self.after)
+            .map_err(|e| format!("Failed to write config file: {}", e))?;
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            let _ = fs::set_permissions(&temp_path, metadata.permissions());
+        }
+        fs::rename(&temp_path, &self.path).map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            format!("Failed to write config file: {}", e)
+        })?;
+
+        Ok(backup_path)
+    }
+}
+
+// Where desktop apps keep their settings on each platform
+fn app_support_dir(home: &Path) -> PathBuf {
+    #[cfg(target_os = "macos")]
+    {
+        home.join("Library/Application Support")
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join("AppData/Roaming"))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"))
+    }
+}
+
+// Cline is a VS Code extension and keeps its settings in the extension's global storage
+fn cline_dir(home: &Path) -> PathBuf {
+    app_support_dir(home).join("Code/User/globalStorage/saoudrizwan.claude-dev")
+}
+
+const DIFF_CONTEXT: usize = 3;
+// Diffing gives up on finding the smallest diff after this long
+const DIFF_TIMEOUT: Duration = Duration::from_millis(200);
+
+// Empty when nothing changed
+fn unified_diff(old_name: &str, new_name: &str, before: &str, after: &str) -> String {
+    TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(before, after)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT)
+        .header(old_name, new_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets() -> [ServerTarget; 2] {
+        let mut headers = Map::new();
+        headers.insert("Authorization".to_string(), json!("Bearer jn_test"));
+        [
+            ServerTarget::Stdio {
+                command: "/usr/bin/juan-note".to_string(),
+                args: vec!["--mcp-stdio".to_string()],
+            },
+            ServerTarget::Http {
+                url: "http://127.0.0.1:3030/mcp".to_string(),
+                headers,
+            },
+        ]
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn every_provider_adds_and_removes_its_entry_without_touching_the_rest() {
+        for provider in PROVIDERS {
+            for target in targets() {
+                let Ok(entry) = provider.entry(&target) else {
+                    continue;
+                };
+                let home = tempfile::tempdir().unwrap();
+                let path = provider.config_path(home.path());
+                // XDG_CONFIG_HOME can move some configs out of the temporary home
+                if !path.starts_with(home.path()) {
+                    continue;
+                }
+
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                let existing = json!({
+                    "theme": "dark",
+                    provider.servers_key: { "other": { "command": "other-server" } }
+                });
+                fs::write(&path, serde_json::to_string_pretty(&existing).unwrap()).unwrap();
+
+                let edit = provider
+                    .plan_edit(home.path(), "juan-note", Some(entry.clone()))
+                    .unwrap();
+                assert!(edit.changed(), "{}", provider.id);
+                edit.apply().unwrap();
+
+                let config = read_json(&path);
+                let servers = provider.servers(&config).unwrap();
+                assert_eq!(servers["juan-note"], entry, "{}", provider.id);
+                assert_eq!(servers["other"], existing[provider.servers_key]["other"]);
+                assert_eq!(config["theme"], "dark");
+
+                // Adding the same entry again is not a change
+                let again = provider
+                    .plan_edit(home.path(), "juan-note", Some(entry))
+                    .unwrap();
+                assert!(!again.changed(), "{}", provider.id);
+
+                provider
+                    .plan_edit(home.path(), "juan-note", None)
+                    .unwrap()
+                    .apply()
+                    .unwrap();
+                assert_eq!(read_json(&path), existing, "{}", provider.id);
+            }
+        }
+    }
+
+    #[test]
+    fn amp_servers_key_is_a_single_flat_key() {
+        let provider = find("amp").unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let entry = provider.entry(&targets()[0]).unwrap();
+
+        let edit = provider
+            .plan_edit(home.path(), "juan-note", Some(entry))
+            .unwrap();
+
+        let config: Value = serde_json::from_str(&edit.after).unwrap();
+        assert!(config["amp.mcpServers"]["juan-note"].is_object());
+        assert!(config.get("amp").is_none());
+    }
+
+    #[test]
+    fn creates_a_missing_config_without_a_backup() {
+        let provider = find("gemini").unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let entry = provider.entry(&targets()[1]).unwrap();
+
+        let edit = provider
+            .plan_edit(home.path(), "juan-note", Some(entry))
+            .unwrap();
+        assert!(!edit.existed);
+        assert!(edit.diff().starts_with("--- /dev/null\n"));
+
+        assert_eq!(edit.apply().unwrap(), None);
+        let written = fs::read_to_string(provider.config_path(home.path())).unwrap();
+        assert!(written.ends_with("}\n"));
+    }
+
+    #[test]
+    fn backs_up_the_existing_config_and_leaves_no_temp_file() {
+        let provider = find("claude-code").unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let path = provider.config_path(home.path());
+        let before = r#"{"mcpServers":{}}"#;
+        fs::write(&path, before).unwrap();
+
+        let entry = provider.entry(&targets()[0]).unwrap();
+        let edit = provider
+            .plan_edit(home.path(), "juan-note", Some(entry))
+            .unwrap();
+        let backup = edit.apply().unwrap().unwrap();
+
+        assert_eq!(fs::read_to_string(&backup).unwrap(), before);
+        // No trailing newline before, none after
+        assert!(!fs::read_to_string(&path).unwrap().ends_with('\n'));
+
+        let mut names: Vec<String> = fs::read_dir(home.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert_eq!(names[0], ".claude.json");
+        assert!(names[1].starts_with(".claude.json.juan-note-backup-"));
+    }
+
+    #[test]
+    fn refuses_configs_that_are_not_json_objects() {
+        let provider = find("claude-code").unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let path = provider.config_path(home.path());
+
+        fs::write(&path, "[]").unwrap();
+        let error = provider
+            .plan_edit(home.path(), "juan-note", None)
+            .err()
+            .unwrap();
+        assert_eq!(error, "Config file is not a JSON object");
+
+        fs::write(&path, "{ not json").unwrap();
+        let error = provider
+            .plan_edit(home.path(), "juan-note", None)
+            .err()
+            .unwrap();
+        assert!(
+            error.starts_with("Failed to parse config file"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn diffs_added_and_removed_lines_with_context() {
+        let before = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+
+        let added = unified_diff("old", "new", before, "1\n2\n3\n4\n4.5\n5\n6\n7\n8\n9\n");
+        assert_eq!(
+            added,
+            "--- old\n+++ new\n@@ -2,6 +2,7 @@\n 2\n 3\n 4\n+4.5\n 5\n 6\n 7\n"
+        );
+
+        let removed = unified_diff("old", "new", before, "1\n2\n3\n4\n6\n7\n8\n9\n");
+        assert_eq!(
+            removed,
+            "--- old\n+++ new\n@@ -2,7 +2,6 @@\n 2\n 3\n 4\n-5\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn unchanged_files_have_an_empty_diff() {
+        assert_eq!(unified_diff("old", "new", "a\nb\n", "a\nb\n"), "");
+        assert_eq!(unified_diff("old", "new", "", ""), "");
+    }
+}
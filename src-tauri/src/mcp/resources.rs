@@ -0,0 +1,89 @@
+use serde_json::{json, Value};
+
+use super::{RpcError, INTERNAL_ERROR, RESOURCE_NOT_FOUND};
+use crate::commands::*;
+
+const NOTE_URI_PREFIX: &str = "note://";
+const STATES_URI: &str = "state://all";
+const JSON_MIME_TYPE: &str = "application/json";
+
+pub fn list() -> Result<Value, RpcError> {
+    let notes = get_all_notes().map_err(|e| RpcError::new(INTERNAL_ERROR, e))?;
+
+    let mut resources: Vec<Value> = notes
+        .data
+        .iter()
+        .filter_map(|note| {
+            let id = note.id?;
+            Some(json!({
+                "uri": format!("{}{}", NOTE_URI_PREFIX, id),
+                "name": note.title,
+                "description": format!(
+                    "Note in section '{}'{}",
+                    note.section,
+                    if note.done { " (done)" } else { "" }
+                ),
+                "mimeType": JSON_MIME_TYPE
+            }))
+        })
+        .collect();
+
+    resources.push(json!({
+        "uri": STATES_URI,
+        "name": "States",
+        "description": "All states (kanban columns) notes can be in",
+        "mimeType": JSON_MIME_TYPE
+    }));
+
+    Ok(json!({ "resources": resources }))
+}
+
+pub fn templates() -> Value {
+    json!({
+        "resourceTemplates": [{
+            "uriTemplate": "note://{id}",
+            "name": "Note",
+            "description": "A single note by id",
+            "mimeType": JSON_MIME_TYPE
+        }]
+    })
+}
+
+pub fn read(params: Value) -> Result<Value, RpcError> {
+    let uri = params
+        .get("uri")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params("Missing resource uri"))?;
+
+    let contents = if uri == STATES_URI {
+        let states = get_all_states().map_err(|e| RpcError::new(INTERNAL_ERROR, e))?;
+        json!(states.data)
+    } else if let Some(id) = uri.strip_prefix(NOTE_URI_PREFIX) {
+        let id: i64 = id
+            .parse()
+            .map_err(|_| RpcError::invalid_params(format!("Invalid note uri: {}", uri)))?;
+        let note = get_note(id).map_err(|e| RpcError::new(INTERNAL_ERROR, e))?;
+        match note.data {
+            Some(note) => json!(note),
+            None => {
+                return Err(RpcError::new(
+                    RESOURCE_NOT_FOUND,
+                    format!("Resource not found: {}", uri),
+                ))
+            }
+        }
+    } else {
+        return Err(RpcError::invalid_params(format!(
+            "Unsupported resource uri: {}",
+            uri
+        )));
+    };
+
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": JSON_MIME_TYPE,
+            "text": contents.to_string()
+        }]
+    }))
+}
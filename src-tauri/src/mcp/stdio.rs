@@ -0,0 +1,36 @@
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use super::{error_response, handle_payload, McpContext, RpcError, PARSE_ERROR};
+
+/// Serve MCP over stdin/stdout, one JSON-RPC message per line. Only the MCP
+/// client talks on stdout, so anything else must go to stderr.
+pub async fn serve() -> std::io::Result<()> {
+    // The client launched this process as the user, so it gets full access
+    let context = McpContext { can_write: true };
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(payload) => handle_payload(payload, &context),
+            Err(e) => Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+            )),
+        };
+
+        if let Some(response) = response {
+            stdout.write_all(response.to_string().as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+    }
+
+    Ok(())
+}
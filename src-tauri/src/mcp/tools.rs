@@ -0,0 +1,413 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::{McpContext, RpcError};
+use crate::commands::*;
+use crate::settings;
+
+struct Tool {
+    name: &'static str,
+    description: &'static str,
+    // Tools that change data need a token with the write scope
+    writes: bool,
+    input_schema: fn() -> Value,
+}
+
+const TOOLS: &[Tool] = &[
+    // Note management
+    Tool {
+        name: "create_note",
+        description: "Create a new note in Juan Note",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "title": { "type": "string", "description": "Note title" },
+                    "content": { "type": "string", "description": "Note content" },
+                    "priority": { "type": "integer", "minimum": 0, "maximum": 5, "description": "Priority from 0 (none) to 5 (highest)" },
+                    "labels": { "type": "array", "items": { "type": "string" }, "description": "Array of label strings" },
+                    "deadline": { "type": "string", "format": "date-time", "description": "Deadline in ISO 8601 format" },
+                    "reminder_minutes": { "type": "integer", "description": "Minutes before the deadline to remind" },
+                    "done": { "type": "boolean", "description": "Whether the note is done" },
+                    "state_id": { "type": "integer", "description": "State ID for the note" },
+                    "order": { "type": "integer", "description": "Display order within the state" },
+                    "section": { "type": "string", "description": "Section the note belongs to" }
+                }),
+                &["title", "content"],
+            )
+        },
+    },
+    Tool {
+        name: "get_note",
+        description: "Get a specific note by ID",
+        writes: false,
+        input_schema: || {
+            object_schema(
+                json!({ "id": { "type": "integer", "description": "Note ID" } }),
+                &["id"],
+            )
+        },
+    },
+    Tool {
+        name: "get_all_notes",
+        description: "Get all notes from Juan Note",
+        writes: false,
+        input_schema: || object_schema(json!({}), &[]),
+    },
+    Tool {
+        name: "list_notes",
+        description: "Get a page of notes in list order, optionally with only some fields (e.g. without content; use get_note for a note's full content). Pass next_cursor from the result as cursor to get the next page",
+        writes: false,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "limit": { "type": "integer", "minimum": 1, "maximum": 500, "description": "Notes per page (default 100)" },
+                    "cursor": { "type": "string", "description": "next_cursor of the previous page; omit for the first page" },
+                    "fields": { "type": "array", "items": { "type": "string" }, "description": "Note fields to return, e.g. [\"title\", \"state_id\"]; id is always returned" }
+                }),
+                &[],
+            )
+        },
+    },
+    Tool {
+        name: "update_note",
+        description: "Update an existing note; only the given fields change. Pass the note's version as expected_version so the update fails with the current note instead of overwriting someone else's change",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "id": { "type": "integer", "description": "Note ID" },
+                    "title": { "type": "string", "description": "New title" },
+                    "content": { "type": "string", "description": "New content" },
+                    "priority": { "type": "integer", "minimum": 0, "maximum": 5, "description": "New priority level" },
+                    "labels": { "type": "array", "items": { "type": "string" }, "description": "New labels array" },
+                    "deadline": { "type": "string", "format": "date-time", "description": "New deadline in ISO 8601 format" },
+                    "reminder_minutes": { "type": "integer", "description": "New reminder minutes" },
+                    "done": { "type": "boolean", "description": "New done status" },
+                    "state_id": { "type": "integer", "description": "New state ID" },
+                    "order": { "type": "integer", "description": "New order" },
+                    "section": { "type": "string", "description": "New section" },
+                    "expected_version": { "type": "integer", "description": "Version of the note the change is based on; fails with a conflict if the note has changed since" }
+                }),
+                &["id"],
+            )
+        },
+    },
+    Tool {
+        name: "delete_note",
+        description: "Delete a note by ID",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({ "id": { "type": "integer", "description": "Note ID to delete" } }),
+                &["id"],
+            )
+        },
+    },
+    Tool {
+        name: "search_notes",
+        description: "Search notes using full-text search over titles, content, labels, sections and state names. When few notes match, typo-tolerant matches and a suggested query are added",
+        writes: false,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "query": { "type": "string", "description": "Search query" },
+                    "limit": { "type": "integer", "description": "Maximum number of results" },
+                    "offset": { "type": "integer", "description": "Number of results to skip" },
+                    "fuzzy": { "type": "boolean", "description": "Add typo-tolerant matches when few notes match (default false)" }
+                }),
+                &["query"],
+            )
+        },
+    },
+    Tool {
+        name: "semantic_search",
+        description: "Find notes by meaning rather than exact words, ranked by similarity to the query. Needs the local embedding model; embeddings are computed offline",
+        writes: false,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "query": { "type": "string", "description": "What the notes should be about" },
+                    "limit": { "type": "integer", "description": "Maximum number of results (default 10)" },
+                    "min_score": { "type": "number", "description": "Minimum cosine similarity from -1 to 1 (default 0.25)" }
+                }),
+                &["query"],
+            )
+        },
+    },
+    Tool {
+        name: "similar_notes",
+        description: "Find the notes closest in meaning to a note, ranked by similarity",
+        writes: false,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "id": { "type": "integer", "description": "Note ID" },
+                    "limit": { "type": "integer", "description": "Maximum number of results (default 10)" },
+                    "min_score": { "type": "number", "description": "Minimum cosine similarity from -1 to 1 (default 0.25)" }
+                }),
+                &["id"],
+            )
+        },
+    },
+    Tool {
+        name: "update_note_done",
+        description: "Update the done status of a note",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "id": { "type": "integer", "description": "Note ID" },
+                    "done": { "type": "boolean", "description": "New done status" }
+                }),
+                &["id", "done"],
+            )
+        },
+    },
+    // State management
+    Tool {
+        name: "get_all_states",
+        description: "Get all available states",
+        writes: false,
+        input_schema: || object_schema(json!({}), &[]),
+    },
+    Tool {
+        name: "create_state",
+        description: "Create a new state",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "name": { "type": "string", "description": "State name" },
+                    "position": { "type": "integer", "description": "State position/order" },
+                    "color": { "type": "string", "description": "State color (hex code)" }
+                }),
+                &["name", "position"],
+            )
+        },
+    },
+    Tool {
+        name: "update_state",
+        description: "Update an existing state. Pass the state's version as expected_version so the update fails with the current state instead of overwriting someone else's change",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "id": { "type": "integer", "description": "State ID" },
+                    "name": { "type": "string", "description": "New name" },
+                    "position": { "type": "integer", "description": "New position" },
+                    "color": { "type": "string", "description": "New color" },
+                    "expected_version": { "type": "integer", "description": "Version of the state the change is based on; fails with a conflict if the state has changed since" }
+                }),
+                &["id"],
+            )
+        },
+    },
+    Tool {
+        name: "delete_state",
+        description: "Delete a state by ID",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({ "id": { "type": "integer", "description": "State ID to delete" } }),
+                &["id"],
+            )
+        },
+    },
+    // Bulk operations
+    Tool {
+        name: "bulk_delete_notes",
+        description: "Delete multiple notes at once",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({ "note_ids": note_ids_schema("Array of note IDs to delete") }),
+                &["note_ids"],
+            )
+        },
+    },
+    Tool {
+        name: "bulk_update_notes_priority",
+        description: "Update priority for multiple notes",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "note_ids": note_ids_schema("Array of note IDs"),
+                    "priority": { "type": "integer", "minimum": 0, "maximum": 5, "description": "New priority level" }
+                }),
+                &["note_ids", "priority"],
+            )
+        },
+    },
+    Tool {
+        name: "bulk_update_notes_done",
+        description: "Update done status for multiple notes",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "note_ids": note_ids_schema("Array of note IDs"),
+                    "done": { "type": "boolean", "description": "New done status" }
+                }),
+                &["note_ids", "done"],
+            )
+        },
+    },
+    Tool {
+        name: "bulk_update_notes_state",
+        description: "Update state for multiple notes",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "note_ids": note_ids_schema("Array of note IDs"),
+                    "state_id": { "type": "integer", "description": "New state ID" }
+                }),
+                &["note_ids", "state_id"],
+            )
+        },
+    },
+    Tool {
+        name: "bulk_update_notes_order",
+        description: "Update display order for multiple notes",
+        writes: true,
+        input_schema: || {
+            object_schema(
+                json!({
+                    "note_ids": note_ids_schema("Array of note IDs"),
+                    "orders": { "type": "array", "items": { "type": "integer" }, "description": "Array of new order values (same length as note_ids)" }
+                }),
+                &["note_ids", "orders"],
+            )
+        },
+    },
+];
+
+pub fn list() -> Value {
+    let tools: Vec<Value> = TOOLS
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": (tool.input_schema)(),
+                "annotations": { "readOnlyHint": !tool.writes }
+            })
+        })
+        .collect();
+
+    json!({ "tools": tools })
+}
+
+pub fn call(params: Value, context: &McpContext) -> Result<Value, RpcError> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params("Missing tool name"))?;
+
+    let tool = TOOLS
+        .iter()
+        .find(|tool| tool.name == name)
+        .ok_or_else(|| RpcError::invalid_params(format!("Unknown tool: {}", name)))?;
+
+    if tool.writes && !context.can_write {
+        return Ok(tool_error(format!(
+            "Tool '{}' changes data and needs an API token with the write scope",
+            name
+        )));
+    }
+
+    let arguments = match params.get("arguments") {
+        Some(Value::Null) | None => json!({}),
+        Some(arguments) => arguments.clone(),
+    };
+
+    // Same cap as the REST bulk endpoints
+    if let Some(note_ids) = arguments.get("note_ids").and_then(Value::as_array) {
+        let max_bulk_ids = settings::current().limits.max_bulk_ids;
+        if note_ids.len() > max_bulk_ids {
+            return Ok(tool_error(format!(
+                "Bulk operations are limited to {} notes, got {}",
+                max_bulk_ids,
+                note_ids.len()
+            )));
+        }
+    }
+
+    // Bad arguments and failed commands are reported in the tool result so the model can react
+    Ok(match run(name, arguments) {
+        Ok(result) => tool_result(result),
+        Err(e) => tool_error(e),
+    })
+}
+
+fn run(name: &str, arguments: Value) -> Result<Value, String> {
+    match name {
+        "create_note" => to_value(create_note(parse(arguments)?)?),
+        "get_note" => to_value(get_note(parse::<IdArgument>(arguments)?.id)?),
+        "get_all_notes" => to_value(get_all_notes()?),
+        "list_notes" => to_value(list_notes(parse(arguments)?)?),
+        "update_note" => to_value(update_note(parse(arguments)?)?),
+        "delete_note" => to_value(delete_note(parse(arguments)?)?),
+        "search_notes" => to_value(search_notes(parse(arguments)?)?),
+        // MCP messages are handled synchronously, so the tools call the blocking versions
+        "semantic_search" => to_value(search_by_meaning(parse(arguments)?)?),
+        "similar_notes" => to_value(find_similar_notes(parse(arguments)?)?),
+        "update_note_done" => to_value(update_note_done(parse(arguments)?)?),
+        "get_all_states" => to_value(get_all_states()?),
+        "create_state" => to_value(create_state(parse(arguments)?)?),
+        "update_state" => to_value(update_state(parse(arguments)?)?),
+        "delete_state" => to_value(delete_state(parse::<IdArgument>(arguments)?.id)?),
+        "bulk_delete_notes" => to_value(bulk_delete_notes(parse(arguments)?)?),
+        "bulk_update_notes_priority" => to_value(bulk_update_notes_priority(parse(arguments)?)?),
+        "bulk_update_notes_done" => to_value(bulk_update_notes_done(parse(arguments)?)?),
+        "bulk_update_notes_state" => to_value(bulk_update_notes_state(parse(arguments)?)?),
+        "bulk_update_notes_order" => to_value(bulk_update_notes_order(parse(arguments)?)?),
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct IdArgument {
+    id: i64,
+}
+
+fn parse<T: DeserializeOwned>(arguments: Value) -> Result<T, String> {
+    serde_json::from_value(arguments).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+fn to_value<T: Serialize>(response: T) -> Result<Value, String> {
+    serde_json::to_value(response).map_err(|e| format!("Failed to serialize result: {}", e))
+}
+
+// Command responses carry their own `success`/`error`, which decide `isError`
+fn tool_result(result: Value) -> Value {
+    let is_error = result.get("success").and_then(Value::as_bool) == Some(false);
+
+    json!({
+        "content": [{ "type": "text", "text": result.to_string() }],
+        "structuredContent": result,
+        "isError": is_error
+    })
+}
+
+fn tool_error(message: String) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": message }],
+        "isError": true
+    })
+}
+
+fn object_schema(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
+}
+
+fn note_ids_schema(description: &str) -> Value {
+    json!({ "type": "array", "items": { "type": "integer" }, "description": description })
+}
@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct State {
     pub id: Option<i64>,
     pub name: String,
@@ -9,38 +10,46 @@ pub struct State {
     pub color: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Sequence number of the state's last change
+    #[serde(default)]
+    pub change_seq: i64,
+    /// Goes up by one with every update
+    #[serde(default)]
+    pub version: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateStateRequest {
     pub name: String,
     pub position: i32,
     pub color: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateStateRequest {
     pub id: i64,
     pub name: Option<String>,
     pub position: Option<i32>,
     pub color: Option<String>,
+    /// Only update if the state is still at this version
+    pub expected_version: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct StateResponse {
     pub success: bool,
     pub data: Option<State>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct StatesListResponse {
     pub success: bool,
     pub data: Vec<State>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Note {
     pub id: Option<i64>,
     pub title: String,
@@ -55,9 +64,15 @@ pub struct Note {
     pub state_id: Option<i64>,
     pub order: i32,
     pub section: String,
+    /// Sequence number of the note's last change
+    #[serde(default)]
+    pub change_seq: i64,
+    /// Goes up by one with every update
+    #[serde(default)]
+    pub version: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateNoteRequest {
     pub title: String,
     pub content: String,
@@ -71,7 +86,7 @@ pub struct CreateNoteRequest {
     pub section: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateNoteRequest {
     pub id: i64,
     pub title: Option<String>,
@@ -84,27 +99,170 @@ pub struct UpdateNoteRequest {
     pub state_id: Option<i64>,
     pub order: Option<i32>,
     pub section: Option<String>,
+    /// Only update if the note is still at this version
+    pub expected_version: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct NoteResponse {
     pub success: bool,
     pub data: Option<Note>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct NotesListResponse {
     pub success: bool,
     pub data: Vec<Note>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ListNotesRequest {
+    /// Notes per page, 1 to 500 (default 100)
+    pub limit: Option<i32>,
+    /// `next_cursor` of the previous page; omitted for the first page
+    pub cursor: Option<String>,
+    /// Note fields to return, e.g. `["title", "state_id"]`; all fields when
+    /// omitted. `id` is always returned.
+    pub fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListNotesQuery {
+    /// Notes per page, 1 to 500 (default 100)
+    pub limit: Option<i32>,
+    /// `next_cursor` of the previous page; omitted for the first page
+    pub cursor: Option<String>,
+    /// Comma-separated note fields to return, e.g. `title,state_id`; all
+    /// fields when omitted. `id` is always returned.
+    pub fields: Option<String>,
+}
+
+impl From<ListNotesQuery> for ListNotesRequest {
+    fn from(query: ListNotesQuery) -> Self {
+        Self {
+            limit: query.limit,
+            cursor: query.cursor,
+            fields: query.fields.map(|fields| {
+                fields
+                    .split(',')
+                    .map(|field| field.trim().to_string())
+                    .filter(|field| !field.is_empty())
+                    .collect()
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NotesPageResponse {
+    pub success: bool,
+    /// Notes in list order, with the requested fields
+    #[schema(value_type = Vec<Object>)]
+    pub data: Vec<serde_json::Value>,
+    /// Notes in the whole list, not just this page
+    pub total_count: i64,
+    /// Pass as `cursor` to get the next page; `null` on the last page
+    pub next_cursor: Option<String>,
+    /// Changes whenever anything on the page does
+    pub etag: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SearchRequest {
     pub query: String,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// Add typo-tolerant matches when full-text search finds few notes (default false)
+    pub fuzzy: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchNotesResponse {
+    pub success: bool,
+    pub data: Vec<Note>,
+    /// "Did you mean" query, when words of the query aren't in any note
+    pub suggestion: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchIndexStatus {
+    pub healthy: bool,
+    // FTS5's description of the mismatch when the index isn't healthy
+    pub problem: Option<String>,
+    pub rebuilt: bool,
+    pub note_count: i64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchIndexResponse {
+    pub success: bool,
+    pub data: Option<SearchIndexStatus>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticSearchRequest {
+    pub query: String,
+    pub limit: Option<i32>,
+    // Cosine similarity a note needs to be returned, from -1 to 1 (default 0.25)
+    pub min_score: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarNotesRequest {
+    pub id: i64,
+    pub limit: Option<i32>,
+    pub min_score: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoredNote {
+    pub note: Note,
+    pub score: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticSearchResponse {
+    pub success: bool,
+    pub data: Vec<ScoredNote>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingStatus {
+    // Whether this build includes the embedding model
+    pub available: bool,
+    pub model: String,
+    pub model_dir: String,
+    pub missing_files: Vec<String>,
+    pub embedded_notes: i64,
+    pub total_notes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingStatusResponse {
+    pub success: bool,
+    pub data: Option<EmbeddingStatus>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CalendarQuery {
+    /// Only include notes in this section
+    pub section: Option<String>,
+    /// Only include notes with this label
+    pub label: Option<String>,
+    /// Only include notes in this state, by ID or name
+    pub state: Option<String>,
+    /// `todo` (default) for VTODO entries or `event` for VEVENT entries
+    pub component: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -119,30 +277,30 @@ pub struct UpdateNoteDoneRequest {
 }
 
 // Bulk Operations Types
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct BulkDeleteRequest {
     pub note_ids: Vec<i64>,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct BulkUpdatePriorityRequest {
     pub note_ids: Vec<i64>,
     pub priority: i32,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct BulkUpdateStateRequest {
     pub note_ids: Vec<i64>,
     pub state_id: i64,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct BulkUpdateDoneRequest {
     pub note_ids: Vec<i64>,
     pub done: bool,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct BulkUpdateOrderRequest {
     pub note_ids: Vec<i64>,
     pub orders: Vec<i32>,
@@ -155,7 +313,247 @@ pub struct ReorderNoteRequest {
     pub state_id: Option<i64>,
 }
 
-#[derive(serde::Serialize)]
+// Change events published on every mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeEntity {
+    Note,
+    State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOperation {
+    Created,
+    Updated,
+    Deleted,
+    // A note moved to a different kanban state; published in addition to `Updated`
+    StateChanged,
+}
+
+impl ChangeOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeOperation::Created => "created",
+            ChangeOperation::Updated => "updated",
+            ChangeOperation::Deleted => "deleted",
+            ChangeOperation::StateChanged => "state_changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChangeEvent {
+    pub entity: ChangeEntity,
+    pub id: i64,
+    pub operation: ChangeOperation,
+    pub data: Option<serde_json::Value>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ChangeEvent {
+    /// Dotted event name used by webhook filters, e.g. `note.state_changed`.
+    pub fn name(&self) -> String {
+        let entity = match self.entity {
+            ChangeEntity::Note => "note",
+            ChangeEntity::State => "state",
+        };
+        format!("{}.{}", entity, self.operation.as_str())
+    }
+}
+
+// Incremental Sync Types
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Tombstone {
+    pub entity: ChangeEntity,
+    pub id: i64,
+    pub change_seq: i64,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Changes {
+    /// Notes created or updated since `since_seq`, as they are now
+    pub notes: Vec<Note>,
+    /// States created or updated since `since_seq`, as they are now
+    pub states: Vec<State>,
+    /// Notes and states deleted since `since_seq`
+    pub deleted: Vec<Tombstone>,
+    /// Pass as `since_seq` to get the changes after these
+    pub latest_seq: i64,
+    /// More changes are waiting; ask again from `latest_seq` right away
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangesResponse {
+    pub success: bool,
+    pub data: Option<Changes>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChangesQuery {
+    /// `latest_seq` of the previous sync; 0 (default) for everything
+    pub since: Option<i64>,
+}
+
+// Webhook Types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Option<i64>,
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: String,
+    pub enabled: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Webhook {
+    /// Whether this webhook subscribed to the event, either by exact name,
+    /// by `entity.*` or with the `*` wildcard.
+    pub fn accepts(&self, event_name: &str) -> bool {
+        self.events.iter().any(|filter| {
+            filter == "*"
+                || filter == event_name
+                || filter
+                    .strip_suffix(".*")
+                    .is_some_and(|entity| event_name.split('.').next() == Some(entity))
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub id: i64,
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookResponse {
+    pub success: bool,
+    pub data: Option<Webhook>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhooksListResponse {
+    pub success: bool,
+    pub data: Vec<Webhook>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Option<i64>,
+    pub webhook_id: i64,
+    pub delivery_id: String,
+    pub event: String,
+    pub payload: String,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDeliveriesResponse {
+    pub success: bool,
+    pub data: Vec<WebhookDelivery>,
+    pub error: Option<String>,
+}
+
+// API Token Types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl TokenScope {
+    /// Scopes are hierarchical: admin implies write, write implies read.
+    pub fn grants(&self, required: TokenScope) -> bool {
+        self.level() >= required.level()
+    }
+
+    fn level(&self) -> u8 {
+        match self {
+            TokenScope::Read => 0,
+            TokenScope::Write => 1,
+            TokenScope::Admin => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiToken {
+    pub id: Option<i64>,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<TokenScope>,
+    pub revoked: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    pub fn has_scope(&self, required: TokenScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiTokenResponse {
+    pub success: bool,
+    pub data: Option<ApiToken>,
+    // The plain token, only returned when it is created
+    pub token: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiTokensListResponse {
+    pub success: bool,
+    pub data: Vec<ApiToken>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsQuery {
+    /// Only stream changes to notes or to states
+    pub entity: Option<ChangeEntity>,
+}
+
+impl EventsQuery {
+    pub fn matches(&self, event: &ChangeEvent) -> bool {
+        self.entity.is_none_or(|entity| entity == event.entity)
+    }
+}
+
+#[derive(serde::Serialize, ToSchema)]
 pub struct BulkOperationResponse {
     pub success: bool,
     pub successful_count: usize,
@@ -180,6 +578,8 @@ impl Note {
             state_id: None,
             order: 0,
             section: "unset".to_string(),
+            change_seq: 0,
+            version: 1,
         }
     }
 
@@ -225,9 +625,13 @@ impl Default for Note {
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct McpServerConfig {
     pub name: String,
+    #[serde(default)]
     pub command: String,
     pub args: Option<Vec<String>>,
     pub env: Option<serde_json::Value>,
+    // Set for servers reached over HTTP instead of launched as a process
+    pub url: Option<String>,
+    pub headers: Option<serde_json::Value>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -236,16 +640,86 @@ pub struct McpConfigResult {
     pub config_path: String,
     pub mcp_servers: Vec<McpServerConfig>,
     pub error: Option<String>,
+    // Why the file was read: the client it belongs to, a custom path or its name
+    pub reason: Option<String>,
+    // Time spent reading and parsing the file
+    pub duration_ms: Option<u64>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct McpScanResponse {
     pub success: bool,
     pub data: Option<Vec<McpConfigResult>>,
+    // Limits the scan ran into, such as the timeout
+    pub errors: Option<Vec<String>>,
     pub error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpProviderInfo {
+    pub id: String,
+    pub name: String,
+    pub config_path: String,
+    pub installed: bool,
+    // Whether the config already has a Juan Note entry
+    pub configured: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+pub struct McpProvidersResponse {
+    pub success: bool,
+    pub data: Vec<McpProviderInfo>,
+    pub error: Option<String>,
+}
+
+/// A Juan Note entry added to or removed from one client's config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpConfigChange {
+    pub provider: String,
+    pub config_path: String,
+    pub changed: bool,
+    // Unified diff of the config file, empty when nothing changes
+    pub diff: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpIntegrationStatus {
+    Healthy,
+    // The entry points at another executable or port, or has a bad token
+    Stale,
+    // The entry looks right but the server didn't answer the handshake
+    Unreachable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpIntegrationCheck {
+    pub provider: String,
+    pub config_path: String,
+    // "stdio" or "http"
+    pub transport: String,
+    pub status: McpIntegrationStatus,
+    pub problems: Vec<String>,
+    pub repaired: bool,
+    pub backup_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct McpIntegrationsResponse {
+    pub success: bool,
+    pub data: Vec<McpIntegrationCheck>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct McpConfigChangesResponse {
+    pub success: bool,
+    pub data: Vec<McpConfigChange>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpFunctionParameter {
     pub name: String,
     pub r#type: String,
@@ -253,18 +727,209 @@ pub struct McpFunctionParameter {
     pub required: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpFunction {
     pub name: String,
     pub description: Option<String>,
     pub parameters: Vec<McpFunctionParameter>,
     pub server_name: String,
     pub server_provider: String,
+    // The tool's full JSON Schema, for clients that need more than the flat parameter list
+    pub input_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct McpFunctionQueryResponse {
     pub success: bool,
     pub data: Option<Vec<McpFunction>>,
+    // Servers that couldn't be reached, one message per server
+    pub errors: Option<Vec<String>>,
+    pub error: Option<String>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    // Origins allowed to call the HTTP API from a browser context
+    pub allowed_origins: Vec<String>,
+    pub limits: ApiLimits,
+    // Port the HTTP server binds first; updated when it has to fall back to another one
+    pub server_port: u16,
+    pub mcp_scan: McpScanSettings,
+    pub search_weights: SearchWeights,
+    // Directory holding the sentence-embedding model for semantic search;
+    // `None` uses juan-note-models/all-MiniLM-L6-v2 next to the database
+    pub embedding_model_dir: Option<String>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![
+                // Tauri webview on macOS/Linux, then Windows/Android
+                "tauri://localhost".to_string(),
+                "http://tauri.localhost".to_string(),
+                "https://tauri.localhost".to_string(),
+                // Vite dev server
+                "http://localhost:1420".to_string(),
+                "http://127.0.0.1:1420".to_string(),
+            ],
+            limits: ApiLimits::default(),
+            server_port: 3001,
+            mcp_scan: McpScanSettings::default(),
+            search_weights: SearchWeights::default(),
+            embedding_model_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiLimits {
+    pub rate_limiting_enabled: bool,
+    // Token bucket refill rates; each bucket holds up to `burst` requests
+    pub requests_per_minute_per_token: u32,
+    pub requests_per_minute_per_client: u32,
+    pub burst: u32,
+    // Applied when the HTTP server starts
+    pub max_body_bytes: usize,
+    pub max_bulk_ids: usize,
+}
+
+impl Default for ApiLimits {
+    fn default() -> Self {
+        Self {
+            rate_limiting_enabled: true,
+            requests_per_minute_per_token: 300,
+            requests_per_minute_per_client: 600,
+            burst: 60,
+            max_body_bytes: 1024 * 1024,
+            max_bulk_ids: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpScanSettings {
+    // Search the config directories for other MCP configs besides the known
+    // clients' files and the custom paths
+    pub discover: bool,
+    // Extra config files to read; `~/` is expanded to the home directory
+    pub custom_config_paths: Vec<String>,
+    pub max_depth: usize,
+    // Only for discovered files; the known clients' configs and the custom
+    // paths are read whatever their size
+    pub max_file_bytes: u64,
+    // For the whole scan, including discovery
+    pub timeout_ms: u64,
+}
+
+impl Default for McpScanSettings {
+    fn default() -> Self {
+        Self {
+            discover: false,
+            custom_config_paths: Vec::new(),
+            max_depth: 3,
+            max_file_bytes: 1024 * 1024,
+            timeout_ms: 5000,
+        }
+    }
+}
+
+// bm25 weight of each column of the search index; a match in a column with a
+// higher weight ranks the note higher
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchWeights {
+    pub title: f64,
+    pub labels: f64,
+    pub content: f64,
+    pub section: f64,
+    pub state: f64,
+}
+
+impl Default for SearchWeights {
+    fn default() -> Self {
+        Self {
+            title: 10.0,
+            labels: 5.0,
+            content: 1.0,
+            section: 2.0,
+            state: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateSettingsRequest {
+    pub allowed_origins: Option<Vec<String>>,
+    pub limits: Option<ApiLimits>,
+    pub server_port: Option<u16>,
+    pub mcp_scan: Option<McpScanSettings>,
+    pub search_weights: Option<SearchWeights>,
+    // An empty string goes back to the default directory
+    pub embedding_model_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsResponse {
+    pub success: bool,
+    pub data: Option<AppSettings>,
     pub error: Option<String>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpServerStatus {
+    pub server_running: bool,
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub configured_port: u16,
+    pub started_at: Option<DateTime<Utc>>,
+    pub uptime_seconds: Option<i64>,
+    // Why the server last failed to start or stopped unexpectedly
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallMcpToolRequest {
+    pub server_name: String,
+    // Disambiguates servers with the same name configured in several clients
+    pub provider: Option<String>,
+    pub tool_name: String,
+    pub arguments: Option<serde_json::Value>,
+    pub save_as_note: Option<bool>,
+    pub note_title: Option<String>,
+    pub append_to_note_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRun {
+    pub id: Option<i64>,
+    pub server_name: String,
+    pub server_provider: Option<String>,
+    pub tool_name: String,
+    pub arguments: serde_json::Value,
+    // The raw `tools/call` result
+    pub result: Option<serde_json::Value>,
+    // Text content of the result, as written into notes
+    pub output: Option<String>,
+    pub is_error: bool,
+    pub error: Option<String>,
+    pub note_id: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolRunResponse {
+    pub success: bool,
+    pub data: Option<ToolRun>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolRunsListResponse {
+    pub success: bool,
+    pub data: Vec<ToolRun>,
+    pub error: Option<String>,
+}
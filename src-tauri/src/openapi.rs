@@ -0,0 +1,261 @@
+// OpenAPI 3 document for the HTTP API, built from the handlers' `#[utoipa::path]`
+// attributes and the request/response types in models.rs, and served at
+// /openapi.json.
+
+use axum::response::Json as JsonResponse;
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ResponseBuilder, ServerBuilder};
+use utoipa::{Modify, OpenApi};
+
+use crate::auth::PUBLIC_PATHS;
+use crate::http_server::{self, http_server_status};
+use crate::mcp;
+use crate::settings;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Juan Note API",
+        description = "REST API for the Juan Note app. Every endpoint except /, /health and \
+            /openapi.json needs an API token, sent as `Authorization: Bearer <token>` or, for GET \
+            requests from SSE, WebSocket and calendar clients, as `?access_token=<token>`. The \
+            read scope covers GET endpoints, POST /notes/search and POST /mcp; write covers \
+            everything that changes notes and states; admin covers /tokens.\n\n\
+            Only requests addressed to localhost, 127.0.0.1 or [::1] are served, and browser \
+            requests must come from an origin allowed in the app settings. Requests are rate \
+            limited per token and per client address (429 with Retry-After), and bodies or bulk \
+            requests over the configured limits get 413."
+    ),
+    paths(
+        http_server::api_documentation,
+        http_server::health_check,
+        openapi_json,
+        http_server::get_all_notes_handler,
+        http_server::list_notes_handler,
+        http_server::create_note_handler,
+        http_server::search_notes_handler,
+        http_server::get_note_handler,
+        http_server::update_note_handler,
+        http_server::delete_note_handler,
+        http_server::update_note_done_handler,
+        http_server::calendar_feed_handler,
+        http_server::events_sse_handler,
+        http_server::events_ws_handler,
+        http_server::get_all_states_handler,
+        http_server::create_state_handler,
+        http_server::update_state_handler,
+        http_server::delete_state_handler,
+        http_server::bulk_delete_notes_handler,
+        http_server::bulk_update_priority_handler,
+        http_server::bulk_update_done_handler,
+        http_server::bulk_update_state_handler,
+        http_server::bulk_update_order_handler,
+        http_server::get_changes_handler,
+        mcp::http::mcp_handler,
+        http_server::get_all_api_tokens_handler,
+        http_server::create_api_token_handler,
+        http_server::revoke_api_token_handler,
+    ),
+    modifiers(&Security),
+    tags(
+        (name = "meta", description = "Documentation and health"),
+        (name = "notes", description = "Notes"),
+        (name = "states", description = "Kanban states"),
+        (name = "bulk", description = "Operations on many notes at once"),
+        (name = "sync", description = "Changes since a sequence number, for keeping caches up to date"),
+        (name = "calendar", description = "iCalendar feed of note deadlines"),
+        (name = "events", description = "Live change events"),
+        (name = "mcp", description = "Model Context Protocol endpoint"),
+        (name = "tokens", description = "API tokens (admin scope)"),
+    )
+)]
+struct ApiDoc;
+
+// Bearer auth and the 401/403 responses on every operation except the public
+// ones, plus the 429 that per-client rate limiting can send anywhere
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("API token created in the app"))
+                    .build(),
+            ),
+        );
+
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            let public = PUBLIC_PATHS.contains(&path.as_str());
+            for operation in operations_mut(item) {
+                let responses = &mut operation.responses.responses;
+                if !public {
+                    operation.security =
+                        Some(vec![utoipa::openapi::security::SecurityRequirement::new(
+                            "bearer",
+                            Vec::<String>::new(),
+                        )]);
+                    responses
+                        .entry("401".to_string())
+                        .or_insert_with(|| response("Missing, invalid or revoked token"));
+                    responses
+                        .entry("403".to_string())
+                        .or_insert_with(|| response("Token lacks the required scope"));
+                }
+                responses.entry("429".to_string()).or_insert_with(|| {
+                    response("Rate limit exceeded; Retry-After says how many seconds to wait")
+                });
+            }
+        }
+    }
+}
+
+fn operations_mut(item: &mut utoipa::openapi::PathItem) -> impl Iterator<Item = &mut Operation> {
+    [
+        item.get.as_mut(),
+        item.put.as_mut(),
+        item.post.as_mut(),
+        item.delete.as_mut(),
+        item.patch.as_mut(),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+fn response(description: &str) -> utoipa::openapi::RefOr<utoipa::openapi::Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .build()
+        .into()
+}
+
+/// The OpenAPI document, with the running server as its server URL.
+pub fn openapi_document() -> utoipa::openapi::OpenApi {
+    let port = http_server_status()
+        .port
+        .unwrap_or_else(|| settings::current().server_port);
+
+    let mut document = ApiDoc::openapi();
+    document.servers = Some(vec![ServerBuilder::new()
+        .url(format!("http://localhost:{}", port))
+        .build()]);
+    document
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "meta",
+    responses((status = 200, description = "This OpenAPI document", body = Object))
+)]
+pub(crate) async fn openapi_json() -> JsonResponse<utoipa::openapi::OpenApi> {
+    JsonResponse(openapi_document())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Method;
+    use serde_json::Value;
+
+    fn document() -> Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    // Routes that have no matching operation in the OpenAPI document, as
+    // `METHOD /path`. Axum's `/notes/:id` matches the document's `/notes/{id}`.
+    fn undocumented_routes<'a>(
+        routes: impl IntoIterator<Item = (&'a Method, &'a str)>,
+    ) -> Vec<String> {
+        let document = document();
+
+        routes
+            .into_iter()
+            .filter(|(method, path)| {
+                let openapi_path = path
+                    .split('/')
+                    .map(|segment| match segment.strip_prefix(':') {
+                        Some(param) => format!("{{{}}}", param),
+                        None => segment.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                document["paths"][openapi_path.as_str()][method.as_str().to_lowercase()].is_null()
+            })
+            .map(|(method, path)| format!("{} {}", method, path))
+            .collect()
+    }
+
+    #[test]
+    fn every_api_route_is_in_the_document() {
+        let routes = http_server::api_routes();
+        let undocumented =
+            undocumented_routes(routes.iter().map(|(method, path, _)| (method, *path)));
+
+        assert!(
+            undocumented.is_empty(),
+            "Routes missing from the OpenAPI document: {}",
+            undocumented.join(", ")
+        );
+    }
+
+    #[test]
+    fn every_documented_operation_is_routed() {
+        let routes: Vec<String> = http_server::api_routes()
+            .iter()
+            .map(|(method, path, _)| {
+                let path = path.replace(":id", "{id}");
+                format!("{} {}", method.as_str().to_lowercase(), path)
+            })
+            .collect();
+
+        let document = document();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                let operation = format!("{} {}", method, path);
+                assert!(routes.contains(&operation), "{} is not routed", operation);
+            }
+        }
+    }
+
+    #[test]
+    fn undocumented_routes_matches_path_parameters_and_methods() {
+        let routes = [
+            (&Method::GET, "/notes/:id"),
+            (&Method::GET, "/notes/:id/history"),
+            (&Method::DELETE, "/health"),
+        ];
+
+        assert_eq!(
+            undocumented_routes(routes),
+            ["GET /notes/:id/history", "DELETE /health"]
+        );
+    }
+
+    #[test]
+    fn only_public_paths_are_documented_without_a_token() {
+        let document = document();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            let public = PUBLIC_PATHS.contains(&path.as_str());
+            for (method, operation) in item.as_object().unwrap() {
+                assert_eq!(
+                    operation["security"].is_null(),
+                    public,
+                    "{} {}",
+                    method,
+                    path
+                );
+                assert!(
+                    !operation["responses"]["429"].is_null(),
+                    "{} {}",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+}
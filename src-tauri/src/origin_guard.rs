@@ -0,0 +1,179 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::net::IpAddr;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::auth::error_response;
+use crate::settings;
+
+/// Reject requests addressed to anything but a loopback host, which is what a
+/// DNS rebinding attack looks like, and requests made by pages on origins that
+/// aren't in the allowlist. Requests without an `Origin` header (curl, MCP
+/// clients, native apps) are not browser-initiated and pass the origin check.
+pub async fn validate_host_and_origin(request: Request, next: Next) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| request.uri().authority().map(|a| a.to_string()));
+
+    match host {
+        Some(host) if is_loopback_host(&host) => {}
+        Some(host) => {
+            return error_response(
+                StatusCode::FORBIDDEN,
+                &format!("Host '{}' is not allowed", host),
+            )
+        }
+        None => return error_response(StatusCode::BAD_REQUEST, "Missing Host header"),
+    }
+
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        if !is_allowed_origin(origin) {
+            return error_response(
+                StatusCode::FORBIDDEN,
+                &format!(
+                    "Origin '{}' is not allowed",
+                    origin.to_str().unwrap_or("<invalid>")
+                ),
+            );
+        }
+    }
+
+    next.run(request).await
+}
+
+/// CORS for the allowlisted origins. The allowlist is read on every request
+/// so changes made in the settings apply without restarting the server.
+pub fn cors_layer() -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(|origin, _| {
+            is_allowed_origin(origin)
+        }))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_NONE_MATCH,
+            header::IF_MATCH,
+        ])
+        .expose_headers([header::ETAG])
+}
+
+pub fn is_allowed_origin(origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+
+    settings::current()
+        .allowed_origins
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+}
+
+/// Whether a `Host` header value (`name[:port]`) names this machine's loopback interface.
+pub fn is_loopback_host(host: &str) -> bool {
+    let name = if let Some(rest) = host.strip_prefix('[') {
+        // IPv6 literal, e.g. `[::1]:3001`
+        match rest.split_once(']') {
+            Some((address, "")) => address,
+            Some((address, port)) if port.strip_prefix(':').is_some_and(is_port) => address,
+            _ => return false,
+        }
+    } else {
+        match host.split_once(':') {
+            Some((name, port)) if is_port(port) => name,
+            Some(_) => return false,
+            None => host,
+        }
+    };
+
+    name.eq_ignore_ascii_case("localhost")
+        || name
+            .parse::<IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+}
+
+fn is_port(port: &str) -> bool {
+    port.parse::<u16>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::Service;
+
+    async fn status_for(host: Option<&str>, origin: Option<&str>) -> StatusCode {
+        let mut app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(validate_host_and_origin));
+
+        let mut request = Request::builder().uri("/");
+        if let Some(host) = host {
+            request = request.header(header::HOST, host);
+        }
+        if let Some(origin) = origin {
+            request = request.header(header::ORIGIN, origin);
+        }
+        // The router is always ready, so it can be called without polling first
+        let response = app
+            .call(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.status()
+    }
+
+    #[tokio::test]
+    async fn accepts_loopback_hosts() {
+        assert_eq!(
+            status_for(Some("localhost:3001"), None).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status_for(Some("127.0.0.1:3001"), None).await,
+            StatusCode::OK
+        );
+        assert_eq!(status_for(Some("[::1]:3001"), None).await, StatusCode::OK);
+        assert_eq!(status_for(Some("[::1]"), None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rejects_other_hosts() {
+        assert_eq!(
+            status_for(Some("evil.com:3001"), None).await,
+            StatusCode::FORBIDDEN
+        );
+        // A rebinding domain that merely starts with a loopback address
+        assert_eq!(
+            status_for(Some("127.0.0.1.evil.com"), None).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status_for(None, None).await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn checks_the_origin_against_the_allowlist() {
+        assert_eq!(
+            status_for(Some("localhost:3001"), Some("tauri://localhost")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status_for(Some("localhost:3001"), Some("https://evil.com")).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::auth::error_response;
+use crate::models::{ApiLimits, ApiToken};
+use crate::settings;
+
+// Buckets that have been idle this long are full again and can be dropped
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(600);
+const PRUNE_THRESHOLD: usize = 1024;
+// Hard cap on tracked buckets; the least recently used ones are evicted past it
+const MAX_BUCKETS: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Token(i64),
+    Client(IpAddr),
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+static BUCKETS: OnceLock<Mutex<HashMap<BucketKey, Bucket>>> = OnceLock::new();
+
+fn buckets() -> &'static Mutex<HashMap<BucketKey, Bucket>> {
+    BUCKETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Per-client limit, keyed on the peer address. Runs before authentication so
+/// requests that never get past it are throttled too; nothing the client sends
+/// is trusted yet, so local clients all share the loopback bucket and are told
+/// apart by `limit_by_token` once authenticated.
+pub async fn limit_by_client(request: Request, next: Next) -> Response {
+    let limits = settings::current().limits;
+    if !limits.rate_limiting_enabled {
+        return next.run(request).await;
+    }
+
+    let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() else {
+        return next.run(request).await;
+    };
+
+    let key = BucketKey::Client(addr.ip());
+    match take(key, limits.requests_per_minute_per_client, &limits) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+/// Per-token limit. Must run after `auth::require_api_token`, which puts the
+/// authenticated token in the request extensions.
+pub async fn limit_by_token(request: Request, next: Next) -> Response {
+    let limits = settings::current().limits;
+    if !limits.rate_limiting_enabled {
+        return next.run(request).await;
+    }
+
+    let Some(token_id) = request
+        .extensions()
+        .get::<ApiToken>()
+        .and_then(|token| token.id)
+    else {
+        return next.run(request).await;
+    };
+
+    match take(
+        BucketKey::Token(token_id),
+        limits.requests_per_minute_per_token,
+        &limits,
+    ) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+// Take one request from the bucket, or return how long until one is available
+fn take(key: BucketKey, requests_per_minute: u32, limits: &ApiLimits) -> Result<(), Duration> {
+    let capacity = limits.burst.max(1) as f64;
+    let refill_per_sec = requests_per_minute.max(1) as f64 / 60.0;
+    let now = Instant::now();
+
+    let mut buckets = buckets().lock().unwrap();
+
+    if !buckets.contains_key(&key) {
+        make_room(&mut buckets, now);
+    }
+
+    let bucket = buckets.entry(key).or_insert(Bucket {
+        tokens: capacity,
+        last_refill: now,
+    });
+
+    let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+    bucket.last_refill = now;
+
+    if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        Ok(())
+    } else {
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / refill_per_sec,
+        ))
+    }
+}
+
+// Drop idle buckets once there are many, and the least recently used ones if
+// that still leaves no room for another
+fn make_room(buckets: &mut HashMap<BucketKey, Bucket>, now: Instant) {
+    if buckets.len() > PRUNE_THRESHOLD {
+        buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < IDLE_BUCKET_TTL);
+    }
+
+    while buckets.len() >= MAX_BUCKETS {
+        let Some(oldest) = buckets
+            .iter()
+            .min_by_key(|(_, bucket)| bucket.last_refill)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        buckets.remove(&oldest);
+    }
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    // Retry-After is in whole seconds, round up so clients don't retry too early
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+
+    let mut response = error_response(
+        StatusCode::TOO_MANY_REQUESTS,
+        &format!("Rate limit exceeded, retry in {} seconds", seconds),
+    );
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::Service;
+
+    // Each test uses its own peer address, since the buckets are shared
+    async fn send(peer: [u8; 4], token: Option<&str>) -> Response {
+        let mut app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(limit_by_client));
+
+        let mut request = Request::builder().uri("/");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((peer, 40000))));
+        app.call(request).await.unwrap()
+    }
+
+    #[test]
+    fn empty_bucket_reports_when_the_next_request_fits() {
+        let limits = ApiLimits {
+            burst: 2,
+            ..ApiLimits::default()
+        };
+        let key = BucketKey::Token(-1);
+
+        assert!(take(key.clone(), 60, &limits).is_ok());
+        assert!(take(key.clone(), 60, &limits).is_ok());
+        let retry_after = take(key, 60, &limits).unwrap_err();
+        assert!(retry_after > Duration::from_millis(900) && retry_after <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retry_after_rounds_up_to_whole_seconds() {
+        let response = too_many_requests(Duration::from_millis(1200));
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_buckets_at_the_cap() {
+        let start = Instant::now();
+        let mut buckets: HashMap<BucketKey, Bucket> = (0..MAX_BUCKETS as i64)
+            .map(|id| {
+                let bucket = Bucket {
+                    tokens: 1.0,
+                    last_refill: start + Duration::from_millis(id as u64),
+                };
+                (BucketKey::Token(id), bucket)
+            })
+            .collect();
+
+        make_room(&mut buckets, start + Duration::from_secs(1));
+
+        assert_eq!(buckets.len(), MAX_BUCKETS - 1);
+        assert!(!buckets.contains_key(&BucketKey::Token(0)));
+        assert!(buckets.contains_key(&BucketKey::Token(1)));
+    }
+
+    #[tokio::test]
+    async fn limits_each_client_separately() {
+        let burst = ApiLimits::default().burst;
+        let peer = [127, 0, 0, 31];
+
+        for _ in 0..burst {
+            let response = send(peer, None).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let limited = send(peer, None).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(limited.headers().contains_key(header::RETRY_AFTER));
+
+        assert_eq!(send([127, 0, 0, 32], None).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rotating_tokens_does_not_escape_the_client_limit() {
+        let burst = ApiLimits::default().burst;
+        let peer = [127, 0, 0, 33];
+
+        for attempt in 0..burst {
+            let token = format!("fake-{}", attempt);
+            assert_eq!(send(peer, Some(&token)).await.status(), StatusCode::OK);
+        }
+        let limited = send(peer, Some("fake-next")).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
@@ -0,0 +1,96 @@
+// Data access for notes and states. The Tauri commands, and through them the
+// HTTP server and MCP tools, read and write through these traits and only add
+// responses and change events on top. `SqliteRepository` works on any
+// connection, so an in-memory database with the migrations applied can stand
+// in for the app's.
+
+mod sqlite;
+
+pub use sqlite::SqliteRepository;
+
+use crate::models::*;
+
+/// Notes found by a search, best match first.
+pub struct SearchResults {
+    pub notes: Vec<Note>,
+    /// "Did you mean" query, when words of the query aren't in any note
+    pub suggestion: Option<String>,
+}
+
+/// One page of the note list.
+pub struct NotesPage {
+    pub notes: Vec<Note>,
+    /// Notes in the whole list, not just this page
+    pub total_count: i64,
+    /// Where the next page starts, or `None` after the last page
+    pub next_cursor: Option<String>,
+}
+
+/// What an update with an optional expected version came to.
+pub enum UpdateOutcome<T> {
+    Updated(T),
+    NotFound,
+    /// The row isn't at the expected version any more; holds it as it is now
+    Conflict(T),
+}
+
+pub trait NoteRepository {
+    fn get_note(&self, id: i64) -> Result<Option<Note>, String>;
+
+    /// All notes by order, newest first within the same order.
+    fn list_notes(&self) -> Result<Vec<Note>, String>;
+
+    /// Up to `limit` notes of the list, starting after `cursor`, the previous
+    /// page's `next_cursor`. Notes are read with an empty `content` unless
+    /// `with_content`.
+    fn list_notes_page(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+        with_content: bool,
+    ) -> Result<NotesPage, String>;
+
+    /// Full-text search ranked with `weights`, topped up with typo-tolerant
+    /// matches when the first page finds few notes and `request.fuzzy` asks.
+    fn search_notes(
+        &self,
+        request: &SearchRequest,
+        weights: &SearchWeights,
+    ) -> Result<SearchResults, String>;
+
+    fn create_note(&self, request: &CreateNoteRequest) -> Result<Note, String>;
+
+    /// Update the note, unless `request.expected_version` is given and the
+    /// note has moved past it.
+    fn update_note(&self, request: &UpdateNoteRequest) -> Result<UpdateOutcome<Note>, String>;
+
+    fn set_note_done(&self, id: i64, done: bool) -> Result<Option<Note>, String>;
+
+    fn reorder_note(&self, request: &ReorderNoteRequest) -> Result<Option<Note>, String>;
+
+    /// The deleted note, or `None` if there was none.
+    fn delete_note(&self, id: i64) -> Result<Option<Note>, String>;
+}
+
+pub trait ChangeRepository {
+    /// Up to `limit` changes to notes and states made after `since_seq`,
+    /// oldest first.
+    fn changes_since(&self, since_seq: i64, limit: usize) -> Result<Changes, String>;
+}
+
+pub trait StateRepository {
+    /// All states by position.
+    fn list_states(&self) -> Result<Vec<State>, String>;
+
+    fn get_state(&self, id: i64) -> Result<Option<State>, String>;
+
+    fn create_state(&self, request: &CreateStateRequest) -> Result<State, String>;
+
+    /// Update the state, unless `request.expected_version` is given and the
+    /// state has moved past it.
+    fn update_state(&self, request: &UpdateStateRequest)
+        -> Result<UpdateOutcome<State>, String>;
+
+    /// The deleted state, or `None` if there was none.
+    fn delete_state(&self, id: i64) -> Result<Option<State>, String>;
+}
@@ -0,0 +1,1174 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Row};
+
+use super::{
+    ChangeRepository, NoteRepository, NotesPage, SearchResults, StateRepository, UpdateOutcome,
+};
+use crate::fuzzy;
+use crate::models::*;
+
+// Columns `note_from_row` reads, in order
+const NOTE_COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "content",
+    "created_at",
+    "updated_at",
+    "priority",
+    "labels",
+    "deadline",
+    "reminder_minutes",
+    "done",
+    "state_id",
+    "\"order\"",
+    "section",
+    "change_seq",
+    "version",
+];
+
+// The note list's order. The ID breaks ties so that every note has its own
+// place in the list to resume paging from, and notes without a creation time
+// sort as created at 0, as their cursors say.
+const LIST_ORDER: &str = "\"order\" ASC, IFNULL(created_at, 0) DESC, id DESC";
+
+const STATE_COLUMNS: &str =
+    "id, name, position, color, created_at, updated_at, change_seq, version";
+
+// Below this many full-text results on the first page, search falls back to
+// typo-tolerant matching
+const FEW_SEARCH_RESULTS: usize = 3;
+
+// Trigram matches scored against the query in the fuzzy fallback
+const FUZZY_CANDIDATES: i64 = 200;
+
+/// Notes and states stored in a SQLite database migrated to the current schema.
+pub struct SqliteRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> SqliteRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    fn query_notes(&self, query: &str, params: impl rusqlite::Params) -> Result<Vec<Note>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let rows = stmt
+            .query_map(params, note_from_row)
+            .map_err(|e| format!("Failed to query notes: {}", e))?;
+
+        let mut notes = Vec::new();
+        for note in rows {
+            notes.push(note.map_err(|e| format!("Failed to read note: {}", e))?);
+        }
+
+        Ok(notes)
+    }
+
+    /// The query with each word that isn't in the search index replaced by the
+    /// closest indexed term, or `None` if no word needed correcting.
+    fn suggest_query(&self, query: &str) -> Result<Option<String>, String> {
+        let mut corrected = Vec::new();
+        let mut changed = false;
+
+        for word in fuzzy::words(query) {
+            // Prefix search already covers words that start an indexed term
+            let known: bool = self
+                .conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM notes_fts_vocab WHERE term >= ?1 AND substr(term, 1, length(?1)) = ?1)",
+                    [&word],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to look up search term: {}", e))?;
+            if known {
+                corrected.push(word);
+                continue;
+            }
+
+            let (min_length, max_length) = fuzzy::term_length_range(&word);
+            let mut stmt = self
+                .conn
+                .prepare(
+                    "SELECT term, doc FROM notes_fts_vocab WHERE length(term) BETWEEN ?1 AND ?2",
+                )
+                .map_err(|e| format!("Failed to prepare term query: {}", e))?;
+            let terms = stmt
+                .query_map([min_length, max_length], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<(String, i64)>>>())
+                .map_err(|e| format!("Failed to read search terms: {}", e))?;
+
+            match fuzzy::closest_term(&word, &terms) {
+                Some(term) => {
+                    corrected.push(term);
+                    changed = true;
+                }
+                None => corrected.push(word),
+            }
+        }
+
+        Ok(changed.then(|| corrected.join(" ")))
+    }
+
+    /// The notes with these ids, in the same order; missing ids are skipped.
+    fn notes_by_id(&self, ids: &[i64]) -> Result<Vec<Note>, String> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "SELECT {} FROM notes WHERE id IN ({})",
+            note_columns(None),
+            vec!["?"; ids.len()].join(", ")
+        );
+        let mut notes = self.query_notes(&query, rusqlite::params_from_iter(ids))?;
+        notes.sort_by_key(|note| ids.iter().position(|id| note.id == Some(*id)));
+
+        Ok(notes)
+    }
+
+    /// IDs of notes whose title or labels match the query allowing for typos,
+    /// best match first.
+    fn fuzzy_matches(&self, query: &str) -> Result<Vec<i64>, String> {
+        let Some(trigram_query) = fuzzy::trigram_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT n.id, n.title, n.labels FROM notes_trigram t
+                 JOIN notes n ON n.id = t.rowid
+                 WHERE notes_trigram MATCH ?1
+                 ORDER BY t.rank LIMIT ?2",
+            )
+            .map_err(|e| format!("Failed to prepare fuzzy search: {}", e))?;
+        let candidates = stmt
+            .query_map(rusqlite::params![trigram_query, FUZZY_CANDIDATES], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to execute fuzzy search: {}", e))?;
+
+        let query_words = fuzzy::words(query);
+        let mut scored: Vec<(f64, i64)> = candidates
+            .into_iter()
+            .filter_map(|(id, title, labels)| {
+                fuzzy::match_score(&query_words, &format!("{} {}", title, labels))
+                    .map(|score| (score, id))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(scored.into_iter().map(|(_, id)| id).collect())
+    }
+}
+
+impl NoteRepository for SqliteRepository<'_> {
+    fn get_note(&self, id: i64) -> Result<Option<Note>, String> {
+        let query = format!("SELECT {} FROM notes WHERE id = ?", note_columns(None));
+        Ok(self.query_notes(&query, [id])?.pop())
+    }
+
+    fn list_notes(&self) -> Result<Vec<Note>, String> {
+        let query = format!(
+            "SELECT {} FROM notes ORDER BY {}",
+            note_columns(None),
+            LIST_ORDER
+        );
+        self.query_notes(&query, [])
+    }
+
+    fn list_notes_page(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+        with_content: bool,
+    ) -> Result<NotesPage, String> {
+        // Content is the only column big enough to be worth leaving out
+        let columns = NOTE_COLUMNS
+            .iter()
+            .map(|&column| match column {
+                "content" if !with_content => "'' AS content",
+                column => column,
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // One note more than the page holds shows whether there's a next page
+        let mut notes = match cursor {
+            Some(cursor) => {
+                let (order, created_at, id) = parse_cursor(cursor)?;
+                let query = format!(
+                    "SELECT {} FROM notes
+                     WHERE \"order\" > ?1
+                        OR (\"order\" = ?1 AND (IFNULL(created_at, 0) < ?2
+                            OR (IFNULL(created_at, 0) = ?2 AND id < ?3)))
+                     ORDER BY {} LIMIT ?4",
+                    columns, LIST_ORDER
+                );
+                self.query_notes(
+                    &query,
+                    rusqlite::params![order, created_at, id, limit as i64 + 1],
+                )?
+            }
+            None => {
+                let query = format!(
+                    "SELECT {} FROM notes ORDER BY {} LIMIT ?",
+                    columns, LIST_ORDER
+                );
+                self.query_notes(&query, [limit as i64 + 1])?
+            }
+        };
+
+        let next_cursor = if notes.len() > limit {
+            notes.truncate(limit);
+            notes.last().map(note_cursor)
+        } else {
+            None
+        };
+        let total_count = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count notes: {}", e))?;
+
+        Ok(NotesPage {
+            notes,
+            total_count,
+            next_cursor,
+        })
+    }
+
+    fn search_notes(
+        &self,
+        request: &SearchRequest,
+        weights: &SearchWeights,
+    ) -> Result<SearchResults, String> {
+        let limit = request.limit.unwrap_or(50).min(1000); // Cap at 1000
+        let offset = request.offset.unwrap_or(0);
+
+        let mut notes = if request.query.is_empty() {
+            let query = format!(
+                "SELECT {} FROM notes ORDER BY {} LIMIT {} OFFSET {}",
+                note_columns(None),
+                LIST_ORDER,
+                limit,
+                offset
+            );
+            self.query_notes(&query, [])?
+        } else {
+            let (query, fts_query) =
+                fts_search_query(&request.query, weights, limit as i64, offset as i64);
+            self.query_notes(&query, [fts_query])?
+        };
+
+        // Typos and partial words find little with FTS, so when the first page
+        // comes back nearly empty, add notes found with a corrected query and
+        // notes whose title or labels are close to the query
+        let mut suggestion = None;
+        let limit = limit.max(0) as usize;
+        let fuzzy = request.fuzzy.unwrap_or(false) && !request.query.trim().is_empty();
+        if fuzzy && offset == 0 && notes.len() < FEW_SEARCH_RESULTS.min(limit) {
+            suggestion = self.suggest_query(&request.query)?;
+
+            let mut extra = Vec::new();
+            if let Some(suggested) = &suggestion {
+                let (query, fts_query) = fts_search_query(suggested, weights, limit as i64, 0);
+                extra.extend(self.query_notes(&query, [fts_query])?);
+            }
+            let matches: Vec<i64> = self
+                .fuzzy_matches(&request.query)?
+                .into_iter()
+                .filter(|id| !notes.iter().any(|found| found.id == Some(*id)))
+                .take(limit)
+                .collect();
+            extra.extend(self.notes_by_id(&matches)?);
+
+            for note in extra {
+                if notes.len() >= limit {
+                    break;
+                }
+                if !notes.iter().any(|found| found.id == note.id) {
+                    notes.push(note);
+                }
+            }
+        }
+
+        Ok(SearchResults { notes, suggestion })
+    }
+
+    fn create_note(&self, request: &CreateNoteRequest) -> Result<Note, String> {
+        let labels_json = serde_json::to_string(request.labels.as_deref().unwrap_or_default())
+            .map_err(|e| format!("Failed to serialize labels: {}", e))?;
+
+        let now = Utc::now().timestamp();
+
+        self.conn.execute(
+            "INSERT INTO notes (title, content, created_at, updated_at, priority, labels, deadline, reminder_minutes, done, state_id, \"order\", section)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                request.title,
+                request.content,
+                now,
+                now,
+                request.priority.unwrap_or(0),
+                labels_json,
+                request.deadline.map(|dt| dt.timestamp()),
+                request.reminder_minutes.unwrap_or(0),
+                request.done.unwrap_or(false) as i32,
+                request.state_id,
+                request.order.unwrap_or(0),
+                request.section.as_deref().unwrap_or("unset")
+            ],
+        ).map_err(|e| format!("Failed to create note: {}", e))?;
+
+        self.get_note(self.conn.last_insert_rowid())?
+            .ok_or_else(|| "Created note not found".to_string())
+    }
+
+    fn update_note(&self, request: &UpdateNoteRequest) -> Result<UpdateOutcome<Note>, String> {
+        // Build dynamic update query
+        let mut set_parts = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(title) = &request.title {
+            set_parts.push("title = ?");
+            params.push(Box::new(title.clone()));
+        }
+        if let Some(content) = &request.content {
+            set_parts.push("content = ?");
+            params.push(Box::new(content.clone()));
+        }
+        if let Some(priority) = request.priority {
+            set_parts.push("priority = ?");
+            params.push(Box::new(priority));
+        }
+        if let Some(labels) = &request.labels {
+            let labels_json = serde_json::to_string(labels)
+                .map_err(|e| format!("Failed to serialize labels: {}", e))?;
+            set_parts.push("labels = ?");
+            params.push(Box::new(labels_json));
+        }
+        if let Some(deadline) = &request.deadline {
+            set_parts.push("deadline = ?");
+            params.push(Box::new(deadline.timestamp()));
+        }
+        if let Some(reminder_minutes) = request.reminder_minutes {
+            set_parts.push("reminder_minutes = ?");
+            params.push(Box::new(reminder_minutes));
+        }
+        if let Some(done) = request.done {
+            set_parts.push("done = ?");
+            params.push(Box::new(done as i32));
+        }
+        if let Some(state_id) = request.state_id {
+            set_parts.push("state_id = ?");
+            params.push(Box::new(state_id));
+        }
+        if let Some(order) = request.order {
+            set_parts.push("\"order\" = ?");
+            params.push(Box::new(order));
+        }
+        if let Some(section) = &request.section {
+            set_parts.push("section = ?");
+            params.push(Box::new(section.clone()));
+        }
+
+        set_parts.push("updated_at = ?");
+        params.push(Box::new(Utc::now().timestamp()));
+
+        let mut query = format!("UPDATE notes SET {} WHERE id = ?", set_parts.join(", "));
+        params.push(Box::new(request.id));
+        if let Some(expected_version) = request.expected_version {
+            query.push_str(" AND version = ?");
+            params.push(Box::new(expected_version));
+        }
+
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rows_affected = self
+            .conn
+            .execute(&query, &param_refs[..])
+            .map_err(|e| format!("Failed to update note: {}", e))?;
+
+        Ok(update_outcome(rows_affected, self.get_note(request.id)?))
+    }
+
+    fn set_note_done(&self, id: i64, done: bool) -> Result<Option<Note>, String> {
+        self.conn
+            .execute(
+                "UPDATE notes SET done = ?, updated_at = ? WHERE id = ?",
+                rusqlite::params![done as i32, Utc::now().timestamp(), id],
+            )
+            .map_err(|e| format!("Failed to update note done status: {}", e))?;
+
+        self.get_note(id)
+    }
+
+    fn reorder_note(&self, request: &ReorderNoteRequest) -> Result<Option<Note>, String> {
+        let now = Utc::now().timestamp();
+
+        // Update the note's order and optionally its state
+        let result = match request.state_id {
+            Some(state_id) => self.conn.execute(
+                "UPDATE notes SET \"order\" = ?, state_id = ?, updated_at = ? WHERE id = ?",
+                rusqlite::params![request.new_order, state_id, now, request.note_id],
+            ),
+            None => self.conn.execute(
+                "UPDATE notes SET \"order\" = ?, updated_at = ? WHERE id = ?",
+                rusqlite::params![request.new_order, now, request.note_id],
+            ),
+        };
+        result.map_err(|e| format!("Failed to reorder note: {}", e))?;
+
+        self.get_note(request.note_id)
+    }
+
+    fn delete_note(&self, id: i64) -> Result<Option<Note>, String> {
+        let note = self.get_note(id)?;
+
+        self.conn
+            .execute("DELETE FROM notes WHERE id = ?", [id])
+            .map_err(|e| format!("Failed to delete note: {}", e))?;
+
+        Ok(note)
+    }
+}
+
+impl ChangeRepository for SqliteRepository<'_> {
+    fn changes_since(&self, since_seq: i64, limit: usize) -> Result<Changes, String> {
+        // Everything is read from one snapshot, so a change made meanwhile
+        // shows up whole in this call or the next
+        let _snapshot = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let current_seq: i64 = self
+            .conn
+            .query_row(
+                "SELECT value FROM change_sequence WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to read change sequence: {}", e))?;
+        if since_seq > current_seq {
+            return Err(format!(
+                "since_seq {} is ahead of the database's latest change {}; sync again from 0",
+                since_seq, current_seq
+            ));
+        }
+
+        // One change more than asked for shows whether more are waiting
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT 'note', id, change_seq, NULL, 0 FROM notes WHERE change_seq > ?1
+                 UNION ALL
+                 SELECT 'state', id, change_seq, NULL, 0 FROM states WHERE change_seq > ?1
+                 UNION ALL
+                 SELECT entity, entity_id, change_seq, deleted_at, 1 FROM tombstones WHERE change_seq > ?1
+                 ORDER BY 3 LIMIT ?2",
+            )
+            .map_err(|e| format!("Failed to prepare changes query: {}", e))?;
+        let mut changed = stmt
+            .query_map(rusqlite::params![since_seq, limit as i64 + 1], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, bool>(4)?,
+                ))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to query changes: {}", e))?;
+
+        let has_more = changed.len() > limit;
+        changed.truncate(limit);
+        let mut changes = Changes {
+            notes: Vec::new(),
+            states: Vec::new(),
+            deleted: Vec::new(),
+            latest_seq: match changed.last() {
+                Some((_, _, change_seq, _, _)) if has_more => *change_seq,
+                _ => current_seq,
+            },
+            has_more,
+        };
+
+        for (entity, id, change_seq, deleted_at, deleted) in changed {
+            let entity = match entity.as_str() {
+                "note" => ChangeEntity::Note,
+                "state" => ChangeEntity::State,
+                other => return Err(format!("Unknown tombstone entity '{}'", other)),
+            };
+            if deleted {
+                changes.deleted.push(Tombstone {
+                    entity,
+                    id,
+                    change_seq,
+                    deleted_at: deleted_at.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                });
+                continue;
+            }
+            match entity {
+                ChangeEntity::Note => changes.notes.extend(self.get_note(id)?),
+                ChangeEntity::State => changes.states.extend(self.get_state(id)?),
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+impl StateRepository for SqliteRepository<'_> {
+    fn list_states(&self) -> Result<Vec<State>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM states ORDER BY position ASC",
+                STATE_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map([], state_from_row)
+            .map_err(|e| format!("Failed to query states: {}", e))?;
+
+        let mut states = Vec::new();
+        for state in rows {
+            states.push(state.map_err(|e| format!("Failed to parse state: {}", e))?);
+        }
+
+        Ok(states)
+    }
+
+    fn get_state(&self, id: i64) -> Result<Option<State>, String> {
+        let state = self.conn.query_row(
+            &format!("SELECT {} FROM states WHERE id = ?", STATE_COLUMNS),
+            [id],
+            state_from_row,
+        );
+
+        match state {
+            Ok(state) => Ok(Some(state)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("Failed to get state: {}", e)),
+        }
+    }
+
+    fn create_state(&self, request: &CreateStateRequest) -> Result<State, String> {
+        let now = Utc::now().timestamp();
+
+        self.conn
+            .execute(
+                "INSERT INTO states (name, position, color, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                rusqlite::params![request.name, request.position, request.color, now, now],
+            )
+            .map_err(|e| format!("Failed to create state: {}", e))?;
+
+        self.get_state(self.conn.last_insert_rowid())?
+            .ok_or_else(|| "Created state not found".to_string())
+    }
+
+    fn update_state(&self, request: &UpdateStateRequest) -> Result<UpdateOutcome<State>, String> {
+        // Build dynamic update query
+        let mut set_parts = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(name) = &request.name {
+            set_parts.push("name = ?");
+            params.push(Box::new(name.clone()));
+        }
+        if let Some(position) = request.position {
+            set_parts.push("position = ?");
+            params.push(Box::new(position));
+        }
+        if let Some(color) = &request.color {
+            set_parts.push("color = ?");
+            params.push(Box::new(color.clone()));
+        }
+
+        if set_parts.is_empty() {
+            return Err("No fields to update".to_string());
+        }
+
+        set_parts.push("updated_at = ?");
+        params.push(Box::new(Utc::now().timestamp()));
+
+        let mut query = format!("UPDATE states SET {} WHERE id = ?", set_parts.join(", "));
+        params.push(Box::new(request.id));
+        if let Some(expected_version) = request.expected_version {
+            query.push_str(" AND version = ?");
+            params.push(Box::new(expected_version));
+        }
+
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rows_affected = self
+            .conn
+            .execute(&query, &param_refs[..])
+            .map_err(|e| format!("Failed to update state: {}", e))?;
+
+        Ok(update_outcome(rows_affected, self.get_state(request.id)?))
+    }
+
+    fn delete_state(&self, id: i64) -> Result<Option<State>, String> {
+        let Some(state) = self.get_state(id)? else {
+            return Ok(None);
+        };
+
+        let rows_affected = self
+            .conn
+            .execute("DELETE FROM states WHERE id = ?", [id])
+            .map_err(|e| format!("Failed to delete state: {}", e))?;
+
+        Ok((rows_affected > 0).then_some(state))
+    }
+}
+
+// `NOTE_COLUMNS` as a select list, qualified with `table` when it's joined
+fn note_columns(table: Option<&str>) -> String {
+    NOTE_COLUMNS
+        .iter()
+        .map(|column| match table {
+            Some(table) => format!("{}.{}", table, column),
+            None => column.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn note_from_row(row: &Row) -> rusqlite::Result<Note> {
+    let labels_json: String = row.get(6)?;
+
+    Ok(Note {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        content: row.get(2)?,
+        created_at: row
+            .get::<_, Option<i64>>(3)?
+            .map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+        updated_at: row
+            .get::<_, Option<i64>>(4)?
+            .map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+        priority: row.get(5)?,
+        labels: serde_json::from_str(&labels_json).unwrap_or_default(),
+        deadline: row
+            .get::<_, Option<i64>>(7)?
+            .map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+        reminder_minutes: row.get(8)?,
+        done: row.get::<_, i32>(9)? != 0,
+        state_id: row.get(10)?,
+        order: row.get(11)?,
+        section: row.get(12)?,
+        change_seq: row.get(13)?,
+        version: row.get(14)?,
+    })
+}
+
+// An update of an existing row only misses it when the version condition
+// doesn't hold
+fn update_outcome<T>(rows_affected: usize, current: Option<T>) -> UpdateOutcome<T> {
+    match current {
+        Some(current) if rows_affected == 0 => UpdateOutcome::Conflict(current),
+        Some(updated) => UpdateOutcome::Updated(updated),
+        None => UpdateOutcome::NotFound,
+    }
+}
+
+// Cursors hold the sort key of the last note on a page, hex-encoded so that
+// clients treat them as opaque. A missing creation time is 0, as in `LIST_ORDER`.
+fn note_cursor(note: &Note) -> String {
+    hex::encode(format!(
+        "{}:{}:{}",
+        note.order,
+        note.created_at
+            .map_or(0, |created_at| created_at.timestamp()),
+        note.id.unwrap_or_default()
+    ))
+}
+
+fn parse_cursor(cursor: &str) -> Result<(i32, i64, i64), String> {
+    let invalid = || format!("Invalid cursor: {}", cursor);
+
+    let key = hex::decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(invalid)?;
+    let mut parts = key.split(':');
+    let mut next = || parts.next().ok_or_else(invalid);
+    let order = next()?.parse().map_err(|_| invalid())?;
+    let created_at = next()?.parse().map_err(|_| invalid())?;
+    let id = next()?.parse().map_err(|_| invalid())?;
+
+    Ok((order, created_at, id))
+}
+
+fn state_from_row(row: &Row) -> rusqlite::Result<State> {
+    Ok(State {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        position: row.get(2)?,
+        color: row.get(3)?,
+        created_at: timestamp_column(row, 4)?,
+        updated_at: timestamp_column(row, 5)?,
+        change_seq: row.get(6)?,
+        version: row.get(7)?,
+    })
+}
+
+// States written before timestamps became integers hold RFC 3339 or unix
+// timestamps as text
+fn timestamp_column(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    if let Ok(ts) = row.get::<_, Option<i64>>(index) {
+        return Ok(ts.map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default()));
+    }
+
+    let text: String = row.get(index)?;
+    Ok(DateTime::parse_from_rfc3339(&text)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            text.parse::<i64>()
+                .ok()
+                .and_then(|ts| DateTime::from_timestamp(ts, 0))
+        }))
+}
+
+fn fts_search_query(
+    query: &str,
+    weights: &SearchWeights,
+    limit: i64,
+    offset: i64,
+) -> (String, String) {
+    // Weights follow the index's column order: title, content, labels, section, state
+    let sql = format!(
+        "SELECT {}
+         FROM notes n
+         JOIN notes_fts fts ON n.id = fts.rowid
+         WHERE fts.notes_fts MATCH ?
+         ORDER BY bm25(notes_fts, {}, {}, {}, {}, {}), n.\"order\" ASC, n.created_at DESC
+         LIMIT {} OFFSET {}",
+        note_columns(Some("n")),
+        weights.title,
+        weights.content,
+        weights.labels,
+        weights.section,
+        weights.state,
+        limit,
+        offset
+    );
+
+    (sql, enhance_search_query(query))
+}
+
+fn enhance_search_query(query: &str) -> String {
+    // Enhance the search query for better FTS results
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    if words.len() == 1 {
+        // Single word - use prefix search
+        format!("{}*", words[0])
+    } else {
+        // Multiple words - use AND operator
+        words
+            .iter()
+            .map(|word| format!("{}*", word))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::run_migrations;
+
+    fn migrated_connection() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn note_request(title: &str, content: &str, order: i32) -> CreateNoteRequest {
+        CreateNoteRequest {
+            title: title.to_string(),
+            content: content.to_string(),
+            priority: None,
+            labels: Some(vec!["home".to_string()]),
+            deadline: None,
+            reminder_minutes: None,
+            done: None,
+            state_id: None,
+            order: Some(order),
+            section: None,
+        }
+    }
+
+    fn note_update(id: i64) -> UpdateNoteRequest {
+        UpdateNoteRequest {
+            id,
+            title: None,
+            content: None,
+            priority: None,
+            labels: None,
+            deadline: None,
+            reminder_minutes: None,
+            done: None,
+            state_id: None,
+            order: None,
+            section: None,
+            expected_version: None,
+        }
+    }
+
+    fn search(query: &str, fuzzy: bool) -> SearchRequest {
+        SearchRequest {
+            query: query.to_string(),
+            limit: None,
+            offset: None,
+            fuzzy: Some(fuzzy),
+        }
+    }
+
+    #[test]
+    fn creates_reads_updates_and_deletes_a_note() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+
+        let created = notes
+            .create_note(&note_request("Shopping", "Milk", 0))
+            .unwrap();
+        let id = created.id.unwrap();
+        assert_eq!(created.labels, ["home"]);
+        assert_eq!(created.section, "unset");
+        assert_eq!(created.version, 1);
+        assert_eq!(notes.get_note(id).unwrap().unwrap().title, "Shopping");
+
+        let update = UpdateNoteRequest {
+            content: Some("Milk and eggs".to_string()),
+            ..note_update(id)
+        };
+        let UpdateOutcome::Updated(updated) = notes.update_note(&update).unwrap() else {
+            panic!("note wasn't updated");
+        };
+        assert_eq!(updated.title, "Shopping");
+        assert_eq!(updated.content, "Milk and eggs");
+        assert_eq!(updated.version, 2);
+
+        // The note has moved past version 1
+        let stale = UpdateNoteRequest {
+            title: Some("Groceries".to_string()),
+            expected_version: Some(1),
+            ..note_update(id)
+        };
+        let UpdateOutcome::Conflict(current) = notes.update_note(&stale).unwrap() else {
+            panic!("stale update wasn't rejected");
+        };
+        assert_eq!(current.title, "Shopping");
+
+        let deleted = notes.delete_note(id).unwrap().unwrap();
+        assert_eq!(deleted.id, Some(id));
+        assert!(notes.get_note(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_notes_are_not_found() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+
+        assert!(notes.get_note(42).unwrap().is_none());
+        assert!(matches!(
+            notes.update_note(&note_update(42)).unwrap(),
+            UpdateOutcome::NotFound
+        ));
+        assert!(notes.delete_note(42).unwrap().is_none());
+    }
+
+    #[test]
+    fn lists_notes_by_order_then_newest_first() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+
+        let later = notes.create_note(&note_request("Later", "", 2)).unwrap();
+        let first = notes.create_note(&note_request("First", "", 1)).unwrap();
+        let second = notes.create_note(&note_request("Second", "", 1)).unwrap();
+
+        let ids: Vec<Option<i64>> = notes
+            .list_notes()
+            .unwrap()
+            .iter()
+            .map(|note| note.id)
+            .collect();
+        // Same order, so the newer note comes first
+        assert_eq!(ids, [second.id, first.id, later.id]);
+    }
+
+    #[test]
+    fn pages_through_every_note() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+
+        for (title, order) in [("a", 1), ("b", 0), ("c", 1), ("d", 0), ("e", 1)] {
+            notes.create_note(&note_request(title, "", order)).unwrap();
+        }
+        // Notes from before creation times were always set
+        conn.execute(
+            "UPDATE notes SET created_at = NULL WHERE title IN ('b', 'c')",
+            [],
+        )
+        .unwrap();
+
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = notes.list_notes_page(cursor.as_deref(), 2, false).unwrap();
+            assert_eq!(page.total_count, 5);
+            assert!(page.notes.iter().all(|note| note.content.is_empty()));
+            paged.extend(page.notes.into_iter().map(|note| note.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let listed: Vec<Option<i64>> = notes
+            .list_notes()
+            .unwrap()
+            .iter()
+            .map(|note| note.id)
+            .collect();
+        assert_eq!(paged, listed);
+    }
+
+    #[test]
+    fn fetches_notes_by_id_in_the_given_order() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+        let first = notes.create_note(&note_request("First", "", 0)).unwrap();
+        let second = notes.create_note(&note_request("Second", "", 1)).unwrap();
+
+        let fetched = notes
+            .notes_by_id(&[second.id.unwrap(), 999, first.id.unwrap()])
+            .unwrap();
+
+        let titles: Vec<&str> = fetched.iter().map(|note| note.title.as_str()).collect();
+        assert_eq!(titles, ["Second", "First"]);
+        assert!(notes.notes_by_id(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn searches_with_and_without_the_fuzzy_fallback() {
+        let conn = migrated_connection();
+        let notes = SqliteRepository::new(&conn);
+        let weights = SearchWeights::default();
+
+        let groceries = notes
+            .create_note(&note_request("Buy groceries", "Milk and eggs", 0))
+            .unwrap();
+        notes
+            .create_note(&note_request("Call the plumber", "Kitchen sink", 0))
+            .unwrap();
+
+        let found = notes.search_notes(&search("eggs", true), &weights).unwrap();
+        assert_eq!(found.notes.len(), 1);
+        assert_eq!(found.notes[0].id, groceries.id);
+        assert!(found.suggestion.is_none());
+
+        let exact_only = notes
+            .search_notes(&search("grocerys", false), &weights)
+            .unwrap();
+        assert!(exact_only.notes.is_empty());
+        assert!(exact_only.suggestion.is_none());
+
+        // Off unless asked for
+        let default = SearchRequest {
+            fuzzy: None,
+            ..search("grocerys", true)
+        };
+        assert!(notes
+            .search_notes(&default, &weights)
+            .unwrap()
+            .notes
+            .is_empty());
+
+        let fuzzy = notes
+            .search_notes(&search("grocerys", true), &weights)
+            .unwrap();
+        assert_eq!(fuzzy.notes.len(), 1);
+        assert_eq!(fuzzy.notes[0].id, groceries.id);
+        assert_eq!(fuzzy.suggestion.as_deref(), Some("groceries"));
+    }
+
+    #[test]
+    fn creates_reads_updates_and_deletes_a_state() {
+        let conn = migrated_connection();
+        let states = SqliteRepository::new(&conn);
+        let existing = states.list_states().unwrap().len();
+
+        let created = states
+            .create_state(&CreateStateRequest {
+                name: "Blocked".to_string(),
+                position: 99,
+                color: Some("#ff0000".to_string()),
+            })
+            .unwrap();
+        let id = created.id.unwrap();
+        assert_eq!(states.get_state(id).unwrap().unwrap().name, "Blocked");
+        assert_eq!(states.list_states().unwrap().len(), existing + 1);
+
+        let update = UpdateStateRequest {
+            id,
+            name: Some("Waiting".to_string()),
+            position: None,
+            color: None,
+            expected_version: Some(created.version),
+        };
+        let UpdateOutcome::Updated(updated) = states.update_state(&update).unwrap() else {
+            panic!("state wasn't updated");
+        };
+        assert_eq!(updated.name, "Waiting");
+        assert_eq!(updated.color.as_deref(), Some("#ff0000"));
+        assert!(matches!(
+            states.update_state(&update).unwrap(),
+            UpdateOutcome::Conflict(_)
+        ));
+
+        assert_eq!(states.delete_state(id).unwrap().unwrap().name, "Waiting");
+        assert!(states.get_state(id).unwrap().is_none());
+        assert!(states.delete_state(id).unwrap().is_none());
+    }
+
+    fn latest_seq(changes: &impl ChangeRepository) -> i64 {
+        changes.changes_since(0, 1000).unwrap().latest_seq
+    }
+
+    #[test]
+    fn change_seq_goes_up_with_every_note_and_state_write() {
+        let conn = migrated_connection();
+        let repository = SqliteRepository::new(&conn);
+        let start = latest_seq(&repository);
+
+        let note = repository
+            .create_note(&note_request("Sync me", "", 0))
+            .unwrap();
+        assert!(note.change_seq > start);
+
+        let UpdateOutcome::Updated(updated) = repository
+            .update_note(&UpdateNoteRequest {
+                title: Some("Synced".to_string()),
+                ..note_update(note.id.unwrap())
+            })
+            .unwrap()
+        else {
+            panic!("note not updated");
+        };
+        assert!(updated.change_seq > note.change_seq);
+
+        let state = repository
+            .create_state(&CreateStateRequest {
+                name: "Review".to_string(),
+                position: 50,
+                color: None,
+            })
+            .unwrap();
+        assert!(state.change_seq > updated.change_seq);
+
+        let UpdateOutcome::Updated(renamed) = repository
+            .update_state(&UpdateStateRequest {
+                id: state.id.unwrap(),
+                name: Some("In review".to_string()),
+                position: None,
+                color: None,
+                expected_version: None,
+            })
+            .unwrap()
+        else {
+            panic!("state not updated");
+        };
+        assert!(renamed.change_seq > state.change_seq);
+        assert_eq!(latest_seq(&repository), renamed.change_seq);
+    }
+
+    #[test]
+    fn deletes_show_up_as_tombstones() {
+        let conn = migrated_connection();
+        let repository = SqliteRepository::new(&conn);
+        let note = repository
+            .create_note(&note_request("Short lived", "", 0))
+            .unwrap();
+        let state = repository
+            .create_state(&CreateStateRequest {
+                name: "Temporary".to_string(),
+                position: 60,
+                color: None,
+            })
+            .unwrap();
+        let since = latest_seq(&repository);
+
+        repository.delete_note(note.id.unwrap()).unwrap();
+        repository.delete_state(state.id.unwrap()).unwrap();
+
+        let changes = repository.changes_since(since, 100).unwrap();
+        assert!(changes.notes.is_empty());
+        assert!(changes.states.is_empty());
+        let deleted: Vec<(ChangeEntity, i64)> = changes
+            .deleted
+            .iter()
+            .map(|tombstone| (tombstone.entity, tombstone.id))
+            .collect();
+        assert_eq!(
+            deleted,
+            [
+                (ChangeEntity::Note, note.id.unwrap()),
+                (ChangeEntity::State, state.id.unwrap())
+            ]
+        );
+        assert!(changes.deleted[0].change_seq > since);
+        assert!(changes.deleted[0].deleted_at.is_some());
+    }
+
+    #[test]
+    fn a_cursor_returns_only_newer_changes() {
+        let conn = migrated_connection();
+        let repository = SqliteRepository::new(&conn);
+        let start = latest_seq(&repository);
+
+        let first = repository
+            .create_note(&note_request("First", "", 0))
+            .unwrap();
+        let second = repository
+            .create_note(&note_request("Second", "", 0))
+            .unwrap();
+
+        // A page of one says more is waiting, and its cursor picks up after it
+        let page = repository.changes_since(start, 1).unwrap();
+        assert_eq!(page.notes.len(), 1);
+        assert_eq!(page.notes[0].id, first.id);
+        assert!(page.has_more);
+        assert_eq!(page.latest_seq, first.change_seq);
+
+        let rest = repository.changes_since(page.latest_seq, 100).unwrap();
+        assert_eq!(rest.notes.len(), 1);
+        assert_eq!(rest.notes[0].id, second.id);
+        assert!(!rest.has_more);
+
+        let none = repository.changes_since(rest.latest_seq, 100).unwrap();
+        assert!(none.notes.is_empty() && none.states.is_empty() && none.deleted.is_empty());
+        assert_eq!(none.latest_seq, rest.latest_seq);
+
+        let error = repository
+            .changes_since(rest.latest_seq + 1, 100)
+            .unwrap_err();
+        assert!(error.contains("sync again from 0"), "{}", error);
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+use crate::database::get_database_path;
+use crate::models::AppSettings;
+
+static SETTINGS: OnceLock<RwLock<AppSettings>> = OnceLock::new();
+
+fn settings_lock() -> &'static RwLock<AppSettings> {
+    SETTINGS.get_or_init(|| RwLock::new(load_settings()))
+}
+
+/// The current settings, read from disk the first time they're needed.
+pub fn current() -> AppSettings {
+    settings_lock().read().unwrap().clone()
+}
+
+/// Apply `update` to the current settings and persist the result.
+pub fn update(update: impl FnOnce(&mut AppSettings)) -> Result<AppSettings, String> {
+    let mut settings = settings_lock().write().unwrap();
+
+    let mut updated = settings.clone();
+    update(&mut updated);
+
+    let json = serde_json::to_string_pretty(&updated)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    std::fs::write(get_settings_path(), json)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    *settings = updated.clone();
+    Ok(updated)
+}
+
+fn load_settings() -> AppSettings {
+    let path = get_settings_path();
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid settings file {}: {}", path.display(), e);
+            AppSettings::default()
+        }),
+        Err(_) => AppSettings::default(),
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    // Kept next to the database so dev and release builds don't share settings
+    PathBuf::from(get_database_path()).with_file_name("juan-note-settings.json")
+}
@@ -0,0 +1,378 @@
+use crate::commands::webhook_commands::{get_webhooks_for_event, record_webhook_delivery};
+use crate::events;
+use crate::models::{ChangeEvent, Webhook, WebhookDelivery};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde_json::json;
+use sha2::Sha256;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+pub const EVENT_HEADER: &str = "X-Juan-Note-Event";
+pub const DELIVERY_HEADER: &str = "X-Juan-Note-Delivery";
+// `sha256=<hex HMAC of the raw request body>`, keyed with the webhook secret
+pub const SIGNATURE_HEADER: &str = "X-Juan-Note-Signature";
+
+const MAX_ATTEMPTS: i32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn get_http_client() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("juan-note-webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build webhook HTTP client")
+    })
+}
+
+/// Listen on the change-event bus and deliver every event to the webhooks subscribed to it.
+pub async fn run_delivery_worker() {
+    let mut receiver = events::subscribe();
+
+    loop {
+        match receiver.recv().await {
+            Ok(event) => dispatch(&event),
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!(
+                    "Webhook worker fell behind, {} events were not delivered",
+                    skipped
+                );
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+fn dispatch(event: &ChangeEvent) {
+    let event_name = event.name();
+
+    let webhooks = match get_webhooks_for_event(&event_name) {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            eprintln!("Failed to load webhooks for {}: {}", event_name, e);
+            return;
+        }
+    };
+
+    if webhooks.is_empty() {
+        return;
+    }
+
+    let payload = json!({
+        "event": event_name,
+        "entity": event.entity,
+        "id": event.id,
+        "operation": event.operation,
+        "data": event.data,
+        "timestamp": event.timestamp,
+    })
+    .to_string();
+
+    for webhook in webhooks {
+        tokio::spawn(deliver_with_retries(
+            webhook,
+            event_name.clone(),
+            payload.clone(),
+        ));
+    }
+}
+
+/// POST `payload` to the webhook, retrying with exponential backoff until it succeeds
+/// or `MAX_ATTEMPTS` is reached. Every attempt is written to the delivery log.
+pub async fn deliver_with_retries(webhook: Webhook, event_name: String, payload: String) {
+    deliver(
+        &webhook,
+        &event_name,
+        &payload,
+        INITIAL_BACKOFF,
+        record_webhook_delivery,
+    )
+    .await
+}
+
+// `deliver_with_retries` with the first retry delay and the delivery log
+// passed in, so tests can retry at once and log to their own database
+async fn deliver<R>(
+    webhook: &Webhook,
+    event_name: &str,
+    payload: &str,
+    initial_backoff: Duration,
+    recorder: R,
+) where
+    R: Fn(&WebhookDelivery) -> Result<(), String> + Clone + Send + 'static,
+{
+    let delivery_id = generate_delivery_id();
+    let mut backoff = initial_backoff;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let delivery = send(webhook, event_name, &delivery_id, payload, attempt).await;
+        let retryable = !delivery.success && is_retryable(delivery.status_code);
+
+        record(&delivery, recorder.clone()).await;
+
+        if !retryable || attempt == MAX_ATTEMPTS {
+            return;
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Deliver a single `ping` event, used to check a webhook endpoint from the UI.
+pub async fn send_ping(webhook: &Webhook) -> WebhookDelivery {
+    let payload = json!({
+        "event": "ping",
+        "webhook_id": webhook.id,
+        "timestamp": Utc::now(),
+    })
+    .to_string();
+
+    let delivery = send(webhook, "ping", &generate_delivery_id(), &payload, 1).await;
+    record(&delivery, record_webhook_delivery).await;
+
+    delivery
+}
+
+// Add a delivery to the log. Writing waits for the database's write
+// connection, so it happens on the blocking thread pool.
+async fn record<R>(delivery: &WebhookDelivery, recorder: R)
+where
+    R: Fn(&WebhookDelivery) -> Result<(), String> + Send + 'static,
+{
+    let delivery = delivery.clone();
+    let result = tokio::task::spawn_blocking(move || recorder(&delivery))
+        .await
+        .unwrap_or_else(|e| Err(format!("Failed to record webhook delivery: {}", e)));
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+async fn send(
+    webhook: &Webhook,
+    event_name: &str,
+    delivery_id: &str,
+    payload: &str,
+    attempt: i32,
+) -> WebhookDelivery {
+    let mut delivery = WebhookDelivery {
+        id: None,
+        webhook_id: webhook.id.unwrap_or_default(),
+        delivery_id: delivery_id.to_string(),
+        event: event_name.to_string(),
+        payload: payload.to_string(),
+        attempt,
+        status_code: None,
+        success: false,
+        error: None,
+        created_at: Some(Utc::now()),
+    };
+
+    let result = get_http_client()
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, event_name)
+        .header(DELIVERY_HEADER, delivery_id)
+        .header(SIGNATURE_HEADER, sign_payload(&webhook.secret, payload))
+        .body(payload.to_string())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            delivery.status_code = Some(status.as_u16() as i32);
+            delivery.success = status.is_success();
+            if !status.is_success() {
+                delivery.error = Some(format!("Webhook responded with {}", status));
+            }
+        }
+        Err(e) => {
+            delivery.error = Some(format!("Failed to deliver webhook: {}", e));
+        }
+    }
+
+    delivery
+}
+
+pub fn sign_payload(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// Network errors, timeouts, rate limiting and server errors are worth retrying;
+// other client errors will fail the same way every time
+fn is_retryable(status_code: Option<i32>) -> bool {
+    match status_code {
+        None => true,
+        Some(code) => code == 408 || code == 429 || code >= 500,
+    }
+}
+
+fn generate_delivery_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::webhook_commands::insert_webhook_delivery;
+    use crate::database::run_migrations;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use rusqlite::Connection;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Receiver {
+        // Statuses to answer with, in order; 200 once they run out
+        statuses: Arc<Mutex<VecDeque<u16>>>,
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        receiver.requests.lock().unwrap().push((headers, body));
+        let status = receiver.statuses.lock().unwrap().pop_front().unwrap_or(200);
+        StatusCode::from_u16(status).unwrap()
+    }
+
+    async fn start_receiver(statuses: &[u16]) -> (Webhook, Receiver) {
+        let receiver = Receiver::default();
+        receiver.statuses.lock().unwrap().extend(statuses);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let webhook = Webhook {
+            id: Some(1),
+            url: format!("http://{}/hook", addr),
+            events: vec!["*".to_string()],
+            secret: "test-secret".to_string(),
+            enabled: true,
+            created_at: None,
+            updated_at: None,
+        };
+        (webhook, receiver)
+    }
+
+    // A migrated in-memory database holding webhook 1, for the delivery log
+    fn delivery_log() -> Arc<Mutex<Connection>> {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO webhooks (id, url, secret) VALUES (1, 'http://localhost/hook', 'test-secret')",
+            [],
+        )
+        .unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    async fn deliver_logged(
+        webhook: &Webhook,
+        event_name: &str,
+        payload: &str,
+    ) -> Vec<(i32, bool)> {
+        let log = delivery_log();
+        let recorder = {
+            let log = log.clone();
+            move |delivery: &WebhookDelivery| {
+                insert_webhook_delivery(&log.lock().unwrap(), delivery)
+            }
+        };
+        deliver(webhook, event_name, payload, Duration::ZERO, recorder).await;
+
+        let conn = log.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT attempt, success FROM webhook_deliveries ORDER BY id")
+            .unwrap();
+        let attempts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        attempts
+    }
+
+    #[tokio::test]
+    async fn signs_the_payload_with_the_webhook_secret() {
+        let (webhook, receiver) = start_receiver(&[]).await;
+
+        let payload = r#"{"event":"note.created"}"#.to_string();
+        deliver_logged(&webhook, "note.created", &payload).await;
+
+        let requests = receiver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (headers, body) = &requests[0];
+        assert_eq!(body, &payload);
+        assert_eq!(headers[EVENT_HEADER], "note.created");
+        assert_eq!(headers[DELIVERY_HEADER].len(), 32);
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"test-secret").unwrap();
+        mac.update(payload.as_bytes());
+        let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+        assert_eq!(headers[SIGNATURE_HEADER], expected.as_str());
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_until_delivered() {
+        let (webhook, receiver) = start_receiver(&[500, 503]).await;
+
+        let attempts = deliver_logged(&webhook, "note.updated", "{}").await;
+        assert_eq!(attempts, vec![(1, false), (2, false), (3, true)]);
+
+        // Every attempt of a delivery carries the same delivery ID
+        let requests = receiver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|(headers, _)| headers[DELIVERY_HEADER] == requests[0].0[DELIVERY_HEADER]));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (webhook, receiver) = start_receiver(&[400]).await;
+
+        let attempts = deliver_logged(&webhook, "note.deleted", "{}").await;
+
+        assert_eq!(attempts, vec![(1, false)]);
+        assert_eq!(receiver.requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt() {
+        let (webhook, receiver) = start_receiver(&[500; MAX_ATTEMPTS as usize]).await;
+
+        let attempts = deliver_logged(&webhook, "note.updated", "{}").await;
+
+        assert_eq!(attempts.len(), MAX_ATTEMPTS as usize);
+        assert!(attempts.iter().all(|(_, success)| !success));
+        assert_eq!(
+            receiver.requests.lock().unwrap().len(),
+            MAX_ATTEMPTS as usize
+        );
+    }
+}
@@ -1,6 +1,12 @@
 import { useState, useEffect, useCallback } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { NoteService } from "../services/noteService";
-import type { Note, CreateNoteRequest, UpdateNoteRequest } from "../types/note";
+import type {
+  Note,
+  CreateNoteRequest,
+  UpdateNoteRequest,
+  ChangeEvent,
+} from "../types/note";
 
 export interface UseNotesReturn {
   notes: Note[];
@@ -54,7 +60,11 @@ export function useNotes(activeSection?: string): UseNotesReturn {
     try {
       const response = await NoteService.createNote(request);
       if (response.success && response.data) {
-        setAllNotes(prev => [response.data!, ...prev]);
+        // The change event for this note may already have added it
+        setAllNotes(prev => [
+          response.data!,
+          ...prev.filter(note => note.id !== response.data!.id),
+        ]);
       } else {
         setError(response.error || "Failed to create note");
       }
@@ -144,7 +154,7 @@ export function useNotes(activeSection?: string): UseNotesReturn {
       setLoading(true);
       setError(null);
       try {
-        const response = await NoteService.searchNotes({ query, limit: 50 });
+        const response = await NoteService.searchNotes({ query, limit: 50, fuzzy: true });
         if (response.success) {
           setAllNotes(response.data);
         } else {
@@ -218,6 +228,37 @@ export function useNotes(activeSection?: string): UseNotesReturn {
     initialize();
   }, []);
 
+  // Apply changes made elsewhere (REST API, MCP agents) as they happen
+  useEffect(() => {
+    const unlistenChanges = listen<ChangeEvent>("change-event", event => {
+      const change = event.payload;
+      if (change.entity !== "note") return;
+
+      if (change.operation === "deleted") {
+        setAllNotes(prev => prev.filter(note => note.id !== change.id));
+        return;
+      }
+
+      const changed = change.data as Note | undefined;
+      if (!changed) return;
+      setAllNotes(prev =>
+        prev.some(note => note.id === change.id)
+          ? prev.map(note => (note.id === change.id ? changed : note))
+          : [changed, ...prev]
+      );
+    });
+
+    // Events were dropped, so the local copy can't be trusted anymore
+    const unlistenLagged = listen("change-event-lagged", () => {
+      refreshNotes();
+    });
+
+    return () => {
+      unlistenChanges.then(unlisten => unlisten());
+      unlistenLagged.then(unlisten => unlisten());
+    };
+  }, [refreshNotes]);
+
   return {
     notes,
     loading,
@@ -1,9 +1,11 @@
 import { useState, useEffect, useCallback } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { NoteService } from "../services/noteService";
 import type {
   State,
   CreateStateRequest,
   UpdateStateRequest,
+  ChangeEvent,
 } from "../types/note";
 
 export function useStates() {
@@ -48,11 +50,13 @@ export function useStates() {
       try {
         const response = await NoteService.createState(request);
         if (response.success && response.data) {
-          // Replace optimistic state with real one
+          // Replace optimistic state with real one (the change event may have added it already)
           setStates(prev =>
-            prev.map(state =>
-              state.id === optimisticState.id ? response.data! : state
-            )
+            prev
+              .filter(state => state.id !== response.data!.id)
+              .map(state =>
+                state.id === optimisticState.id ? response.data! : state
+              )
           );
           return response.data;
         } else {
@@ -297,6 +301,37 @@ export function useStates() {
     initializeStates();
   }, []);
 
+  // Apply state changes made elsewhere (REST API, MCP agents) as they happen
+  useEffect(() => {
+    const unlistenChanges = listen<ChangeEvent>("change-event", event => {
+      const change = event.payload;
+      if (change.entity !== "state") return;
+
+      if (change.operation === "deleted") {
+        setStates(prev => prev.filter(state => state.id !== change.id));
+        return;
+      }
+
+      const changed = change.data as State | undefined;
+      if (!changed) return;
+      setStates(prev =>
+        (prev.some(state => state.id === change.id)
+          ? prev.map(state => (state.id === change.id ? changed : state))
+          : [...prev, changed]
+        ).sort((a, b) => a.position - b.position)
+      );
+    });
+
+    const unlistenLagged = listen("change-event-lagged", () => {
+      loadStates();
+    });
+
+    return () => {
+      unlistenChanges.then(unlisten => unlisten());
+      unlistenLagged.then(unlisten => unlisten());
+    };
+  }, [loadStates]);
+
   return {
     states,
     loading,
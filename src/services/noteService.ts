@@ -4,7 +4,15 @@ import type {
   UpdateNoteRequest,
   NoteResponse,
   NotesListResponse,
+  ListNotesRequest,
+  NotesPageResponse,
   SearchRequest,
+  SearchNotesResponse,
+  SearchIndexResponse,
+  SemanticSearchRequest,
+  SimilarNotesRequest,
+  SemanticSearchResponse,
+  EmbeddingStatusResponse,
   DeleteNoteRequest,
   UpdateNoteDoneRequest,
   CreateStateRequest,
@@ -13,8 +21,13 @@ import type {
   StatesListResponse,
   BulkOperationResponse,
   ReorderNoteRequest,
+  ChangesResponse,
   McpScanResponse,
   McpFunctionQueryResponse,
+  McpProvidersResponse,
+  McpConfigChangesResponse,
+  McpTransport,
+  McpIntegrationsResponse,
 } from "../types/note";
 
 export class NoteService {
@@ -68,6 +81,20 @@ export class NoteService {
     }
   }
 
+  static async listNotes(request: ListNotesRequest = {}): Promise<NotesPageResponse> {
+    try {
+      return await invoke("list_notes", { request });
+    } catch (error) {
+      console.error("Failed to list notes:", error);
+      return {
+        success: false,
+        data: [],
+        total_count: 0,
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
   static async updateNote(request: UpdateNoteRequest): Promise<NoteResponse> {
     try {
       return await invoke("update_note", { request });
@@ -92,7 +119,9 @@ export class NoteService {
     }
   }
 
-  static async searchNotes(request: SearchRequest): Promise<NotesListResponse> {
+  static async searchNotes(
+    request: SearchRequest
+  ): Promise<SearchNotesResponse> {
     try {
       return await invoke("search_notes", { request });
     } catch (error) {
@@ -105,6 +134,84 @@ export class NoteService {
     }
   }
 
+  static async checkSearchIndex(): Promise<SearchIndexResponse> {
+    try {
+      return await invoke("check_search_index");
+    } catch (error) {
+      console.error("Failed to check search index:", error);
+      return {
+        success: false,
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async rebuildSearchIndex(): Promise<SearchIndexResponse> {
+    try {
+      return await invoke("rebuild_search_index");
+    } catch (error) {
+      console.error("Failed to rebuild search index:", error);
+      return {
+        success: false,
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async semanticSearch(
+    request: SemanticSearchRequest
+  ): Promise<SemanticSearchResponse> {
+    try {
+      return await invoke("semantic_search", { request });
+    } catch (error) {
+      console.error("Failed to run semantic search:", error);
+      return {
+        success: false,
+        data: [],
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async similarNotes(
+    request: SimilarNotesRequest
+  ): Promise<SemanticSearchResponse> {
+    try {
+      return await invoke("similar_notes", { request });
+    } catch (error) {
+      console.error("Failed to find similar notes:", error);
+      return {
+        success: false,
+        data: [],
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async getEmbeddingStatus(): Promise<EmbeddingStatusResponse> {
+    try {
+      return await invoke("get_embedding_status");
+    } catch (error) {
+      console.error("Failed to get embedding status:", error);
+      return {
+        success: false,
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async reindexEmbeddings(): Promise<EmbeddingStatusResponse> {
+    try {
+      return await invoke("reindex_embeddings");
+    } catch (error) {
+      console.error("Failed to reindex embeddings:", error);
+      return {
+        success: false,
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
   static async updateNoteDone(
     request: UpdateNoteDoneRequest
   ): Promise<NoteResponse> {
@@ -286,9 +393,21 @@ export class NoteService {
     }
   }
 
-  static async scanMcpConfigs(): Promise<McpScanResponse> {
+  static async getChanges(sinceSeq: number = 0): Promise<ChangesResponse> {
     try {
-      return await invoke("scan_mcp_configs");
+      return await invoke("get_changes", { sinceSeq });
+    } catch (error) {
+      console.error("Failed to get changes:", error);
+      return {
+        success: false,
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async scanMcpConfigs(discover?: boolean): Promise<McpScanResponse> {
+    try {
+      return await invoke("scan_mcp_configs", { discover });
     } catch (error) {
       console.error("Failed to scan MCP configs:", error);
       return {
@@ -310,9 +429,46 @@ export class NoteService {
     }
   }
 
-  static async addJuanNoteMcpServer(): Promise<McpScanResponse> {
+  static async getMcpProviders(): Promise<McpProvidersResponse> {
+    try {
+      return await invoke("get_mcp_providers");
+    } catch (error) {
+      console.error("Failed to get MCP clients:", error);
+      return {
+        success: false,
+        data: [],
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async previewMcpConfigChanges(
+    action: "add" | "remove",
+    providers?: string[],
+    transport?: McpTransport
+  ): Promise<McpConfigChangesResponse> {
     try {
-      return await invoke("add_juan_note_mcp_server");
+      return await invoke("preview_mcp_config_changes", {
+        action,
+        providers,
+        transport,
+      });
+    } catch (error) {
+      console.error("Failed to preview MCP config changes:", error);
+      return {
+        success: false,
+        data: [],
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async addJuanNoteMcpServer(
+    providers?: string[],
+    transport?: McpTransport
+  ): Promise<McpScanResponse> {
+    try {
+      return await invoke("add_juan_note_mcp_server", { providers, transport });
     } catch (error) {
       console.error("Failed to add Juan Note MCP server:", error);
       return {
@@ -322,9 +478,11 @@ export class NoteService {
     }
   }
 
-  static async removeJuanNoteMcpServer(): Promise<McpScanResponse> {
+  static async removeJuanNoteMcpServer(
+    providers?: string[]
+  ): Promise<McpScanResponse> {
     try {
-      return await invoke("remove_juan_note_mcp_server");
+      return await invoke("remove_juan_note_mcp_server", { providers });
     } catch (error) {
       console.error("Failed to remove Juan Note MCP server:", error);
       return {
@@ -334,6 +492,32 @@ export class NoteService {
     }
   }
 
+  static async verifyMcpIntegrations(): Promise<McpIntegrationsResponse> {
+    try {
+      return await invoke("verify_mcp_integrations");
+    } catch (error) {
+      console.error("Failed to verify MCP integrations:", error);
+      return {
+        success: false,
+        data: [],
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
+  static async repairMcpIntegrations(): Promise<McpIntegrationsResponse> {
+    try {
+      return await invoke("repair_mcp_integrations");
+    } catch (error) {
+      console.error("Failed to repair MCP integrations:", error);
+      return {
+        success: false,
+        data: [],
+        error: error instanceof Error ? error.message : "Unknown error",
+      };
+    }
+  }
+
   static async getMcpServerConfig(): Promise<string> {
     try {
       return await invoke("get_mcp_server_config");
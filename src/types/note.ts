@@ -5,6 +5,8 @@ export interface State {
   color?: string;
   created_at?: string;
   updated_at?: string;
+  change_seq?: number;
+  version?: number;
 }
 
 export interface Note {
@@ -21,6 +23,8 @@ export interface Note {
   state_id?: number;
   order: number;
   section: string;
+  change_seq?: number;
+  version?: number;
 }
 
 export interface CreateNoteRequest {
@@ -48,6 +52,8 @@ export interface UpdateNoteRequest {
   state_id?: number;
   order?: number;
   section?: string;
+  // Fails with a conflict, returning the current note, if it has changed since
+  expected_version?: number;
 }
 
 export interface NoteResponse {
@@ -62,10 +68,87 @@ export interface NotesListResponse {
   error?: string;
 }
 
+export interface ListNotesRequest {
+  limit?: number;
+  cursor?: string;
+  fields?: (keyof Note)[];
+}
+
+export interface NotesPageResponse {
+  success: boolean;
+  // Only the requested fields, plus id
+  data: Partial<Note>[];
+  total_count: number;
+  next_cursor?: string;
+  etag?: string;
+  error?: string;
+}
+
 export interface SearchRequest {
   query: string;
   limit?: number;
   offset?: number;
+  // Add typo-tolerant matches when few notes match (default false)
+  fuzzy?: boolean;
+}
+
+export interface SearchNotesResponse {
+  success: boolean;
+  data: Note[];
+  suggestion?: string;
+  error?: string;
+}
+
+export interface SearchIndexStatus {
+  healthy: boolean;
+  problem?: string;
+  rebuilt: boolean;
+  note_count: number;
+  duration_ms: number;
+}
+
+export interface SearchIndexResponse {
+  success: boolean;
+  data?: SearchIndexStatus;
+  error?: string;
+}
+
+export interface SemanticSearchRequest {
+  query: string;
+  limit?: number;
+  min_score?: number;
+}
+
+export interface SimilarNotesRequest {
+  id: number;
+  limit?: number;
+  min_score?: number;
+}
+
+export interface ScoredNote {
+  note: Note;
+  score: number;
+}
+
+export interface SemanticSearchResponse {
+  success: boolean;
+  data: ScoredNote[];
+  error?: string;
+}
+
+export interface EmbeddingStatus {
+  available: boolean;
+  model: string;
+  model_dir: string;
+  missing_files: string[];
+  embedded_notes: number;
+  total_notes: number;
+}
+
+export interface EmbeddingStatusResponse {
+  success: boolean;
+  data?: EmbeddingStatus;
+  error?: string;
 }
 
 export interface DeleteNoteRequest {
@@ -88,6 +171,8 @@ export interface UpdateStateRequest {
   name?: string;
   position?: number;
   color?: string;
+  // Fails with a conflict, returning the current state, if it has changed since
+  expected_version?: number;
 }
 
 export interface StateResponse {
@@ -146,6 +231,8 @@ export interface McpServerConfig {
   command: string;
   args?: string[];
   env?: any;
+  url?: string;
+  headers?: Record<string, string>;
 }
 
 export interface McpConfigResult {
@@ -153,11 +240,62 @@ export interface McpConfigResult {
   config_path: string;
   mcp_servers: McpServerConfig[];
   error?: string;
+  reason?: string;
+  duration_ms?: number;
 }
 
 export interface McpScanResponse {
   success: boolean;
   data?: McpConfigResult[];
+  errors?: string[];
+  error?: string;
+}
+
+export type McpTransport = "stdio" | "http";
+
+export interface McpProviderInfo {
+  id: string;
+  name: string;
+  config_path: string;
+  installed: boolean;
+  configured: boolean;
+}
+
+export interface McpProvidersResponse {
+  success: boolean;
+  data: McpProviderInfo[];
+  error?: string;
+}
+
+export interface McpConfigChange {
+  provider: string;
+  config_path: string;
+  changed: boolean;
+  diff: string;
+  error?: string;
+}
+
+export type McpIntegrationStatus = "healthy" | "stale" | "unreachable";
+
+export interface McpIntegrationCheck {
+  provider: string;
+  config_path: string;
+  transport: McpTransport;
+  status: McpIntegrationStatus;
+  problems: string[];
+  repaired: boolean;
+  backup_path?: string;
+}
+
+export interface McpIntegrationsResponse {
+  success: boolean;
+  data: McpIntegrationCheck[];
+  error?: string;
+}
+
+export interface McpConfigChangesResponse {
+  success: boolean;
+  data: McpConfigChange[];
   error?: string;
 }
 
@@ -174,10 +312,51 @@ export interface McpFunction {
   parameters: McpFunctionParameter[];
   server_name: string;
   server_provider: string;
+  input_schema?: any;
 }
 
 export interface McpFunctionQueryResponse {
   success: boolean;
   data?: McpFunction[];
+  errors?: string[];
+  error?: string;
+}
+
+export type ChangeEntity = "note" | "state";
+
+export type ChangeOperation =
+  | "created"
+  | "updated"
+  | "deleted"
+  | "state_changed";
+
+export interface ChangeEvent {
+  entity: ChangeEntity;
+  id: number;
+  operation: ChangeOperation;
+  data?: Note | State;
+  timestamp: string;
+}
+
+// Incremental Sync Types
+export interface Tombstone {
+  entity: ChangeEntity;
+  id: number;
+  change_seq: number;
+  deleted_at?: string;
+}
+
+export interface Changes {
+  notes: Note[];
+  states: State[];
+  deleted: Tombstone[];
+  // Pass as sinceSeq to get the changes after these
+  latest_seq: number;
+  has_more: boolean;
+}
+
+export interface ChangesResponse {
+  success: boolean;
+  data?: Changes;
   error?: string;
 }